@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use axum::response::{Html, IntoResponse};
+use axum::routing::{delete, get, post};
+use axum::Router;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::config::ServerConfig;
+use crate::model::AppState;
+use crate::openapi::ApiDoc;
+use crate::{auth, routes, session, telemetry};
+
+// Handler for serving the static index.html
+async fn serve_index() -> impl IntoResponse {
+    Html(include_str!("../static/index.html"))
+}
+async fn serve_test() -> impl IntoResponse {
+    Html("Hello world!")
+}
+
+/// Builds the complete application router. Kept separate from `main` so the
+/// router can be driven in-process, e.g. with `tower::ServiceExt::oneshot`.
+pub fn router(state: Arc<AppState>) -> Router {
+    // API routes spend OpenAI budget, so they're behind the API key and rate
+    // limits; the metrics reveal usage, so they are too
+    let api = Router::new()
+        .route("/api/search", post(routes::search_papers))
+        .route("/api/session", delete(routes::reset_session))
+        .route("/api/papers/{id}/similar", get(routes::similar_papers))
+        .route("/metrics", get(routes::metrics))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), auth::enforce));
+
+    Router::new()
+        .route("/", get(serve_index))
+        .route("/test", get(serve_test))
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi()))
+        .merge(api)
+        .route_layer(axum::middleware::from_fn(telemetry::track_metrics))
+        // layers run outside-in from the bottom: the request id is set first,
+        // so the trace span and the response both carry it
+        .layer(cors_layer(&state.config))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(TraceLayer::new_for_http().make_span_with(telemetry::make_request_span))
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+        .with_state(state)
+}
+
+fn cors_layer(config: &ServerConfig) -> CorsLayer {
+    let allow_origin = if config.cors_allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::from(Any)
+    } else {
+        AllowOrigin::list(
+            config
+                .cors_allowed_origins
+                .iter()
+                .filter_map(|origin| match origin.parse::<HeaderValue>() {
+                    Ok(origin) => Some(origin),
+                    Err(_) => {
+                        tracing::warn!("ignoring invalid CORS origin {origin:?}");
+                        None
+                    }
+                }),
+        )
+    };
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers(Any)
+        .expose_headers([
+            HeaderName::from_static(session::SESSION_HEADER),
+            HeaderName::from_static(telemetry::REQUEST_ID_HEADER),
+        ])
+}
@@ -31,16 +31,21 @@ impl<'a> ArxivParser<'a> {
             // so we can clear related variables and start anew
             b"entry" => {
                 self.in_entry = true;
-                self.current_paper = Some(Paper::new());
+                let mut paper = Paper::new();
+                paper.source = "arxiv".to_string();
+                self.current_paper = Some(paper);
                 self.current_authors.clear();
                 self.current_categories.clear();
             }
             // otherwise, change the parsing state
+            b"id" if self.in_entry => self.current_field = Some("id"),
             b"title" if self.in_entry => self.current_field = Some("title"),
             b"author" if self.in_entry => self.current_field = Some("author"),
             b"summary" if self.in_entry => self.current_field = Some("abstract"),
             b"link" if self.in_entry => self.current_field = Some("link"),
             b"category" if self.in_entry => self.current_field = Some("category"),
+            b"published" if self.in_entry => self.current_field = Some("published"),
+            b"arxiv:doi" if self.in_entry => self.current_field = Some("doi"),
             _ => (),
         };
     }
@@ -53,9 +58,12 @@ impl<'a> ArxivParser<'a> {
         // otherwise, attempt to get the text and fill in the relevant field
         let text = str::from_utf8(event.as_ref())?.to_owned();
         match self.current_field {
+            Some("id") => paper.id = format!("arxiv:{}", arxiv_id(&text)),
             Some("title") => paper.title = text,
             Some("author") => self.current_authors.push(text),
             Some("abstract") => paper.abstract_text = text,
+            Some("published") => paper.published = text,
+            Some("doi") => paper.doi = Some(text),
             _ => (),
         }
         Ok(())
@@ -108,7 +116,8 @@ impl<'a> ArxivParser<'a> {
             }
             // else, just change the currently parsed field to None
             // as there is now nothing to parse
-            b"title" | b"author" | b"summary" | b"link" | b"category" => {
+            b"id" | b"title" | b"author" | b"summary" | b"link" | b"category" | b"published"
+            | b"arxiv:doi" => {
                 self.current_field = None;
             }
             _ => (),
@@ -141,3 +150,12 @@ impl<'a> ArxivParser<'a> {
         Ok(self.papers.clone())
     }
 }
+
+// "http://arxiv.org/abs/1706.03762v7" -> "1706.03762", so every version of a paper shares an id
+fn arxiv_id(entry_id: &str) -> &str {
+    let id = entry_id.rsplit_once("/abs/").map_or(entry_id, |(_, id)| id);
+    match id.rsplit_once('v') {
+        Some((base, version)) if !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => id,
+    }
+}
@@ -5,8 +5,47 @@ use serde_json::json;
 use crate::arxiv_parser::ArxivParser;
 use crate::error::ArxivError;
 use crate::model::{ArxivSearchTool, Paper, SearchArgs};
+use crate::paper_source::PaperSource;
 
-const ARXIV_URL: &str = "http://export.arxiv.org/api/query";
+pub(crate) const ARXIV_URL: &str = "http://export.arxiv.org/api/query";
+
+impl ArxivSearchTool {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+}
+
+impl Default for ArxivSearchTool {
+    fn default() -> Self {
+        Self::new(ARXIV_URL)
+    }
+}
+
+impl PaperSource for ArxivSearchTool {
+    fn name(&self) -> &'static str {
+        "arxiv"
+    }
+
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<Paper>, ArxivError> {
+        let client = reqwest::Client::new();
+
+        let response = client
+            .get(&self.base_url)
+            .query(&[
+                ("search_query", format!("all:{}", query)),
+                ("start", 0.to_string()),
+                ("max_results", max_results.to_string()),
+            ])
+            .send()
+            .await?
+            .text()
+            .await?;
+
+        ArxivParser::new().parse_response(&response)
+    }
+}
 
 impl Tool for ArxivSearchTool {
     const NAME: &'static str = "search_arxiv";
@@ -36,21 +75,7 @@ impl Tool for ArxivSearchTool {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        let max_results = args.max_results.unwrap_or(5);
-        let client = reqwest::Client::new();
-
-        let response = client
-            .get(ARXIV_URL)
-            .query(&[
-                ("search_query", format!("all:{}", args.query)),
-                ("start", 0.to_string()),
-                ("max_results", max_results.to_string()),
-            ])
-            .send()
-            .await?
-            .text()
-            .await?;
-
-        Ok(ArxivParser::new().parse_response(&response)?)
+        let max_results = args.max_results.unwrap_or(5).max(1) as usize;
+        self.search(&args.query, max_results).await
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+
+use crate::config::ServerConfig;
+use crate::model::AppState;
+use crate::rate_limit::RateLimiter;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// API key checks and rate limits applied to the `/api` routes.
+pub(crate) struct AccessControl {
+    key_hashes: HashSet<String>,
+    per_key: RateLimiter,
+    per_ip: RateLimiter,
+}
+
+impl AccessControl {
+    pub(crate) fn new(config: &ServerConfig) -> Self {
+        Self {
+            key_hashes: config.api_key_hashes.clone(),
+            per_key: RateLimiter::per_minute(config.requests_per_minute_per_key),
+            per_ip: RateLimiter::per_minute(config.requests_per_minute_per_ip),
+        }
+    }
+
+    fn auth_enabled(&self) -> bool {
+        !self.key_hashes.is_empty()
+    }
+}
+
+/// SHA-256 hex digest of an API key, as stored in `API_KEY_HASHES`.
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+// accepts either `x-api-key: <key>` or `Authorization: Bearer <key>`
+fn api_key_from_headers(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| {
+            headers
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+        })
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+/// Middleware enforcing the per-IP limit, then the API key, then the per-key limit.
+///
+/// The client IP comes from `ConnectInfo`; requests without it (e.g. when the
+/// router is driven in-process) share a single `unknown` bucket.
+pub(crate) async fn enforce(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let access = &state.access;
+
+    let client_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    if let Err(retry_after) = access.per_ip.check(&client_ip) {
+        return too_many_requests(retry_after);
+    }
+
+    let key_hash = api_key_from_headers(request.headers()).map(hash_api_key);
+    if access.auth_enabled() {
+        let Some(key_hash) = key_hash.filter(|hash| access.key_hashes.contains(hash)) else {
+            return (StatusCode::UNAUTHORIZED, "Missing or invalid API key").into_response();
+        };
+        if let Err(retry_after) = access.per_key.check(&key_hash) {
+            return too_many_requests(retry_after);
+        }
+    }
+
+    next.run(request).await
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    // Retry-After is in whole seconds; round up so clients never retry too early
+    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, seconds.max(1).to_string())],
+        "Rate limit exceeded",
+    )
+        .into_response()
+}
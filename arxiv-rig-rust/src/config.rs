@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+use std::env;
+use std::path::PathBuf;
+
+use crate::arxiv_search_tool::ARXIV_URL;
+use crate::crossref_source::CROSSREF_URL;
+use crate::pubmed_source::PUBMED_URL;
+
+const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1:8003";
+const DEFAULT_REQUESTS_PER_MINUTE_PER_KEY: u32 = 60;
+const DEFAULT_REQUESTS_PER_MINUTE_PER_IP: u32 = 30;
+const DEFAULT_PAPER_INDEX_PATH: &str = "data/paper_index.json";
+const DEFAULT_QDRANT_URL: &str = "http://localhost:6334";
+
+/// Server settings, read from the environment (or `.env`).
+///
+/// | variable                  | meaning                                                        |
+/// |---------------------------|----------------------------------------------------------------|
+/// | `BIND_ADDRESS`            | address to listen on (default `127.0.0.1:8003`)                |
+/// | `API_KEY_HASHES`          | comma-separated SHA-256 hex digests of accepted API keys;      |
+/// |                           | authentication is disabled when empty                          |
+/// | `RATE_LIMIT_PER_KEY`      | API requests per minute per key (default 60, 0 disables)       |
+/// | `RATE_LIMIT_PER_IP`       | API requests per minute per client IP (default 30, 0 disables) |
+/// | `CORS_ALLOWED_ORIGINS`    | comma-separated origins allowed to call the API, or `*`;       |
+/// |                           | cross-origin requests are refused when empty                   |
+/// | `ARXIV_BASE_URL`          | arXiv query endpoint                                           |
+/// | `PUBMED_BASE_URL`         | NCBI E-utilities base URL                                      |
+/// | `CROSSREF_BASE_URL`       | Crossref REST API base URL                                     |
+/// | `OPENAI_BASE_URL`         | OpenAI-compatible API base URL (defaults to OpenAI itself)     |
+/// | `TEMPLATE_DIR`            | directory whose `table.html`/`cards.html`/`list.html` replace  |
+/// |                           | the templates embedded in the binary                           |
+/// | `DEV_MODE`                | `true` to hot-reload templates from `TEMPLATE_DIR`             |
+/// | `PAPER_INDEX`             | where paper embeddings for recommendations are kept: `local`   |
+/// |                           | (default), `qdrant` or `disabled`                              |
+/// | `PAPER_INDEX_PATH`        | JSON file for the local index (default                         |
+/// |                           | `data/paper_index.json`)                                       |
+/// | `QDRANT_URL`              | Qdrant gRPC URL (default `http://localhost:6334`)              |
+///
+/// Only hashes of the API keys are ever stored; use `arxiv-rig-rust hash-key <key>`
+/// to produce the value for `API_KEY_HASHES`.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub bind_address: String,
+    pub api_key_hashes: HashSet<String>,
+    pub requests_per_minute_per_key: u32,
+    pub requests_per_minute_per_ip: u32,
+    pub cors_allowed_origins: Vec<String>,
+    pub arxiv_base_url: String,
+    pub pubmed_base_url: String,
+    pub crossref_base_url: String,
+    pub openai_base_url: Option<String>,
+    pub template_dir: Option<PathBuf>,
+    pub dev_mode: bool,
+    pub paper_index: PaperIndexKind,
+    pub paper_index_path: PathBuf,
+    pub qdrant_url: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaperIndexKind {
+    Local,
+    Qdrant,
+    Disabled,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: DEFAULT_BIND_ADDRESS.to_string(),
+            api_key_hashes: HashSet::new(),
+            requests_per_minute_per_key: DEFAULT_REQUESTS_PER_MINUTE_PER_KEY,
+            requests_per_minute_per_ip: DEFAULT_REQUESTS_PER_MINUTE_PER_IP,
+            cors_allowed_origins: Vec::new(),
+            arxiv_base_url: ARXIV_URL.to_string(),
+            pubmed_base_url: PUBMED_URL.to_string(),
+            crossref_base_url: CROSSREF_URL.to_string(),
+            openai_base_url: None,
+            template_dir: None,
+            dev_mode: false,
+            paper_index: PaperIndexKind::Local,
+            paper_index_path: PathBuf::from(DEFAULT_PAPER_INDEX_PATH),
+            qdrant_url: DEFAULT_QDRANT_URL.to_string(),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let defaults = Self::default();
+        Ok(Self {
+            bind_address: env::var("BIND_ADDRESS").unwrap_or(defaults.bind_address),
+            api_key_hashes: list_var("API_KEY_HASHES")
+                .into_iter()
+                .map(|hash| hash.to_lowercase())
+                .collect(),
+            requests_per_minute_per_key: number_var(
+                "RATE_LIMIT_PER_KEY",
+                defaults.requests_per_minute_per_key,
+            )?,
+            requests_per_minute_per_ip: number_var(
+                "RATE_LIMIT_PER_IP",
+                defaults.requests_per_minute_per_ip,
+            )?,
+            cors_allowed_origins: list_var("CORS_ALLOWED_ORIGINS"),
+            arxiv_base_url: env::var("ARXIV_BASE_URL").unwrap_or(defaults.arxiv_base_url),
+            pubmed_base_url: env::var("PUBMED_BASE_URL").unwrap_or(defaults.pubmed_base_url),
+            crossref_base_url: env::var("CROSSREF_BASE_URL").unwrap_or(defaults.crossref_base_url),
+            openai_base_url: env::var("OPENAI_BASE_URL").ok(),
+            template_dir: env::var_os("TEMPLATE_DIR").map(PathBuf::from),
+            dev_mode: env::var("DEV_MODE").is_ok_and(|value| value == "true" || value == "1"),
+            paper_index: match env::var("PAPER_INDEX").as_deref() {
+                Err(_) | Ok("local") => PaperIndexKind::Local,
+                Ok("qdrant") => PaperIndexKind::Qdrant,
+                Ok("disabled") => PaperIndexKind::Disabled,
+                Ok(value) => {
+                    return Err(ConfigError::InvalidValue {
+                        name: "PAPER_INDEX",
+                        value: value.to_owned(),
+                    })
+                }
+            },
+            paper_index_path: env::var_os("PAPER_INDEX_PATH")
+                .map(PathBuf::from)
+                .unwrap_or(defaults.paper_index_path),
+            qdrant_url: env::var("QDRANT_URL").unwrap_or(defaults.qdrant_url),
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("{name} must be a number, got {value:?}")]
+    InvalidNumber { name: &'static str, value: String },
+    #[error("{name} has an unsupported value {value:?}")]
+    InvalidValue { name: &'static str, value: String },
+}
+
+fn list_var(name: &str) -> Vec<String> {
+    env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn number_var(name: &'static str, default: u32) -> Result<u32, ConfigError> {
+    match env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| ConfigError::InvalidNumber { name, value }),
+        Err(_) => Ok(default),
+    }
+}
@@ -0,0 +1,174 @@
+use serde::Deserialize;
+
+use crate::error::ArxivError;
+use crate::model::Paper;
+use crate::paper_source::PaperSource;
+
+pub(crate) const CROSSREF_URL: &str = "https://api.crossref.org";
+
+/// Search over DOI-registered works using the Crossref REST API.
+#[derive(Debug, Clone)]
+pub struct CrossrefSource {
+    base_url: String,
+}
+
+impl CrossrefSource {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+}
+
+impl Default for CrossrefSource {
+    fn default() -> Self {
+        Self::new(CROSSREF_URL)
+    }
+}
+
+#[derive(Deserialize)]
+struct CrossrefResponse {
+    message: CrossrefMessage,
+}
+
+#[derive(Deserialize)]
+struct CrossrefMessage {
+    #[serde(default)]
+    items: Vec<CrossrefWork>,
+}
+
+#[derive(Deserialize)]
+struct CrossrefWork {
+    #[serde(rename = "DOI")]
+    doi: String,
+    #[serde(rename = "URL", default)]
+    url: String,
+    #[serde(default)]
+    title: Vec<String>,
+    #[serde(default)]
+    author: Vec<CrossrefAuthor>,
+    #[serde(rename = "abstract")]
+    abstract_text: Option<String>,
+    #[serde(default)]
+    subject: Vec<String>,
+    issued: Option<CrossrefDate>,
+}
+
+#[derive(Deserialize)]
+struct CrossrefAuthor {
+    given: Option<String>,
+    family: Option<String>,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CrossrefDate {
+    #[serde(rename = "date-parts", default)]
+    date_parts: Vec<Vec<Option<u32>>>,
+}
+
+impl CrossrefAuthor {
+    fn display_name(self) -> Option<String> {
+        match (self.given, self.family, self.name) {
+            (Some(given), Some(family), _) => Some(format!("{given} {family}")),
+            (None, Some(family), _) => Some(family),
+            (_, None, name) => name,
+        }
+    }
+}
+
+impl CrossrefDate {
+    // date-parts is [[year, month, day]] with month and day optional
+    fn to_iso(&self) -> String {
+        let parts: Vec<u32> = self
+            .date_parts
+            .first()
+            .map(|parts| parts.iter().map_while(|part| *part).collect())
+            .unwrap_or_default();
+        match parts.as_slice() {
+            [year, month, day, ..] => format!("{year:04}-{month:02}-{day:02}"),
+            [year, month] => format!("{year:04}-{month:02}"),
+            [year] => format!("{year:04}"),
+            [] => String::new(),
+        }
+    }
+}
+
+impl CrossrefWork {
+    fn into_paper(self) -> Paper {
+        let url = if self.url.is_empty() {
+            format!("https://doi.org/{}", self.doi)
+        } else {
+            self.url.replace("http://", "https://")
+        };
+        Paper {
+            id: format!("doi:{}", self.doi.to_lowercase()),
+            title: self.title.into_iter().next().unwrap_or_default(),
+            authors: self
+                .author
+                .into_iter()
+                .filter_map(CrossrefAuthor::display_name)
+                .collect(),
+            abstract_text: self
+                .abstract_text
+                .map(|text| strip_markup(&text))
+                .unwrap_or_default(),
+            url,
+            categories: self.subject,
+            published: self.issued.map(|date| date.to_iso()).unwrap_or_default(),
+            doi: Some(self.doi),
+            source: "crossref".to_string(),
+        }
+    }
+}
+
+// Crossref abstracts are JATS XML fragments (`<jats:p>...</jats:p>`);
+// we only want the text
+fn strip_markup(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                plain.push(' ');
+            }
+            _ if !in_tag => plain.push(c),
+            _ => (),
+        }
+    }
+    plain.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl PaperSource for CrossrefSource {
+    fn name(&self) -> &'static str {
+        "crossref"
+    }
+
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<Paper>, ArxivError> {
+        let response = reqwest::Client::new()
+            .get(format!("{}/works", self.base_url))
+            .query(&[
+                ("query", query.to_string()),
+                ("rows", max_results.to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let papers: Vec<Paper> = serde_json::from_str::<CrossrefResponse>(&response)?
+            .message
+            .items
+            .into_iter()
+            .map(CrossrefWork::into_paper)
+            .collect();
+
+        if papers.is_empty() {
+            return Err(ArxivError::NoResults);
+        }
+        Ok(papers)
+    }
+}
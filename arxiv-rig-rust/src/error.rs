@@ -9,6 +9,8 @@ pub enum ArxivError {
     XmlParsing(#[from] quick_xml::Error),
     #[error("No results found")]
     NoResults,
+    #[error("JSON parsing error: {0}")]
+    JsonParsing(#[from] serde_json::Error),
     #[error("UTF-8 decoding error: {0}")]
     Utf8Error(#[from] std::str::Utf8Error),
 }
@@ -0,0 +1,21 @@
+pub mod app;
+mod arxiv_parser;
+mod arxiv_search_tool;
+pub mod auth;
+pub mod config;
+pub mod crossref_source;
+mod error;
+pub mod model;
+pub mod openapi;
+mod paper_index;
+pub mod paper_search_tool;
+mod paper_source;
+pub mod pubmed_source;
+mod rate_limit;
+mod recommend;
+mod routes;
+pub mod session;
+mod similar_papers_tool;
+pub mod telemetry;
+mod templates;
+mod util;
@@ -1,63 +1,65 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
-use axum::response::{Html, IntoResponse};
-use axum::{Router};
-use axum::routing::{get, post};
 
+use arxiv_rig_rust::config::ServerConfig;
+use arxiv_rig_rust::crossref_source::CrossrefSource;
+use arxiv_rig_rust::model::{AppState, ArxivSearchTool};
+use arxiv_rig_rust::paper_search_tool::PaperSearchTool;
+use arxiv_rig_rust::pubmed_source::PubMedSource;
+use arxiv_rig_rust::{app, auth, telemetry};
 use dotenv::dotenv;
 use rig::client::{ProviderClient};
 use rig::providers::openai::{Client};
 use tokio::net::TcpListener;
-use crate::model::AppState;
-use tower_http::cors::{CorsLayer, Any};
 use tracing::info;
 
-mod model;
-mod error;
-mod arxiv_search_tool;
-mod arxiv_parser;
-mod util;
-mod routes;
-
-// Handler for serving the static index.html
-async fn serve_index() -> impl IntoResponse {
-    Html(include_str!("../static/index.html"))
-}
-async fn serve_test() -> impl IntoResponse {
-    Html("Hello world!")
-}
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // `arxiv-rig-rust hash-key <key>` prints the value to put in API_KEY_HASHES
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, command, key] = args.as_slice() {
+        if command == "hash-key" {
+            println!("{}", auth::hash_api_key(key));
+            return Ok(());
+        }
+    }
+
     dotenv().ok();
+    telemetry::init_tracing();
+    let metrics = telemetry::init_metrics()?;
+    let config = ServerConfig::from_env()?;
   /*  let openai_key = secrets
         .get("OPENAI_API_KEY")
         .context("OPENAI_API_KEY secret not found")?;*/
-    let openai_client = Client::from_env();
+    let openai_client = match &config.openai_base_url {
+        Some(base_url) => Client::from_url(&std::env::var("OPENAI_API_KEY")?, base_url),
+        None => Client::from_env(),
+    };
 
    // let openai_client = openai::Client::new(&openai_key);
 
-    // Create shared state
-    let state = Arc::new(AppState {
-        openai_client,
-    });
+    if config.api_key_hashes.is_empty() {
+        tracing::warn!("API_KEY_HASHES is not set - the API is open to anyone who can reach it");
+    }
+
+    let paper_search = PaperSearchTool::new(
+        ArxivSearchTool::new(&config.arxiv_base_url),
+        PubMedSource::new(&config.pubmed_base_url),
+        CrossrefSource::new(&config.crossref_base_url),
+    );
 
-    // Set up CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([axum::http::Method::GET, axum::http::Method::POST])
-        .allow_headers(Any);
+    let bind_address = config.bind_address.clone();
+
+    // Create shared state
+    let state = Arc::new(AppState::new(openai_client, paper_search, config, metrics)?);
 
     // Create router
-    let router = Router::new()
-        .route("/", get(serve_index))
-        .route("/test", get(serve_test))
-        .route("/api/search", post(routes::search_papers))
-        .layer(cors)
-        .with_state(state.clone());
-    let listener = TcpListener::bind("127.0.0.1:8003").await.unwrap();
+    let router = app::router(state);
+    let listener = TcpListener::bind(&bind_address).await.unwrap();
     info!("->> {:<12} - {:?}\n", "LISTENING", listener.local_addr());
 
-    axum::serve(listener, router.into_make_service())
+    // connect info gives the rate limiter the client's IP address
+    axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .unwrap();
     Ok(())
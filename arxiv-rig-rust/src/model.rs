@@ -1,30 +1,78 @@
+use std::sync::Arc;
+
+use metrics_exporter_prometheus::PrometheusHandle;
+use rig::client::EmbeddingsClient;
 use rig::providers::openai;
 
-#[derive(Debug, serde::Deserialize, serde::Serialize, Clone)]
+use crate::auth::AccessControl;
+use crate::config::{PaperIndexKind, ServerConfig};
+use crate::paper_index::{LocalPaperIndex, PaperIndex, QdrantPaperIndex};
+use crate::paper_search_tool::PaperSearchTool;
+use crate::paper_source::SourceKind;
+use crate::recommend::PaperRecommender;
+use crate::session::SessionStore;
+use crate::templates::{ResultView, Templates};
+
+#[derive(Debug, serde::Deserialize, serde::Serialize, Clone, utoipa::ToSchema)]
 pub struct Paper {
+    /// Stable identifier: "arxiv:<id>", "doi:<doi>" or "pubmed:<pmid>"
+    #[serde(default)]
+    pub id: String,
     pub title: String,
     pub authors: Vec<String>,
     pub abstract_text: String,
     pub url: String,
     pub categories: Vec<String>,
+    #[serde(default)]
+    pub published: String,
+    #[serde(default)]
+    pub doi: Option<String>,
+    /// Which source(s) the paper was found in, e.g. "arxiv" or "arxiv, crossref"
+    #[serde(default)]
+    pub source: String,
 }
 
 impl Paper {
     pub(crate) fn new() -> Self {
         Self {
+            id: String::new(),
             title: String::new(),
             authors: Vec::new(),
             abstract_text: String::new(),
             url: String::new(),
             categories: Vec::new(),
+            published: String::new(),
+            doi: None,
+            source: String::new(),
         }
     }
 }
 
 // Request structure for search endpoint
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
 pub(crate) struct SearchRequest {
+    /// A research question, or a follow-up about earlier results
     pub(crate) query: String,
+    /// How HTML results are rendered. Ignored for JSON responses.
+    #[serde(default)]
+    pub(crate) view: ResultView,
+}
+
+/// Search result returned when the client asks for `application/json`.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct SearchResponse {
+    pub papers: Vec<Paper>,
+    /// The assistant's reply when it answered in prose rather than with papers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+// Query parameters for the similar papers endpoint
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(crate) struct SimilarQuery {
+    /// Number of papers to return, between 1 and 50 (default 5)
+    pub(crate) limit: Option<usize>,
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -34,9 +82,68 @@ pub struct SearchArgs {
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
-pub struct ArxivSearchTool;
+pub struct PaperSearchArgs {
+    pub(crate) query: String,
+    pub(crate) max_results: Option<i32>,
+    pub(crate) sources: Option<Vec<SourceKind>>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct ArxivSearchTool {
+    pub(crate) base_url: String,
+}
 
-#[derive(Clone)]
-pub(crate) struct AppState {
+pub struct AppState {
     pub(crate) openai_client: openai::Client,
+    pub(crate) paper_search: PaperSearchTool,
+    pub(crate) sessions: SessionStore,
+    pub(crate) metrics: PrometheusHandle,
+    pub(crate) access: AccessControl,
+    pub(crate) templates: Templates,
+    pub(crate) recommender: Option<Arc<PaperRecommender>>,
+    pub(crate) config: ServerConfig,
+}
+
+impl AppState {
+    /// The OpenAI client and paper sources are passed in rather than built here,
+    /// so tests can point them at local mock servers.
+    ///
+    /// Fails if a template in the configured override directory doesn't compile
+    /// or the paper index can't be opened.
+    pub fn new(
+        openai_client: openai::Client,
+        paper_search: PaperSearchTool,
+        config: ServerConfig,
+        metrics: PrometheusHandle,
+    ) -> anyhow::Result<Self> {
+        let index = match config.paper_index {
+            PaperIndexKind::Local => Some(PaperIndex::Local(LocalPaperIndex::load(
+                config.paper_index_path.clone(),
+            )?)),
+            PaperIndexKind::Qdrant => Some(PaperIndex::Qdrant(QdrantPaperIndex::connect(
+                &config.qdrant_url,
+            )?)),
+            PaperIndexKind::Disabled => None,
+        };
+        let recommender = index.map(|index| {
+            let model = openai_client.embedding_model(openai::TEXT_EMBEDDING_3_SMALL);
+            Arc::new(PaperRecommender::new(model, index))
+        });
+        // every paper the search tool fetches is fed into the index
+        let paper_search = match &recommender {
+            Some(recommender) => paper_search.with_recommender(recommender.clone()),
+            None => paper_search,
+        };
+
+        Ok(Self {
+            openai_client,
+            paper_search,
+            sessions: SessionStore::default(),
+            metrics,
+            access: AccessControl::new(&config),
+            templates: Templates::new(config.template_dir.clone(), config.dev_mode)?,
+            recommender,
+            config,
+        })
+    }
 }
@@ -0,0 +1,49 @@
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::auth::API_KEY_HEADER;
+use crate::model::{Paper, SearchRequest, SearchResponse};
+use crate::paper_index::SimilarPaper;
+use crate::routes;
+use crate::templates::ResultView;
+
+/// OpenAPI 3 description of the HTTP API, served at `/openapi.json` and
+/// browsable at `/docs`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "arXiv research assistant",
+        description = "Searches arXiv, PubMed and Crossref through an LLM research assistant."
+    ),
+    paths(
+        routes::search_papers,
+        routes::reset_session,
+        routes::similar_papers,
+        routes::metrics,
+    ),
+    components(schemas(Paper, ResultView, SearchRequest, SearchResponse, SimilarPaper)),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "search", description = "Conversational paper search"),
+        (name = "papers", description = "Recommendations for papers found earlier"),
+        (name = "operations", description = "Monitoring"),
+    )
+)]
+pub struct ApiDoc;
+
+// keys can be sent in the x-api-key header or as a bearer token, see `auth::enforce`
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+        );
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
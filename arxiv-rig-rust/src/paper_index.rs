@@ -0,0 +1,346 @@
+use std::path::PathBuf;
+
+use qdrant_client::qdrant::value::Kind;
+use qdrant_client::qdrant::{
+    CreateCollectionBuilder, Distance, GetPointsBuilder, PointId, PointStruct, Query,
+    QueryPointsBuilder, UpsertPointsBuilder, VectorParamsBuilder,
+};
+use qdrant_client::{Payload, Qdrant};
+use tokio::sync::{OnceCell, RwLock};
+
+use crate::model::Paper;
+
+const COLLECTION_NAME: &str = "ARXIV_PAPERS";
+// matches text-embedding-3-small
+pub(crate) const EMBEDDING_DIMENSIONS: u64 = 1536;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PaperIndexError {
+    #[error("Index file error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Index serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Qdrant error: {0}")]
+    Qdrant(#[from] qdrant_client::QdrantError),
+}
+
+/// A paper together with the embedding of its title and abstract.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub(crate) struct IndexedPaper {
+    pub(crate) paper: Paper,
+    pub(crate) embedding: Vec<f64>,
+}
+
+/// A neighbour returned by a similarity query. Serialises as the paper itself
+/// plus a `score` field, so it can be rendered like any other search result.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
+pub struct SimilarPaper {
+    pub score: f64,
+    #[serde(flatten)]
+    pub paper: Paper,
+}
+
+/// Where paper embeddings are kept.
+pub(crate) enum PaperIndex {
+    Local(LocalPaperIndex),
+    Qdrant(QdrantPaperIndex),
+}
+
+impl PaperIndex {
+    pub(crate) async fn contains(&self, id: &str) -> Result<bool, PaperIndexError> {
+        match self {
+            PaperIndex::Local(index) => Ok(index.contains(id).await),
+            PaperIndex::Qdrant(index) => index.contains(id).await,
+        }
+    }
+
+    pub(crate) async fn insert(&self, papers: Vec<IndexedPaper>) -> Result<(), PaperIndexError> {
+        match self {
+            PaperIndex::Local(index) => index.insert(papers).await,
+            PaperIndex::Qdrant(index) => index.insert(papers).await,
+        }
+    }
+
+    /// Nearest neighbours of an indexed paper, best first and excluding the paper
+    /// itself. Returns `None` if the paper isn't in the index.
+    pub(crate) async fn similar_to(
+        &self,
+        id: &str,
+        limit: usize,
+    ) -> Result<Option<Vec<SimilarPaper>>, PaperIndexError> {
+        match self {
+            PaperIndex::Local(index) => Ok(index.similar_to(id, limit).await),
+            PaperIndex::Qdrant(index) => index.similar_to(id, limit).await,
+        }
+    }
+}
+
+/// In-process index persisted as a JSON file; similarity is brute-force cosine,
+/// which is plenty for the few thousand papers a team looks at.
+pub(crate) struct LocalPaperIndex {
+    path: PathBuf,
+    entries: RwLock<Vec<IndexedPaper>>,
+}
+
+impl LocalPaperIndex {
+    /// Loads the index from `path`, starting empty if the file doesn't exist yet.
+    pub(crate) fn load(path: PathBuf) -> Result<Self, PaperIndexError> {
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            entries: RwLock::new(entries),
+        })
+    }
+
+    async fn contains(&self, id: &str) -> bool {
+        self.entries.read().await.iter().any(|entry| entry.paper.id == id)
+    }
+
+    async fn insert(&self, papers: Vec<IndexedPaper>) -> Result<(), PaperIndexError> {
+        let mut entries = self.entries.write().await;
+        for paper in papers {
+            match entries.iter_mut().find(|entry| entry.paper.id == paper.paper.id) {
+                Some(existing) => *existing = paper,
+                None => entries.push(paper),
+            }
+        }
+
+        // write to a temporary file first so a crash can't leave a half-written index
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, serde_json::to_vec(&*entries)?).await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+
+    async fn similar_to(&self, id: &str, limit: usize) -> Option<Vec<SimilarPaper>> {
+        let entries = self.entries.read().await;
+        let target = entries.iter().find(|entry| entry.paper.id == id)?;
+
+        let mut neighbours: Vec<SimilarPaper> = entries
+            .iter()
+            .filter(|entry| entry.paper.id != id)
+            .map(|entry| SimilarPaper {
+                score: cosine_similarity(&target.embedding, &entry.embedding),
+                paper: entry.paper.clone(),
+            })
+            .collect();
+        neighbours.sort_by(|a, b| b.score.total_cmp(&a.score));
+        neighbours.truncate(limit);
+        Some(neighbours)
+    }
+}
+
+fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Index stored in a Qdrant collection, shared by every server pointing at it.
+pub(crate) struct QdrantPaperIndex {
+    client: Qdrant,
+    collection_ready: OnceCell<()>,
+}
+
+impl QdrantPaperIndex {
+    pub(crate) fn connect(url: &str) -> Result<Self, PaperIndexError> {
+        Ok(Self {
+            client: Qdrant::from_url(url).build()?,
+            collection_ready: OnceCell::new(),
+        })
+    }
+
+    // the collection is created on first use, so startup doesn't need Qdrant to be up
+    async fn ensure_collection(&self) -> Result<(), PaperIndexError> {
+        self.collection_ready
+            .get_or_try_init(|| async {
+                if !self.client.collection_exists(COLLECTION_NAME).await? {
+                    self.client
+                        .create_collection(
+                            CreateCollectionBuilder::new(COLLECTION_NAME).vectors_config(
+                                VectorParamsBuilder::new(EMBEDDING_DIMENSIONS, Distance::Cosine),
+                            ),
+                        )
+                        .await?;
+                }
+                Ok::<_, PaperIndexError>(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn contains(&self, id: &str) -> Result<bool, PaperIndexError> {
+        self.ensure_collection().await?;
+        let response = self
+            .client
+            .get_points(GetPointsBuilder::new(COLLECTION_NAME, vec![point_id(id)]))
+            .await?;
+        Ok(!response.result.is_empty())
+    }
+
+    async fn insert(&self, papers: Vec<IndexedPaper>) -> Result<(), PaperIndexError> {
+        self.ensure_collection().await?;
+        let points = papers
+            .into_iter()
+            .map(|indexed| {
+                let payload = Payload::try_from(serde_json::json!({
+                    "paper": serde_json::to_string(&indexed.paper)?,
+                }))
+                .expect("a JSON object is always a valid payload");
+                let vector: Vec<f32> = indexed.embedding.iter().map(|&x| x as f32).collect();
+                Ok(PointStruct::new(point_id(&indexed.paper.id), vector, payload))
+            })
+            .collect::<Result<Vec<_>, PaperIndexError>>()?;
+
+        self.client
+            .upsert_points(UpsertPointsBuilder::new(COLLECTION_NAME, points))
+            .await?;
+        Ok(())
+    }
+
+    async fn similar_to(
+        &self,
+        id: &str,
+        limit: usize,
+    ) -> Result<Option<Vec<SimilarPaper>>, PaperIndexError> {
+        if !self.contains(id).await? {
+            return Ok(None);
+        }
+
+        // one extra result since the paper itself is its own nearest neighbour
+        let response = self
+            .client
+            .query(
+                QueryPointsBuilder::new(COLLECTION_NAME)
+                    .query(Query::new_nearest(point_id(id)))
+                    .limit(limit as u64 + 1)
+                    .with_payload(true),
+            )
+            .await?;
+
+        let neighbours = response
+            .result
+            .into_iter()
+            .filter_map(|point| {
+                let Some(Kind::StringValue(json)) = point.payload.get("paper")?.kind.as_ref() else {
+                    return None;
+                };
+                let paper: Paper = serde_json::from_str(json).ok()?;
+                Some(SimilarPaper {
+                    score: f64::from(point.score),
+                    paper,
+                })
+            })
+            .filter(|neighbour| neighbour.paper.id != id)
+            .take(limit)
+            .collect();
+        Ok(Some(neighbours))
+    }
+}
+
+// Qdrant only accepts integers or UUIDs as point ids, so derive a stable UUID from the paper id
+fn point_id(id: &str) -> PointId {
+    PointId::from(uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, id.as_bytes()).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indexed(id: &str, embedding: &[f64]) -> IndexedPaper {
+        IndexedPaper {
+            paper: Paper {
+                id: id.to_owned(),
+                title: id.to_owned(),
+                ..Paper::new()
+            },
+            embedding: embedding.to_vec(),
+        }
+    }
+
+    fn temp_index() -> LocalPaperIndex {
+        let path = std::env::temp_dir().join(format!("paper-index-{}.json", uuid::Uuid::new_v4()));
+        LocalPaperIndex::load(path).unwrap()
+    }
+
+    #[test]
+    fn cosine_similarity_ignores_magnitude() {
+        assert!((cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-12);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]), 0.0);
+        assert!((cosine_similarity(&[1.0, 0.0], &[-1.0, 0.0]) + 1.0).abs() < 1e-12);
+        assert!((cosine_similarity(&[1.0, 1.0], &[1.0, 0.0]) - 0.5f64.sqrt()).abs() < 1e-12);
+        // an empty text embeds as zeros, which is similar to nothing
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+
+    #[tokio::test]
+    async fn neighbours_are_ranked_best_first_without_the_paper_itself() {
+        let index = temp_index();
+        index
+            .insert(vec![
+                indexed("a", &[1.0, 0.0, 0.0]),
+                indexed("far", &[0.0, 0.0, 1.0]),
+                indexed("near", &[0.9, 0.1, 0.0]),
+                indexed("middle", &[0.5, 0.5, 0.0]),
+            ])
+            .await
+            .unwrap();
+
+        let neighbours = index.similar_to("a", 10).await.unwrap();
+        let ids: Vec<_> = neighbours.iter().map(|n| n.paper.id.as_str()).collect();
+        assert_eq!(ids, ["near", "middle", "far"]);
+        assert!(neighbours.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        assert_eq!(index.similar_to("a", 1).await.unwrap().len(), 1);
+        assert!(index.similar_to("missing", 5).await.is_none());
+        let _ = std::fs::remove_file(&index.path);
+    }
+
+    #[tokio::test]
+    async fn reinserting_a_paper_replaces_its_embedding() {
+        let index = temp_index();
+        index
+            .insert(vec![indexed("a", &[1.0, 0.0]), indexed("b", &[0.0, 1.0])])
+            .await
+            .unwrap();
+        index.insert(vec![indexed("b", &[1.0, 0.0])]).await.unwrap();
+
+        assert_eq!(index.entries.read().await.len(), 2);
+        let neighbours = index.similar_to("a", 5).await.unwrap();
+        assert!((neighbours[0].score - 1.0).abs() < 1e-12);
+        let _ = std::fs::remove_file(&index.path);
+    }
+
+    #[tokio::test]
+    async fn the_index_survives_a_reload() {
+        let index = temp_index();
+        index
+            .insert(vec![indexed("a", &[1.0, 0.0]), indexed("b", &[1.0, 1.0])])
+            .await
+            .unwrap();
+
+        let reloaded = LocalPaperIndex::load(index.path.clone()).unwrap();
+        assert!(reloaded.contains("a").await);
+        assert_eq!(reloaded.similar_to("a", 5).await.unwrap()[0].paper.id, "b");
+        let _ = std::fs::remove_file(&index.path);
+    }
+
+    #[test]
+    fn a_corrupt_index_file_is_an_error() {
+        let path = std::env::temp_dir().join(format!("paper-index-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "not json").unwrap();
+        assert!(matches!(LocalPaperIndex::load(path.clone()), Err(PaperIndexError::Json(_))));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde_json::json;
+use crate::crossref_source::CrossrefSource;
+use crate::error::ArxivError;
+use crate::model::{ArxivSearchTool, Paper, PaperSearchArgs};
+use crate::paper_source::{merge_papers, PaperSource, SourceKind};
+use crate::pubmed_source::PubMedSource;
+use crate::recommend::PaperRecommender;
+
+/// Agent tool that searches several paper sources at once and merges the results.
+#[derive(Clone, Default)]
+pub struct PaperSearchTool {
+    pub(crate) arxiv: ArxivSearchTool,
+    pub(crate) pubmed: PubMedSource,
+    pub(crate) crossref: CrossrefSource,
+    pub(crate) recommender: Option<Arc<PaperRecommender>>,
+}
+
+impl PaperSearchTool {
+    pub fn new(arxiv: ArxivSearchTool, pubmed: PubMedSource, crossref: CrossrefSource) -> Self {
+        Self {
+            arxiv,
+            pubmed,
+            crossref,
+            recommender: None,
+        }
+    }
+
+    pub(crate) fn with_recommender(mut self, recommender: Arc<PaperRecommender>) -> Self {
+        self.recommender = Some(recommender);
+        self
+    }
+
+    #[tracing::instrument(name = "paper_source", skip(self, kind, selected), fields(source = ?kind))]
+    async fn search_source(
+        &self,
+        kind: SourceKind,
+        selected: &[SourceKind],
+        query: &str,
+        max_results: usize,
+    ) -> Option<Result<Vec<Paper>, ArxivError>> {
+        if !selected.contains(&kind) {
+            return None;
+        }
+        let (name, result) = match kind {
+            SourceKind::Arxiv => (self.arxiv.name(), self.arxiv.search(query, max_results).await),
+            SourceKind::Pubmed => (self.pubmed.name(), self.pubmed.search(query, max_results).await),
+            SourceKind::Crossref => (
+                self.crossref.name(),
+                self.crossref.search(query, max_results).await,
+            ),
+        };
+        metrics::counter!("paper_source_requests_total", "source" => name).increment(1);
+        match &result {
+            Ok(_) | Err(ArxivError::NoResults) => (),
+            Err(e) => {
+                metrics::counter!("paper_source_errors_total", "source" => name).increment(1);
+                tracing::warn!("{name} search failed: {e}");
+            }
+        }
+        Some(result)
+    }
+}
+
+impl Tool for PaperSearchTool {
+    const NAME: &'static str = "search_papers";
+    type Error = ArxivError;
+    type Args = PaperSearchArgs;
+    type Output = Vec<Paper>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Search for academic papers across arXiv, PubMed and Crossref. \
+                Duplicates found in several sources are merged."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Search query for papers"
+                    },
+                    "max_results": {
+                        "type": "integer",
+                        "description": "Maximum number of results to return per source (default: 5)"
+                    },
+                    "sources": {
+                        "type": "array",
+                        "items": {
+                            "type": "string",
+                            "enum": ["arxiv", "pubmed", "crossref"]
+                        },
+                        "description": "Sources to search. Use pubmed for biomedical topics and crossref for \
+                            DOI-registered journal work. Defaults to all sources."
+                    }
+                },
+                "required": ["query"]
+            })
+        }
+    }
+
+    #[tracing::instrument(name = "tool_call", skip(self), fields(tool = Self::NAME))]
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let max_results = args.max_results.unwrap_or(5).max(1) as usize;
+        let selected = args
+            .sources
+            .filter(|sources| !sources.is_empty())
+            .unwrap_or_else(|| SourceKind::ALL.to_vec());
+
+        let (arxiv, pubmed, crossref) = tokio::join!(
+            self.search_source(SourceKind::Arxiv, &selected, &args.query, max_results),
+            self.search_source(SourceKind::Pubmed, &selected, &args.query, max_results),
+            self.search_source(SourceKind::Crossref, &selected, &args.query, max_results),
+        );
+
+        // a failing source shouldn't hide the results of the others;
+        // only report an error when nothing came back at all
+        let mut results = Vec::new();
+        let mut last_error = None;
+        for result in [arxiv, pubmed, crossref].into_iter().flatten() {
+            match result {
+                Ok(papers) => results.push(papers),
+                Err(ArxivError::NoResults) => (),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        let papers = merge_papers(results);
+        if papers.is_empty() {
+            return Err(last_error.unwrap_or(ArxivError::NoResults));
+        }
+
+        // embedding happens in the background so it never slows down the answer
+        if let Some(recommender) = self.recommender.clone() {
+            let papers = papers.clone();
+            tokio::spawn(async move {
+                if let Err(e) = recommender.add_papers(papers).await {
+                    tracing::warn!("failed to index papers: {e}");
+                }
+            });
+        }
+        Ok(papers)
+    }
+}
@@ -0,0 +1,221 @@
+use std::future::Future;
+
+use crate::error::ArxivError;
+use crate::model::Paper;
+
+/// A searchable catalogue of papers (arXiv, PubMed, Crossref, ...).
+///
+/// Every source maps its own response format onto the shared [`Paper`] model,
+/// so results from different sources can be merged and rendered the same way.
+pub trait PaperSource {
+    /// Short identifier stored in [`Paper::source`], e.g. `"arxiv"`.
+    fn name(&self) -> &'static str;
+
+    fn search(
+        &self,
+        query: &str,
+        max_results: usize,
+    ) -> impl Future<Output = Result<Vec<Paper>, ArxivError>> + Send;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Arxiv,
+    Pubmed,
+    Crossref,
+}
+
+impl SourceKind {
+    pub const ALL: [SourceKind; 3] = [SourceKind::Arxiv, SourceKind::Pubmed, SourceKind::Crossref];
+}
+
+/// Merges results from several sources, collapsing papers that share a DOI or,
+/// failing that, a normalised title. The first occurrence wins; later duplicates
+/// only fill in fields it is missing and add their source name.
+pub fn merge_papers(results: impl IntoIterator<Item = Vec<Paper>>) -> Vec<Paper> {
+    let mut merged: Vec<Paper> = Vec::new();
+
+    for paper in results.into_iter().flatten() {
+        match merged.iter_mut().find(|existing| is_same_paper(existing, &paper)) {
+            Some(existing) => merge_into(existing, paper),
+            None => merged.push(paper),
+        }
+    }
+
+    merged
+}
+
+fn is_same_paper(a: &Paper, b: &Paper) -> bool {
+    if let (Some(a_doi), Some(b_doi)) = (&a.doi, &b.doi) {
+        return normalise_doi(a_doi) == normalise_doi(b_doi);
+    }
+    let a_title = normalise_title(&a.title);
+    !a_title.is_empty() && a_title == normalise_title(&b.title)
+}
+
+fn merge_into(existing: &mut Paper, duplicate: Paper) {
+    if existing.doi.is_none() {
+        existing.doi = duplicate.doi;
+    }
+    if existing.abstract_text.is_empty() {
+        existing.abstract_text = duplicate.abstract_text;
+    }
+    if existing.published.is_empty() {
+        existing.published = duplicate.published;
+    }
+    if existing.authors.is_empty() {
+        existing.authors = duplicate.authors;
+    }
+    for category in duplicate.categories {
+        if !existing.categories.contains(&category) {
+            existing.categories.push(category);
+        }
+    }
+    if !existing.source.split(", ").any(|s| s == duplicate.source) {
+        existing.source = format!("{}, {}", existing.source, duplicate.source);
+    }
+}
+
+// DOIs are case-insensitive and often written as a resolver URL
+fn normalise_doi(doi: &str) -> String {
+    const PREFIXES: [&str; 5] = [
+        "https://doi.org/",
+        "http://doi.org/",
+        "https://dx.doi.org/",
+        "http://dx.doi.org/",
+        "doi:",
+    ];
+    let doi = doi.trim().to_lowercase();
+    PREFIXES
+        .iter()
+        .find_map(|prefix| doi.strip_prefix(prefix))
+        .unwrap_or(&doi)
+        .trim()
+        .to_owned()
+}
+
+// lowercase, alphanumerics only, single spaces - so "Attention Is All You Need."
+// and "Attention is all you need" compare equal
+fn normalise_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper(title: &str, doi: Option<&str>, source: &str) -> Paper {
+        Paper {
+            title: title.to_owned(),
+            doi: doi.map(str::to_owned),
+            source: source.to_owned(),
+            ..Paper::new()
+        }
+    }
+
+    #[test]
+    fn normalise_doi_strips_resolvers_and_case() {
+        for doi in [
+            "10.1145/3505244",
+            "  10.1145/3505244 ",
+            "doi:10.1145/3505244",
+            "DOI:10.1145/3505244",
+            "https://doi.org/10.1145/3505244",
+            "http://doi.org/10.1145/3505244",
+            "https://dx.doi.org/10.1145/3505244",
+            "http://dx.doi.org/10.1145/3505244",
+        ] {
+            assert_eq!(normalise_doi(doi), "10.1145/3505244", "{doi}");
+        }
+        assert_eq!(normalise_doi("10.48550/arXiv.2307.09288"), "10.48550/arxiv.2307.09288");
+    }
+
+    #[test]
+    fn normalise_title_ignores_case_and_punctuation() {
+        assert_eq!(normalise_title("Attention Is All You Need."), "attention is all you need");
+        assert_eq!(normalise_title("  Llama 2:  Open   Foundation "), "llama 2 open foundation");
+        assert_eq!(normalise_title("..."), "");
+    }
+
+    #[test]
+    fn same_doi_in_different_forms_is_one_paper() {
+        let merged = merge_papers([
+            vec![paper("Llama 2", Some("10.48550/arXiv.2307.09288"), "arxiv")],
+            vec![paper("Llama 2 (preprint)", Some("https://dx.doi.org/10.48550/ARXIV.2307.09288"), "crossref")],
+        ]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].title, "Llama 2");
+        assert_eq!(merged[0].source, "arxiv, crossref");
+    }
+
+    #[test]
+    fn different_dois_are_different_papers_even_with_the_same_title() {
+        // e.g. a conference paper and its journal version
+        let merged = merge_papers([
+            vec![paper("Deep Residual Learning", Some("10.1109/cvpr.2016.90"), "crossref")],
+            vec![paper("Deep Residual Learning", Some("10.48550/arxiv.1512.03385"), "arxiv")],
+        ]);
+        assert_eq!(merged.len(), 2);
+    }
+
+    #[test]
+    fn papers_without_a_doi_merge_by_title() {
+        let merged = merge_papers([
+            vec![paper("Attention Is All You Need", None, "arxiv")],
+            vec![paper("Attention is all you need.", Some("10.5555/3295222.3295349"), "pubmed")],
+        ]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].doi.as_deref(), Some("10.5555/3295222.3295349"));
+        assert_eq!(merged[0].source, "arxiv, pubmed");
+    }
+
+    #[test]
+    fn untitled_papers_are_never_merged_by_title() {
+        let merged = merge_papers([vec![paper("", None, "arxiv")], vec![paper("", None, "pubmed")]]);
+        assert_eq!(merged.len(), 2);
+    }
+
+    #[test]
+    fn duplicates_only_fill_in_missing_fields() {
+        let first = Paper {
+            abstract_text: "From arXiv".to_owned(),
+            categories: vec!["cs.CL".to_owned()],
+            ..paper("Llama 2", Some("10.48550/arxiv.2307.09288"), "arxiv")
+        };
+        let duplicate = Paper {
+            abstract_text: "From Crossref".to_owned(),
+            authors: vec!["Hugo Touvron".to_owned()],
+            published: "2023".to_owned(),
+            categories: vec!["cs.CL".to_owned(), "Computation and Language".to_owned()],
+            ..paper("Llama 2", Some("10.48550/arxiv.2307.09288"), "crossref")
+        };
+
+        let merged = merge_papers([vec![first], vec![duplicate]]);
+        assert_eq!(merged.len(), 1);
+        let paper = &merged[0];
+        assert_eq!(paper.abstract_text, "From arXiv");
+        assert_eq!(paper.authors, ["Hugo Touvron"]);
+        assert_eq!(paper.published, "2023");
+        assert_eq!(paper.categories, ["cs.CL", "Computation and Language"]);
+    }
+
+    #[test]
+    fn a_source_is_listed_once_and_order_is_kept() {
+        let merged = merge_papers([
+            vec![paper("A", None, "arxiv"), paper("B", None, "arxiv"), paper("a", None, "arxiv")],
+            vec![paper("C", None, "crossref"), paper("B", None, "crossref")],
+        ]);
+        let titles: Vec<_> = merged.iter().map(|p| p.title.as_str()).collect();
+        assert_eq!(titles, ["A", "B", "C"]);
+        assert_eq!(merged[0].source, "arxiv");
+        assert_eq!(merged[1].source, "arxiv, crossref");
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::error::ArxivError;
+use crate::model::Paper;
+use crate::paper_source::PaperSource;
+
+pub(crate) const PUBMED_URL: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils";
+
+/// PubMed search through the NCBI E-utilities: `esearch` finds matching PubMed ids,
+/// `esummary` fetches the title, authors, date and DOI for them.
+#[derive(Debug, Clone)]
+pub struct PubMedSource {
+    base_url: String,
+}
+
+impl PubMedSource {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+}
+
+impl Default for PubMedSource {
+    fn default() -> Self {
+        Self::new(PUBMED_URL)
+    }
+}
+
+#[derive(Deserialize)]
+struct ESearchResponse {
+    esearchresult: ESearchResult,
+}
+
+#[derive(Deserialize)]
+struct ESearchResult {
+    #[serde(default)]
+    idlist: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ESummaryResponse {
+    result: ESummaryResult,
+}
+
+// the result object holds a `uids` list plus one entry per uid, keyed by the uid itself
+#[derive(Deserialize)]
+struct ESummaryResult {
+    #[serde(default)]
+    uids: Vec<String>,
+    #[serde(flatten)]
+    documents: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct PubMedSummary {
+    uid: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    pubdate: String,
+    #[serde(default)]
+    authors: Vec<PubMedAuthor>,
+    #[serde(default)]
+    articleids: Vec<PubMedArticleId>,
+}
+
+#[derive(Deserialize)]
+struct PubMedAuthor {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct PubMedArticleId {
+    idtype: String,
+    value: String,
+}
+
+impl PubMedSummary {
+    fn into_paper(self) -> Paper {
+        let doi = self
+            .articleids
+            .iter()
+            .find(|id| id.idtype == "doi")
+            .map(|id| id.value.clone());
+        Paper {
+            id: format!("pubmed:{}", self.uid),
+            title: self.title,
+            authors: self.authors.into_iter().map(|a| a.name).collect(),
+            // esummary doesn't include abstracts
+            abstract_text: String::new(),
+            url: format!("https://pubmed.ncbi.nlm.nih.gov/{}/", self.uid),
+            categories: Vec::new(),
+            published: self.pubdate,
+            doi,
+            source: "pubmed".to_string(),
+        }
+    }
+}
+
+impl PaperSource for PubMedSource {
+    fn name(&self) -> &'static str {
+        "pubmed"
+    }
+
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<Paper>, ArxivError> {
+        let client = reqwest::Client::new();
+
+        let search = client
+            .get(format!("{}/esearch.fcgi", self.base_url))
+            .query(&[
+                ("db", "pubmed".to_string()),
+                ("term", query.to_string()),
+                ("retmax", max_results.to_string()),
+                ("retmode", "json".to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let ids = serde_json::from_str::<ESearchResponse>(&search)?
+            .esearchresult
+            .idlist;
+        if ids.is_empty() {
+            return Err(ArxivError::NoResults);
+        }
+
+        let summary = client
+            .get(format!("{}/esummary.fcgi", self.base_url))
+            .query(&[
+                ("db", "pubmed".to_string()),
+                ("id", ids.join(",")),
+                ("retmode", "json".to_string()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let mut result = serde_json::from_str::<ESummaryResponse>(&summary)?.result;
+
+        // keep the relevance order from esearch
+        let papers = result
+            .uids
+            .iter()
+            .filter_map(|uid| result.documents.remove(uid))
+            .map(serde_json::from_value::<PubMedSummary>)
+            .map(|summary| summary.map(PubMedSummary::into_paper))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if papers.is_empty() {
+            return Err(ArxivError::NoResults);
+        }
+        Ok(papers)
+    }
+}
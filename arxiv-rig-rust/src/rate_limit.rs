@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// buckets are only cleaned up once the map grows past this many clients
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket rate limiter keyed by client (API key hash or IP address).
+///
+/// Each client may burst up to `requests_per_minute` requests, after which
+/// tokens are refilled evenly over the minute.
+pub(crate) struct RateLimiter {
+    capacity: f64,
+    refill_per_second: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// A limiter allowing `requests_per_minute` requests per client; `0` disables limiting.
+    pub(crate) fn per_minute(requests_per_minute: u32) -> Self {
+        Self {
+            capacity: f64::from(requests_per_minute),
+            refill_per_second: f64::from(requests_per_minute) / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token for `client`, or returns how long to wait before retrying.
+    pub(crate) fn check(&self, client: &str) -> Result<(), Duration> {
+        self.check_at(client, Instant::now())
+    }
+
+    pub(crate) fn check_at(&self, client: &str, now: Instant) -> Result<(), Duration> {
+        if self.capacity == 0.0 {
+            return Ok(());
+        }
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            // a bucket that would be full again carries no state worth keeping
+            let full_after = Duration::from_secs_f64(self.capacity / self.refill_per_second);
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated) < full_after);
+        }
+
+        let bucket = buckets.entry(client.to_owned()).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - bucket.tokens;
+            Err(Duration::from_secs_f64(missing / self.refill_per_second))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked(limiter: &RateLimiter) -> usize {
+        limiter.buckets.lock().unwrap().len()
+    }
+
+    #[test]
+    fn clients_can_burst_up_to_the_limit() {
+        let limiter = RateLimiter::per_minute(3);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.check_at("a", now).is_ok());
+        }
+        assert!(limiter.check_at("a", now).is_err());
+        // other clients have their own bucket
+        assert!(limiter.check_at("b", now).is_ok());
+    }
+
+    #[test]
+    fn retry_after_is_the_time_until_the_next_token() {
+        // one token every 20 seconds
+        let limiter = RateLimiter::per_minute(3);
+        let now = Instant::now();
+        for _ in 0..3 {
+            limiter.check_at("a", now).unwrap();
+        }
+        assert_eq!(limiter.check_at("a", now), Err(Duration::from_secs(20)));
+
+        // half a token refilled, half still missing
+        let later = now + Duration::from_secs(10);
+        let retry_after = limiter.check_at("a", later).unwrap_err();
+        assert!((retry_after.as_secs_f64() - 10.0).abs() < 1e-6, "{retry_after:?}");
+    }
+
+    #[test]
+    fn tokens_refill_evenly_up_to_the_capacity() {
+        let limiter = RateLimiter::per_minute(60);
+        let start = Instant::now();
+        for _ in 0..60 {
+            limiter.check_at("a", start).unwrap();
+        }
+        assert!(limiter.check_at("a", start).is_err());
+
+        // one token a second
+        let later = start + Duration::from_secs(2);
+        assert!(limiter.check_at("a", later).is_ok());
+        assert!(limiter.check_at("a", later).is_ok());
+        assert!(limiter.check_at("a", later).is_err());
+
+        // a long pause refills the bucket, but no further than the capacity
+        let much_later = later + Duration::from_secs(3600);
+        for _ in 0..60 {
+            limiter.check_at("a", much_later).unwrap();
+        }
+        assert!(limiter.check_at("a", much_later).is_err());
+    }
+
+    #[test]
+    fn zero_disables_limiting() {
+        let limiter = RateLimiter::per_minute(0);
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert!(limiter.check_at("a", now).is_ok());
+        }
+        assert_eq!(tracked(&limiter), 0);
+    }
+
+    #[test]
+    fn idle_clients_are_evicted_once_the_map_is_full() {
+        let limiter = RateLimiter::per_minute(60);
+        let start = Instant::now();
+        for client in 0..MAX_TRACKED_CLIENTS - 1 {
+            limiter.check_at(&client.to_string(), start).unwrap();
+        }
+        // still active when the map fills up
+        let active = start + Duration::from_secs(30);
+        limiter.check_at("active", active).unwrap();
+        assert_eq!(tracked(&limiter), MAX_TRACKED_CLIENTS);
+
+        // a minute after the start the idle buckets are full again and go
+        let now = start + Duration::from_secs(61);
+        limiter.check_at("new", now).unwrap();
+        assert_eq!(tracked(&limiter), 2);
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets.contains_key("active"));
+        assert!(buckets.contains_key("new"));
+    }
+
+    #[test]
+    fn clocks_going_backwards_do_not_panic() {
+        let limiter = RateLimiter::per_minute(1);
+        let now = Instant::now() + Duration::from_secs(10);
+        limiter.check_at("a", now).unwrap();
+        assert!(limiter.check_at("a", now - Duration::from_secs(5)).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use rig::embeddings::{EmbeddingError, EmbeddingModel};
+use rig::providers::openai;
+
+use crate::model::Paper;
+use crate::paper_index::{IndexedPaper, PaperIndex, PaperIndexError, SimilarPaper};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecommendError {
+    #[error("Embedding error: {0}")]
+    Embedding(#[from] EmbeddingError),
+    #[error(transparent)]
+    Index(#[from] PaperIndexError),
+    #[error("Paper {0} hasn't been indexed yet")]
+    UnknownPaper(String),
+}
+
+/// Embeds every paper the server fetches (title + abstract) into the paper index
+/// and answers "more like this" queries from it.
+pub(crate) struct PaperRecommender {
+    model: openai::EmbeddingModel,
+    index: PaperIndex,
+}
+
+impl PaperRecommender {
+    pub(crate) fn new(model: openai::EmbeddingModel, index: PaperIndex) -> Self {
+        Self { model, index }
+    }
+
+    /// Adds papers that aren't indexed yet. Papers without an id can't be looked
+    /// up again, so they are skipped.
+    pub(crate) async fn add_papers(&self, papers: Vec<Paper>) -> Result<(), RecommendError> {
+        let mut new_papers = Vec::new();
+        for paper in papers {
+            if !paper.id.is_empty()
+                && !new_papers.iter().any(|p: &Paper| p.id == paper.id)
+                && !self.index.contains(&paper.id).await?
+            {
+                new_papers.push(paper);
+            }
+        }
+        if new_papers.is_empty() {
+            return Ok(());
+        }
+
+        let texts: Vec<String> = new_papers
+            .iter()
+            .map(|paper| format!("{}\n\n{}", paper.title, paper.abstract_text))
+            .collect();
+        let embeddings = self.model.embed_texts(texts).await?;
+
+        let indexed = new_papers
+            .into_iter()
+            .zip(embeddings)
+            .map(|(paper, embedding)| IndexedPaper {
+                paper,
+                embedding: embedding.vec,
+            })
+            .collect::<Vec<_>>();
+        tracing::info!("indexed {} new papers", indexed.len());
+        self.index.insert(indexed).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn similar(
+        &self,
+        id: &str,
+        limit: usize,
+    ) -> Result<Vec<SimilarPaper>, RecommendError> {
+        self.index
+            .similar_to(id, limit)
+            .await?
+            .ok_or_else(|| RecommendError::UnknownPaper(id.to_owned()))
+    }
+}
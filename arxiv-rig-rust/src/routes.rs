@@ -1,46 +1,212 @@
+use std::future::IntoFuture;
 use std::sync::Arc;
-use axum::extract::State;
+use std::time::Instant;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
 use axum::Json;
-use axum::response::{Html, IntoResponse};
+use axum::response::{Html, IntoResponse, Response};
 use rig::providers::openai::GPT_4;
 use rig::client::CompletionClient;
 use rig::completion::Prompt;
-use crate::model::{ArxivSearchTool, AppState, Paper, SearchRequest};
+use tracing::Instrument;
+use crate::model::{AppState, Paper, SearchRequest, SearchResponse, SimilarQuery};
 use crate::error::AppError;
-use crate::util;
+use crate::paper_index::SimilarPaper;
+use crate::recommend::RecommendError;
+use crate::session;
+use crate::similar_papers_tool::{self, SimilarPapersTool};
 
 
+/// Sends a message to the research assistant. Messages with the same session id
+/// form one conversation, so follow-ups can refer to earlier results.
+#[utoipa::path(
+    post,
+    path = "/api/search",
+    tag = "search",
+    request_body = SearchRequest,
+    params(
+        ("x-session-id" = Option<String>, Header, description = "Conversation to continue. A new one is started when missing, unknown or expired."),
+    ),
+    responses(
+        (status = 200, description = "Rendered results, or papers as JSON when the request accepts `application/json`",
+            headers(("x-session-id" = String, description = "Session id to send with follow-up messages")),
+            content(
+                (String = "text/html"),
+                (SearchResponse = "application/json"),
+            )),
+        (status = 401, description = "Missing or invalid API key"),
+        (status = 429, description = "Rate limit exceeded",
+            headers(("retry-after" = u64, description = "Seconds until the next request is allowed"))),
+        (status = 500, description = "The search or the LLM call failed", body = String),
+    ),
+    security(("api_key" = []), ("bearer" = [])),
+)]
 pub(crate) async fn search_papers(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<SearchRequest>,
-) -> Result<impl IntoResponse, AppError> {
-    let paper_agent = state.openai_client
+) -> Result<Response, AppError> {
+    let requested_id = session::session_id_from_headers(&headers);
+    let (session_id, session) = state.sessions.resume_or_start(requested_id.as_deref()).await;
+    // hold the session for the whole turn so concurrent messages in the
+    // same conversation can't interleave their history
+    let mut session = session.lock().await;
+
+    // papers from earlier turns are passed as context as well, since older
+    // tool results may already have been trimmed from the history
+    let retrieved_papers = serde_json::to_string(&session.papers)?;
+
+    let mut paper_agent = state.openai_client
         .agent(GPT_4)
         .preamble(
-            "You are a helpful research assistant that can search and analyze academic papers from arXiv. \
-             When asked about a research topic, use the search_arxiv tool to find relevant papers and \
-             return only the raw JSON response from the tool, with no extra commentary or formatting."
+            "You are a helpful research assistant that can search and analyze academic papers from arXiv, \
+             PubMed and Crossref. When asked about a research topic, use the search_papers tool to find \
+             relevant papers, picking the sources that fit the topic (PubMed for biomedical work), and \
+             return only the raw JSON response from the tool, with no extra commentary or formatting. \
+             Follow-up questions may refer to papers found earlier in the conversation (for example \
+             \"only the ones from 2023\"). Answer those from the previously retrieved papers, using their \
+             `published` field for dates, and only search again when new papers are needed. \
+             When asked for papers like a previous one (\"more like this\"), call find_similar_papers \
+             with that paper's `id`. \
+             Always reply with a raw JSON array of papers in the same format as the tool output."
         )
-        .tool(ArxivSearchTool)
-        .build();
+        .context(&format!("Papers already retrieved in this conversation: {retrieved_papers}"))
+        .tool(state.paper_search.clone());
+    if let Some(recommender) = &state.recommender {
+        paper_agent = paper_agent.tool(SimilarPapersTool {
+            recommender: recommender.clone(),
+        });
+    }
+    let paper_agent = paper_agent.build();
 
+    let llm_start = Instant::now();
     let response = paper_agent
         .prompt(&request.query)
-        .await?;
+        .with_history(&mut session.history)
+        .into_future()
+        .instrument(tracing::info_span!("llm_call", model = GPT_4, session_id = %session_id))
+        .await;
+    metrics::histogram!("llm_request_duration_seconds", "model" => GPT_4)
+        .record(llm_start.elapsed().as_secs_f64());
+    if response.is_err() {
+        metrics::counter!("llm_errors_total", "model" => GPT_4).increment(1);
+    }
+    let response = response?;
+    session.trim_history();
 
-    // return the response as HTML
-    // note that if you want to return just a JSON response
-    // you can return `Ok(axum::Json(papers))`
+    // the browser gets rendered HTML, API clients can ask for JSON instead
     let papers: Result<Vec<Paper>, _> = serde_json::from_str(&response);
+    if let Ok(papers) = &papers {
+        session.remember_papers(papers);
+    }
+
+    let body = if accepts_json(&headers) {
+        let result = match papers {
+            Ok(papers) => SearchResponse { papers, message: None },
+            Err(_) => SearchResponse { papers: Vec::new(), message: Some(response) },
+        };
+        Json(result).into_response()
+    } else {
+        let html = match papers {
+            Ok(papers) => state.templates.render(request.view, &papers)?,
+            Err(_) => format!(
+                "<div style=\"white-space: pre-wrap; font-family: monospace;\">{}</div>",
+                html_escape::encode_text(&response)
+            ),
+        };
+        Html(html).into_response()
+    };
+
+    Ok((
+        [
+            (header::SET_COOKIE, session::session_cookie(&session_id)),
+            (header::HeaderName::from_static(session::SESSION_HEADER), session_id),
+        ],
+        body,
+    )
+        .into_response())
+}
 
-    let html = match papers {
-        Ok(papers) => util::format_papers_as_html(&papers)?,
-        Err(_) => format!(
-            "<div style=\"white-space: pre-wrap; font-family: monospace;\">{}</div>",
-            html_escape::encode_text(&response)
-        ),
+fn accepts_json(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"))
+}
+
+/// Forgets the conversation so the next message starts a fresh session.
+#[utoipa::path(
+    delete,
+    path = "/api/session",
+    tag = "search",
+    params(("x-session-id" = Option<String>, Header, description = "Conversation to forget")),
+    responses(
+        (status = 204, description = "The session was removed, or didn't exist"),
+        (status = 401, description = "Missing or invalid API key"),
+    ),
+    security(("api_key" = []), ("bearer" = [])),
+)]
+pub(crate) async fn reset_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Some(session_id) = session::session_id_from_headers(&headers) {
+        state.sessions.remove(&session_id).await;
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// Nearest neighbours of an already indexed paper. Ids contain ':' and, for DOIs,
+/// '/', so clients should percent-encode them.
+#[utoipa::path(
+    get,
+    path = "/api/papers/{id}/similar",
+    tag = "papers",
+    params(
+        ("id" = String, Path, description = "Paper id, e.g. `arxiv:1706.03762`"),
+        SimilarQuery,
+    ),
+    responses(
+        (status = 200, description = "Related papers, most similar first", body = Vec<SimilarPaper>),
+        (status = 404, description = "The paper hasn't been indexed, or recommendations are disabled", body = String),
+        (status = 401, description = "Missing or invalid API key"),
+    ),
+    security(("api_key" = []), ("bearer" = [])),
+)]
+pub(crate) async fn similar_papers(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<SimilarQuery>,
+) -> Result<Response, AppError> {
+    let Some(recommender) = &state.recommender else {
+        return Ok((StatusCode::NOT_FOUND, "Recommendations are disabled").into_response());
     };
+    let limit = query
+        .limit
+        .unwrap_or(similar_papers_tool::DEFAULT_LIMIT)
+        .clamp(1, similar_papers_tool::MAX_LIMIT);
+
+    match recommender.similar(&id, limit).await {
+        Ok(papers) => Ok(Json(papers).into_response()),
+        Err(e @ RecommendError::UnknownPaper(_)) => {
+            Ok((StatusCode::NOT_FOUND, e.to_string()).into_response())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
 
-    Ok(Html(html))
-   // Ok(axum::Json(response.clone()))
-}
\ No newline at end of file
+/// Prometheus scrape endpoint. Behind the API key like the `/api` routes, so
+/// scrapers have to send one when keys are configured.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "operations",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key"),
+    ),
+    security(("api_key" = []), ("bearer" = [])),
+)]
+pub(crate) async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    state.metrics.render()
+}
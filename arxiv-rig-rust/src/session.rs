@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::http::HeaderMap;
+use rig::completion::Message;
+use rig::message::UserContent;
+use tokio::sync::Mutex;
+
+use crate::model::Paper;
+
+pub const SESSION_HEADER: &str = "x-session-id";
+pub(crate) const SESSION_COOKIE: &str = "arxiv_session";
+
+// Sessions that haven't been used for this long are dropped
+const SESSION_TTL: Duration = Duration::from_secs(30 * 60);
+// Upper bound on concurrently tracked sessions; the least recently used one is evicted first
+const MAX_SESSIONS: usize = 1000;
+// Number of rig messages (prompts, tool calls, tool results, replies) kept per session
+const MAX_HISTORY_MESSAGES: usize = 40;
+// Number of distinct papers remembered per session
+const MAX_PAPERS: usize = 50;
+
+/// A single research conversation: the rig message history plus every paper
+/// retrieved so far, so follow-up questions can refer back to earlier results.
+#[derive(Default)]
+pub(crate) struct Session {
+    pub(crate) history: Vec<Message>,
+    pub(crate) papers: Vec<Paper>,
+}
+
+impl Session {
+    /// Adds newly returned papers, skipping ones we already have (matched by URL)
+    /// and dropping the oldest ones once the limit is reached.
+    pub(crate) fn remember_papers(&mut self, papers: &[Paper]) {
+        for paper in papers {
+            if !self.papers.iter().any(|p| p.url == paper.url) {
+                self.papers.push(paper.clone());
+            }
+        }
+        if self.papers.len() > MAX_PAPERS {
+            let excess = self.papers.len() - MAX_PAPERS;
+            self.papers.drain(..excess);
+        }
+    }
+
+    /// Trims the history to the message limit. Whole turns are removed from the front
+    /// so that a tool call is never separated from its tool result.
+    pub(crate) fn trim_history(&mut self) {
+        if self.history.len() <= MAX_HISTORY_MESSAGES {
+            return;
+        }
+        let excess = self.history.len() - MAX_HISTORY_MESSAGES;
+        let cut = self
+            .history
+            .iter()
+            .enumerate()
+            .skip(excess)
+            .find(|(_, message)| is_user_prompt(message))
+            .map(|(i, _)| i)
+            .unwrap_or(self.history.len());
+        self.history.drain(..cut);
+    }
+}
+
+// a user message carrying text starts a new turn; tool results are also sent
+// as user messages but belong to the turn before them
+fn is_user_prompt(message: &Message) -> bool {
+    match message {
+        Message::User { content } => content
+            .iter()
+            .any(|c| matches!(c, UserContent::Text(_))),
+        _ => false,
+    }
+}
+
+struct SessionEntry {
+    session: Arc<Mutex<Session>>,
+    last_seen: Instant,
+}
+
+/// In-memory session storage shared by all handlers.
+///
+/// Each session sits behind its own lock, so concurrent requests for the same
+/// session are handled one after another while other sessions are unaffected.
+pub(crate) struct SessionStore {
+    sessions: Mutex<HashMap<String, SessionEntry>>,
+    ttl: Duration,
+    max_sessions: usize,
+}
+
+impl Default for SessionStore {
+    fn default() -> Self {
+        Self::new(SESSION_TTL, MAX_SESSIONS)
+    }
+}
+
+impl SessionStore {
+    pub(crate) fn new(ttl: Duration, max_sessions: usize) -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            ttl,
+            max_sessions,
+        }
+    }
+
+    /// Returns the session with the given id and that id. Ids the store didn't
+    /// issue, or whose session has expired, start a new session under a fresh
+    /// id, so clients can't pick their own.
+    pub(crate) async fn resume_or_start(&self, id: Option<&str>) -> (String, Arc<Mutex<Session>>) {
+        let mut sessions = self.sessions.lock().await;
+        let now = Instant::now();
+        sessions.retain(|_, entry| now.duration_since(entry.last_seen) < self.ttl);
+
+        if let Some(id) = id {
+            if let Some(entry) = sessions.get_mut(id) {
+                entry.last_seen = now;
+                return (id.to_owned(), entry.session.clone());
+            }
+        }
+
+        if sessions.len() >= self.max_sessions {
+            let oldest = sessions
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_seen)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                sessions.remove(&oldest);
+            }
+        }
+
+        let id = new_session_id();
+        let session = Arc::new(Mutex::new(Session::default()));
+        sessions.insert(
+            id.clone(),
+            SessionEntry {
+                session: session.clone(),
+                last_seen: now,
+            },
+        );
+        (id, session)
+    }
+
+    pub(crate) async fn remove(&self, id: &str) {
+        self.sessions.lock().await.remove(id);
+    }
+}
+
+/// Reads the session id from the `x-session-id` header, falling back to the session cookie.
+pub(crate) fn session_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    let from_header = headers
+        .get(SESSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| is_valid_session_id(id));
+    if let Some(id) = from_header {
+        return Some(id.to_owned());
+    }
+
+    headers
+        .get_all(axum::http::header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, id)| *name == SESSION_COOKIE && is_valid_session_id(id))
+        .map(|(_, id)| id.to_owned())
+}
+
+fn new_session_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+pub(crate) fn session_cookie(id: &str) -> String {
+    format!("{SESSION_COOKIE}={id}; Path=/; HttpOnly; SameSite=Lax")
+}
+
+// session ids are only ever generated by us, so anything that isn't a short
+// alphanumeric token is treated as missing without looking it up
+fn is_valid_session_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use rig::message::{AssistantContent, ToolResultContent};
+    use rig::OneOrMany;
+
+    use super::*;
+
+    fn paper(url: &str) -> Paper {
+        Paper {
+            url: url.to_owned(),
+            ..Paper::new()
+        }
+    }
+
+    // a prompt, a tool call, its result and the reply: four messages
+    fn turn(n: usize) -> Vec<Message> {
+        vec![
+            Message::user(format!("question {n}")),
+            Message::Assistant {
+                content: OneOrMany::one(AssistantContent::tool_call(
+                    format!("call-{n}"),
+                    "search_papers",
+                    serde_json::json!({ "query": "transformers" }),
+                )),
+            },
+            Message::User {
+                content: OneOrMany::one(UserContent::tool_result(
+                    format!("call-{n}"),
+                    OneOrMany::one(ToolResultContent::text("[]")),
+                )),
+            },
+            Message::assistant(format!("answer {n}")),
+        ]
+    }
+
+    #[test]
+    fn remember_papers_skips_known_urls() {
+        let mut session = Session::default();
+        session.remember_papers(&[paper("a"), paper("b")]);
+        session.remember_papers(&[paper("b"), paper("c")]);
+
+        let urls: Vec<_> = session.papers.iter().map(|p| p.url.as_str()).collect();
+        assert_eq!(urls, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn remember_papers_drops_the_oldest_past_the_limit() {
+        let mut session = Session::default();
+        let papers: Vec<_> = (0..MAX_PAPERS + 5).map(|i| paper(&i.to_string())).collect();
+        session.remember_papers(&papers);
+
+        assert_eq!(session.papers.len(), MAX_PAPERS);
+        assert_eq!(session.papers[0].url, "5");
+        assert_eq!(session.papers[MAX_PAPERS - 1].url, (MAX_PAPERS + 4).to_string());
+    }
+
+    #[test]
+    fn trim_history_leaves_short_histories_alone() {
+        let mut session = Session::default();
+        session.history = turn(1);
+        session.trim_history();
+        assert_eq!(session.history.len(), 4);
+    }
+
+    #[test]
+    fn trim_history_removes_whole_turns() {
+        let mut session = Session::default();
+        // 11 turns of 4 messages is 44, so 4 have to go
+        session.history = (0..11).flat_map(turn).collect();
+        session.trim_history();
+        assert_eq!(session.history.len(), MAX_HISTORY_MESSAGES);
+        assert!(is_user_prompt(&session.history[0]));
+
+        // one message over the limit: the cut can't split a turn, so the
+        // whole first turn goes
+        let mut session = Session::default();
+        session.history = (0..10).flat_map(turn).collect();
+        session.history.push(Message::user("question 10"));
+        session.trim_history();
+        assert_eq!(session.history.len(), MAX_HISTORY_MESSAGES - 3);
+        assert!(is_user_prompt(&session.history[0]));
+    }
+
+    #[test]
+    fn tool_results_are_not_user_prompts() {
+        let messages = turn(1);
+        assert!(is_user_prompt(&messages[0]));
+        assert!(!is_user_prompt(&messages[1]));
+        assert!(!is_user_prompt(&messages[2]));
+        assert!(!is_user_prompt(&messages[3]));
+    }
+
+    #[tokio::test]
+    async fn issued_ids_resume_their_session() {
+        let store = SessionStore::default();
+        let (id, session) = store.resume_or_start(None).await;
+        session.lock().await.remember_papers(&[paper("a")]);
+
+        let (resumed_id, resumed) = store.resume_or_start(Some(&id)).await;
+        assert_eq!(resumed_id, id);
+        assert_eq!(resumed.lock().await.papers.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn unknown_ids_get_a_fresh_session() {
+        let store = SessionStore::default();
+        let (id, session) = store.resume_or_start(Some("chosen-by-the-client")).await;
+        assert_ne!(id, "chosen-by-the-client");
+        assert!(session.lock().await.history.is_empty());
+
+        let (again, _) = store.resume_or_start(Some("chosen-by-the-client")).await;
+        assert_ne!(again, "chosen-by-the-client");
+        assert_ne!(again, id);
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_are_dropped() {
+        let store = SessionStore::new(Duration::from_millis(20), MAX_SESSIONS);
+        let (id, _) = store.resume_or_start(None).await;
+        tokio::time::sleep(Duration::from_millis(40)).await;
+
+        let (new_id, _) = store.resume_or_start(Some(&id)).await;
+        assert_ne!(new_id, id);
+        assert_eq!(store.sessions.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_session_is_evicted() {
+        let store = SessionStore::new(SESSION_TTL, 2);
+        let (first, _) = store.resume_or_start(None).await;
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        let (second, _) = store.resume_or_start(None).await;
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        // using the first one again makes the second the least recently used
+        store.resume_or_start(Some(&first)).await;
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        store.resume_or_start(None).await;
+
+        let sessions = store.sessions.lock().await;
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.contains_key(&first));
+        assert!(!sessions.contains_key(&second));
+    }
+
+    #[tokio::test]
+    async fn store_holds_at_most_max_sessions() {
+        let store = SessionStore::default();
+        for _ in 0..MAX_SESSIONS + 10 {
+            store.resume_or_start(None).await;
+        }
+        assert_eq!(store.sessions.lock().await.len(), MAX_SESSIONS);
+    }
+
+    #[tokio::test]
+    async fn removed_sessions_start_over() {
+        let store = SessionStore::default();
+        let (id, _) = store.resume_or_start(None).await;
+        store.remove(&id).await;
+        let (new_id, _) = store.resume_or_start(Some(&id)).await;
+        assert_ne!(new_id, id);
+    }
+
+    #[test]
+    fn session_id_prefers_the_header_over_the_cookie() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::COOKIE,
+            "theme=dark; arxiv_session=from-cookie".parse().unwrap(),
+        );
+        assert_eq!(session_id_from_headers(&headers).as_deref(), Some("from-cookie"));
+
+        headers.insert(SESSION_HEADER, "from-header".parse().unwrap());
+        assert_eq!(session_id_from_headers(&headers).as_deref(), Some("from-header"));
+
+        headers.insert(SESSION_HEADER, "not valid!".parse().unwrap());
+        assert_eq!(session_id_from_headers(&headers).as_deref(), Some("from-cookie"));
+    }
+}
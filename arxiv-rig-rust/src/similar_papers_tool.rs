@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde_json::json;
+
+use crate::paper_index::SimilarPaper;
+use crate::recommend::{PaperRecommender, RecommendError};
+
+pub(crate) const DEFAULT_LIMIT: usize = 5;
+pub(crate) const MAX_LIMIT: usize = 50;
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub struct SimilarPapersArgs {
+    pub(crate) paper_id: String,
+    pub(crate) limit: Option<usize>,
+}
+
+/// Agent tool returning the papers closest to a previously retrieved paper.
+#[derive(Clone)]
+pub(crate) struct SimilarPapersTool {
+    pub(crate) recommender: Arc<PaperRecommender>,
+}
+
+impl Tool for SimilarPapersTool {
+    const NAME: &'static str = "find_similar_papers";
+    type Error = RecommendError;
+    type Args = SimilarPapersArgs;
+    type Output = Vec<SimilarPaper>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Find papers similar to one that was already retrieved (\"more like this\"), \
+                using the index of every paper the server has seen. Results include a similarity score."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "paper_id": {
+                        "type": "string",
+                        "description": "The `id` field of a previously retrieved paper, e.g. \"arxiv:1706.03762\""
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of similar papers to return (default: 5, at most 50)"
+                    }
+                },
+                "required": ["paper_id"]
+            }),
+        }
+    }
+
+    #[tracing::instrument(name = "tool_call", skip(self), fields(tool = Self::NAME))]
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        self.recommender
+            .similar(
+                &args.paper_id,
+                args.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT),
+            )
+            .await
+    }
+}
@@ -0,0 +1,209 @@
+use std::time::Instant;
+
+use axum::body::Body;
+use axum::extract::{MatchedPath, Request};
+use axum::http::Response;
+use axum::middleware::Next;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Span, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const LATENCY_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Installs the global tracing subscriber: JSON logs filtered by `RUST_LOG`
+/// (default `info`), plus a layer that turns rig's token usage events into metrics.
+pub fn init_tracing() {
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_filter(env_filter),
+        )
+        // filtered separately so token usage is counted whatever RUST_LOG says
+        .with(TokenUsageLayer.with_filter(Targets::new().with_target("rig", Level::INFO)))
+        .init();
+}
+
+/// Installs the Prometheus recorder behind the `metrics` macros and returns the
+/// handle used to render `/metrics`.
+pub fn init_metrics() -> Result<PrometheusHandle, Box<dyn std::error::Error>> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Suffix("duration_seconds".to_string()),
+            LATENCY_BUCKETS,
+        )?
+        .install_recorder()?;
+    Ok(handle)
+}
+
+/// Records request count and latency per route, method and status.
+pub(crate) async fn track_metrics(request: Request, next: Next) -> Response<Body> {
+    let start = Instant::now();
+    // use the route template rather than the raw URI to keep label cardinality bounded
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| request.uri().path().to_owned());
+    let method = request.method().to_string();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    let labels = [("method", method), ("path", path), ("status", status)];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels)
+        .record(start.elapsed().as_secs_f64());
+
+    response
+}
+
+/// Root span for every HTTP request, tagged with the id set by `SetRequestIdLayer`.
+pub(crate) fn make_request_span(request: &Request<Body>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "http_request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id = %request_id,
+    )
+}
+
+/// Counts LLM token usage.
+///
+/// rig 0.13 doesn't return usage from `Prompt::prompt`, but the OpenAI provider logs
+/// it on every completion (`OpenAI completion token usage: "Prompt tokens: 12 Total tokens: 40"`)
+/// so we pick it up from there. Usage messages that no longer parse, e.g. after a
+/// rig upgrade changed the wording, are counted in `llm_token_usage_unparsed_total`
+/// rather than silently dropped.
+struct TokenUsageLayer;
+
+impl<S: Subscriber> Layer<S> for TokenUsageLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor(None);
+        event.record(&mut visitor);
+        let Some(message) = visitor.0 else {
+            return;
+        };
+        if !message.contains("token usage") {
+            return;
+        }
+
+        match parse_token_usage(&message) {
+            Some(usage) => {
+                metrics::counter!("llm_tokens_total", "kind" => "prompt").increment(usage.prompt);
+                metrics::counter!("llm_tokens_total", "kind" => "completion").increment(usage.completion);
+            }
+            // "N/A" when the provider sent no usage at all
+            None if message.contains("N/A") => {}
+            None => metrics::counter!("llm_token_usage_unparsed_total").increment(1),
+        }
+    }
+}
+
+struct MessageVisitor(Option<String>);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = Some(format!("{value:?}"));
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct TokenUsage {
+    prompt: u64,
+    completion: u64,
+}
+
+// rig only logs prompt and total tokens; the completion is the difference
+fn parse_token_usage(message: &str) -> Option<TokenUsage> {
+    let prompt = number_after(message, "Prompt tokens: ")?;
+    let total = number_after(message, "Total tokens: ")?;
+    Some(TokenUsage {
+        prompt,
+        completion: total.saturating_sub(prompt),
+    })
+}
+
+fn number_after(text: &str, label: &str) -> Option<u64> {
+    let start = text.find(label)? + label.len();
+    text[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    use super::*;
+
+    // the line rig 0.13's OpenAI provider logs after every completion
+    const RIG_USAGE_MESSAGE: &str = "OpenAI completion token usage: \"Prompt tokens: 1523 Total tokens: 1687\"";
+
+    // sends `message` as a rig event through the layer and renders the metrics it recorded
+    fn record_rig_event(message: &str) -> String {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let subscriber = tracing_subscriber::registry()
+            .with(TokenUsageLayer.with_filter(Targets::new().with_target("rig", Level::INFO)));
+        metrics::with_local_recorder(&recorder, || {
+            tracing::subscriber::with_default(subscriber, || {
+                tracing::info!(target: "rig", "{}", message);
+            });
+        });
+        handle.render()
+    }
+
+    #[test]
+    fn parses_rigs_usage_message() {
+        assert_eq!(
+            parse_token_usage(RIG_USAGE_MESSAGE),
+            Some(TokenUsage { prompt: 1523, completion: 164 })
+        );
+        assert_eq!(parse_token_usage("OpenAI completion token usage: \"N/A\""), None);
+        // a total below the prompt can't make the completion negative
+        assert_eq!(
+            parse_token_usage("Prompt tokens: 10 Total tokens: 4"),
+            Some(TokenUsage { prompt: 10, completion: 0 })
+        );
+    }
+
+    #[test]
+    fn usage_events_become_token_counters() {
+        let metrics = record_rig_event(RIG_USAGE_MESSAGE);
+        assert!(metrics.contains("llm_tokens_total{kind=\"prompt\"} 1523"), "{metrics}");
+        assert!(metrics.contains("llm_tokens_total{kind=\"completion\"} 164"), "{metrics}");
+        assert!(!metrics.contains("llm_token_usage_unparsed_total"));
+    }
+
+    #[test]
+    fn reworded_usage_messages_are_counted_as_unparsed() {
+        let metrics = record_rig_event("OpenAI completion token usage: input=1523 output=164");
+        assert!(!metrics.contains("llm_tokens_total"));
+        assert!(metrics.contains("llm_token_usage_unparsed_total 1"), "{metrics}");
+
+        // no usage sent at all isn't a parsing problem
+        let metrics = record_rig_event("OpenAI completion token usage: \"N/A\"");
+        assert!(!metrics.contains("llm_token_usage_unparsed_total"));
+    }
+}
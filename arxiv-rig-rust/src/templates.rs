@@ -0,0 +1,217 @@
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use tera::{Context, Tera};
+
+use crate::model::Paper;
+
+/// The ways search results can be rendered. Each view is a Tera template
+/// named `<view>.html`.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize, utoipa::ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultView {
+    #[default]
+    Table,
+    Cards,
+    List,
+}
+
+impl ResultView {
+    const ALL: [ResultView; 3] = [ResultView::Table, ResultView::Cards, ResultView::List];
+
+    fn template_name(self) -> &'static str {
+        match self {
+            ResultView::Table => "table.html",
+            ResultView::Cards => "cards.html",
+            ResultView::List => "list.html",
+        }
+    }
+
+    // compiled into the binary so the server doesn't depend on its working directory
+    fn default_template(self) -> &'static str {
+        match self {
+            ResultView::Table => include_str!("../templates/table.html"),
+            ResultView::Cards => include_str!("../templates/cards.html"),
+            ResultView::List => include_str!("../templates/list.html"),
+        }
+    }
+}
+
+/// Result templates, compiled once at startup.
+///
+/// Templates are embedded in the binary. If an override directory is configured,
+/// any `<view>.html` found there replaces the embedded version. In dev mode the
+/// override directory is checked on every render and the templates are recompiled
+/// when a file has changed.
+pub(crate) struct Templates {
+    tera: RwLock<Tera>,
+    override_dir: Option<PathBuf>,
+    hot_reload: bool,
+    loaded_at: RwLock<Option<SystemTime>>,
+}
+
+impl Templates {
+    pub(crate) fn new(override_dir: Option<PathBuf>, hot_reload: bool) -> Result<Self, tera::Error> {
+        let tera = compile(override_dir.as_deref())?;
+        let loaded_at = override_dir.as_deref().and_then(latest_modification);
+        Ok(Self {
+            tera: RwLock::new(tera),
+            override_dir,
+            hot_reload,
+            loaded_at: RwLock::new(loaded_at),
+        })
+    }
+
+    pub(crate) fn render(&self, view: ResultView, papers: &[Paper]) -> Result<String, tera::Error> {
+        if self.hot_reload {
+            self.reload_if_changed()?;
+        }
+
+        let mut context = Context::new();
+        context.insert("papers", papers);
+        self.tera
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .render(view.template_name(), &context)
+    }
+
+    fn reload_if_changed(&self) -> Result<(), tera::Error> {
+        let Some(dir) = self.override_dir.as_deref() else {
+            return Ok(());
+        };
+        let modified = latest_modification(dir);
+        if modified == *self.loaded_at.read().unwrap_or_else(|e| e.into_inner()) {
+            return Ok(());
+        }
+
+        tracing::info!("reloading result templates from {}", dir.display());
+        let tera = compile(Some(dir))?;
+        *self.tera.write().unwrap_or_else(|e| e.into_inner()) = tera;
+        *self.loaded_at.write().unwrap_or_else(|e| e.into_inner()) = modified;
+        Ok(())
+    }
+}
+
+fn compile(override_dir: Option<&Path>) -> Result<Tera, tera::Error> {
+    let mut tera = Tera::default();
+    for view in ResultView::ALL {
+        let name = view.template_name();
+        match override_dir.map(|dir| dir.join(name)).filter(|path| path.is_file()) {
+            Some(path) => tera.add_template_file(&path, Some(name))?,
+            None => tera.add_raw_template(name, view.default_template())?,
+        }
+    }
+    Ok(tera)
+}
+
+// newest modification time among the override templates, used to detect edits
+fn latest_modification(dir: &Path) -> Option<SystemTime> {
+    ResultView::ALL
+        .iter()
+        .filter_map(|view| std::fs::metadata(dir.join(view.template_name())).ok())
+        .filter_map(|metadata| metadata.modified().ok())
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use std::time::Duration;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("arxiv-templates-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // sets the mtime explicitly so edits are seen even on coarse-grained filesystems
+    fn write_template(dir: &Path, view: ResultView, body: &str, modified: SystemTime) {
+        let mut file = File::create(dir.join(view.template_name())).unwrap();
+        file.write_all(body.as_bytes()).unwrap();
+        file.set_modified(modified).unwrap();
+    }
+
+    fn papers() -> Vec<Paper> {
+        vec![Paper {
+            title: "Attention Is All You Need".to_string(),
+            ..Paper::new()
+        }]
+    }
+
+    #[test]
+    fn embedded_templates_are_used_without_an_override_dir() {
+        let templates = Templates::new(None, false).unwrap();
+
+        for view in ResultView::ALL {
+            let html = templates.render(view, &papers()).unwrap();
+            assert!(html.contains("Attention Is All You Need"), "{view:?}");
+        }
+    }
+
+    #[test]
+    fn override_files_replace_only_their_own_view() {
+        let dir = temp_dir();
+        write_template(&dir, ResultView::Cards, "cards: {{ papers | length }}", SystemTime::now());
+
+        let templates = Templates::new(Some(dir.clone()), false).unwrap();
+
+        assert_eq!(templates.render(ResultView::Cards, &papers()).unwrap(), "cards: 1");
+        let table = templates.render(ResultView::Table, &papers()).unwrap();
+        assert!(table.contains("papers-table"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_missing_override_dir_falls_back_to_the_embedded_templates() {
+        let dir = std::env::temp_dir().join(format!("arxiv-templates-{}", uuid::Uuid::new_v4()));
+
+        let templates = Templates::new(Some(dir), true).unwrap();
+
+        let table = templates.render(ResultView::Table, &papers()).unwrap();
+        assert!(table.contains("papers-table"));
+    }
+
+    #[test]
+    fn hot_reload_picks_up_edited_templates() {
+        let dir = temp_dir();
+        let loaded = SystemTime::now() - Duration::from_secs(60);
+        write_template(&dir, ResultView::List, "v1", loaded);
+        let templates = Templates::new(Some(dir.clone()), true).unwrap();
+        assert_eq!(templates.render(ResultView::List, &papers()).unwrap(), "v1");
+
+        write_template(&dir, ResultView::List, "v2", loaded + Duration::from_secs(10));
+
+        assert_eq!(templates.render(ResultView::List, &papers()).unwrap(), "v2");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn hot_reload_picks_up_newly_added_templates() {
+        let dir = temp_dir();
+        let templates = Templates::new(Some(dir.clone()), true).unwrap();
+        assert!(templates.render(ResultView::Table, &papers()).unwrap().contains("papers-table"));
+
+        write_template(&dir, ResultView::Table, "custom", SystemTime::now());
+
+        assert_eq!(templates.render(ResultView::Table, &papers()).unwrap(), "custom");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn templates_are_not_reloaded_without_hot_reload() {
+        let dir = temp_dir();
+        let loaded = SystemTime::now() - Duration::from_secs(60);
+        write_template(&dir, ResultView::List, "v1", loaded);
+        let templates = Templates::new(Some(dir.clone()), false).unwrap();
+
+        write_template(&dir, ResultView::List, "v2", loaded + Duration::from_secs(10));
+
+        assert_eq!(templates.render(ResultView::List, &papers()).unwrap(), "v1");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
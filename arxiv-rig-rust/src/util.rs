@@ -1,5 +1,3 @@
-use crate::model::Paper;
-
 pub(crate) fn convert_pdf_url(url: &str) -> String {
     if url.contains("arxiv.org/abs/") {
         // Convert abstract URL to PDF URL
@@ -14,14 +12,3 @@ pub(crate) fn convert_pdf_url(url: &str) -> String {
         url.replace("http://", "https://")
     }
 }
-
-// HTML formatting function for papers
-pub fn format_papers_as_html(papers: &[Paper]) -> Result<String, anyhow::Error> {
-    let tpl = std::fs::read_to_string("static/table.html")?;
-    let mut context = tera::Context::new();
-    context.insert("papers", papers);
-
-    let result = tera::Tera::one_off(&tpl, &context, false)?;
-
-    Ok(result)
-}
\ No newline at end of file
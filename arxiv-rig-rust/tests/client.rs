@@ -0,0 +1,133 @@
+mod common;
+
+use std::collections::BTreeSet;
+
+use arxiv_client::{ArxivClient, ClientError, Paper, ResultView, SearchResponse, SimilarPaper};
+use arxiv_rig_rust::config::ServerConfig;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{body_text, search_script, TestApp};
+use serde_json::Value;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn client_search_returns_papers_and_keeps_the_session() {
+    let app = TestApp::start(search_script("transformers")).await;
+    let client = ArxivClient::new(app.serve().await);
+
+    let response = client.search("find papers about transformers").await.unwrap();
+
+    let titles: Vec<&str> = response.papers.iter().map(|paper| paper.title.as_str()).collect();
+    assert!(titles.contains(&"Attention Is All You Need"));
+    assert!(response.message.is_none());
+    assert!(client.session_id().is_some());
+
+    client.reset_session().await.unwrap();
+    assert!(client.session_id().is_none());
+}
+
+#[tokio::test]
+async fn client_reports_a_missing_api_key() {
+    let config = ServerConfig {
+        api_key_hashes: [arxiv_rig_rust::auth::hash_api_key("secret")].into(),
+        ..ServerConfig::default()
+    };
+    let app = TestApp::with_config(search_script("transformers"), config).await;
+    let client = ArxivClient::new(app.serve().await);
+
+    let result = client.search("find papers about transformers").await;
+
+    assert!(matches!(result, Err(ClientError::Unauthorized)));
+}
+
+#[tokio::test]
+async fn openapi_document_describes_the_api() {
+    let app = TestApp::start(Vec::new()).await;
+
+    let request = Request::get("/openapi.json").body(Body::empty()).unwrap();
+    let response = app.router.clone().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let spec: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    for path in ["/api/search", "/api/session", "/api/papers/{id}/similar"] {
+        assert!(spec["paths"].get(path).is_some(), "missing {path}");
+    }
+    assert!(spec["components"]["schemas"].get("Paper").is_some());
+}
+
+// property names of a schema, following `$ref`s and the `allOf` that `#[serde(flatten)]` produces
+fn schema_properties(spec: &Value, schema: &Value) -> BTreeSet<String> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/components/schemas/");
+        return schema_properties(spec, &spec["components"]["schemas"][name]);
+    }
+    let mut properties: BTreeSet<String> = schema["properties"]
+        .as_object()
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default();
+    for part in schema["allOf"].as_array().into_iter().flatten() {
+        properties.extend(schema_properties(spec, part));
+    }
+    properties
+}
+
+fn json_keys(value: &impl serde::Serialize) -> BTreeSet<String> {
+    serde_json::to_value(value).unwrap().as_object().unwrap().keys().cloned().collect()
+}
+
+fn server_paper() -> arxiv_rig_rust::model::Paper {
+    arxiv_rig_rust::model::Paper {
+        id: "arxiv:1706.03762".to_string(),
+        title: "Attention Is All You Need".to_string(),
+        authors: vec!["Ashish Vaswani".to_string()],
+        abstract_text: "The dominant sequence transduction models...".to_string(),
+        url: "http://arxiv.org/abs/1706.03762v7".to_string(),
+        categories: vec!["cs.CL".to_string()],
+        published: "2017-06-12".to_string(),
+        doi: Some("10.48550/arXiv.1706.03762".to_string()),
+        source: "arxiv".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn client_types_match_the_openapi_schemas() {
+    let app = TestApp::start(Vec::new()).await;
+    let request = Request::get("/openapi.json").body(Body::empty()).unwrap();
+    let response = app.router.clone().oneshot(request).await.unwrap();
+    let spec: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let schema = |name: &str| schema_properties(&spec, &spec["components"]["schemas"][name]);
+
+    // every optional field is filled in so that all of them are serialised
+    let paper: Paper = serde_json::from_value(serde_json::to_value(server_paper()).unwrap()).unwrap();
+    let response = SearchResponse {
+        papers: vec![paper.clone()],
+        message: Some("Here is what I found".to_string()),
+    };
+    let similar = SimilarPaper { score: 0.9, paper: paper.clone() };
+
+    assert_eq!(json_keys(&paper), schema("Paper"));
+    assert_eq!(json_keys(&response), schema("SearchResponse"));
+    assert_eq!(json_keys(&similar), schema("SimilarPaper"));
+    // arxiv_client's request body is private, so its fields are spelled out here
+    assert_eq!(schema("SearchRequest"), BTreeSet::from(["query".to_string(), "view".to_string()]));
+
+    let views: Vec<Value> = [ResultView::Table, ResultView::Cards, ResultView::List]
+        .iter()
+        .map(|view| serde_json::to_value(view).unwrap())
+        .collect();
+    assert_eq!(spec["components"]["schemas"]["ResultView"]["enum"], Value::Array(views));
+}
+
+#[test]
+fn server_responses_round_trip_through_the_client_types() {
+    let server = arxiv_rig_rust::model::SearchResponse {
+        papers: vec![server_paper()],
+        message: Some("Here is what I found".to_string()),
+    };
+    let json = serde_json::to_value(&server).unwrap();
+
+    let client: SearchResponse = serde_json::from_value(json.clone()).unwrap();
+
+    assert_eq!(serde_json::to_value(&client).unwrap(), json);
+}
@@ -0,0 +1,452 @@
+//! Offline harness for driving the real axum `Router` end-to-end.
+//!
+//! Local servers stand in for the network: mock arXiv, PubMed and Crossref
+//! endpoints serving fixtures from `tests/fixtures`, and a scripted
+//! OpenAI-compatible endpoint that replays a fixed list of chat replies
+//! (typically a `search_papers` tool call followed by an echo of the tool
+//! result) and computes toy embeddings for the paper index.
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use arxiv_rig_rust::config::ServerConfig;
+use arxiv_rig_rust::crossref_source::CrossrefSource;
+use arxiv_rig_rust::model::{AppState, ArxivSearchTool};
+use arxiv_rig_rust::paper_search_tool::PaperSearchTool;
+use arxiv_rig_rust::pubmed_source::PubMedSource;
+use axum::body::Body;
+use axum::extract::{Query, State};
+use axum::http::{header, Request, Response, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use metrics_exporter_prometheus::PrometheusBuilder;
+use rig::providers::openai;
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+const EMPTY_FEED: &str = include_str!("../fixtures/arxiv_empty.atom");
+
+/// Feeds served by the mock arXiv endpoint, keyed by the search term the tool sends.
+pub const FIXTURE_FEEDS: &[(&str, &str)] = &[(
+    "transformers",
+    include_str!("../fixtures/arxiv_transformers.atom"),
+)];
+
+/// esearch and esummary responses served by the mock PubMed endpoint, keyed by search term.
+pub const PUBMED_FIXTURES: &[(&str, &str, &str)] = &[(
+    "transformers",
+    include_str!("../fixtures/pubmed_esearch_transformers.json"),
+    include_str!("../fixtures/pubmed_esummary_transformers.json"),
+)];
+const PUBMED_EMPTY_SEARCH: &str = include_str!("../fixtures/pubmed_esearch_empty.json");
+
+/// `works` responses served by the mock Crossref endpoint, keyed by query.
+pub const CROSSREF_FIXTURES: &[(&str, &str)] = &[(
+    "transformers",
+    include_str!("../fixtures/crossref_works_transformers.json"),
+)];
+const CROSSREF_EMPTY_WORKS: &str = include_str!("../fixtures/crossref_works_empty.json");
+
+async fn serve(router: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{addr}")
+}
+
+/// Local stand-in for `export.arxiv.org/api/query`.
+pub struct MockArxiv {
+    pub url: String,
+    /// every `search_query` parameter received, in order
+    pub queries: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockArxiv {
+    pub async fn start() -> Self {
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let router = Router::new()
+            .route("/api/query", get(arxiv_query))
+            .with_state(queries.clone());
+        Self {
+            url: serve(router).await,
+            queries,
+        }
+    }
+
+    pub fn queries(&self) -> Vec<String> {
+        self.queries.lock().unwrap().clone()
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ArxivQuery {
+    search_query: String,
+}
+
+async fn arxiv_query(
+    State(queries): State<Arc<Mutex<Vec<String>>>>,
+    Query(query): Query<ArxivQuery>,
+) -> impl IntoResponse {
+    queries.lock().unwrap().push(query.search_query.clone());
+    let term = query.search_query.trim_start_matches("all:");
+    FIXTURE_FEEDS
+        .iter()
+        .find(|(fixture_term, _)| *fixture_term == term)
+        .map(|(_, feed)| *feed)
+        .unwrap_or(EMPTY_FEED)
+}
+
+/// Local stand-in for the NCBI E-utilities `esearch.fcgi` and `esummary.fcgi`.
+pub struct MockPubMed {
+    pub url: String,
+    /// every `term` parameter received, in order
+    pub terms: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockPubMed {
+    pub async fn start() -> Self {
+        let terms = Arc::new(Mutex::new(Vec::new()));
+        let router = Router::new()
+            .route("/esearch.fcgi", get(pubmed_esearch))
+            .route("/esummary.fcgi", get(pubmed_esummary))
+            .with_state(terms.clone());
+        Self {
+            url: serve(router).await,
+            terms,
+        }
+    }
+
+    pub fn terms(&self) -> Vec<String> {
+        self.terms.lock().unwrap().clone()
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ESearchQuery {
+    term: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ESummaryQuery {
+    id: String,
+}
+
+async fn pubmed_esearch(
+    State(terms): State<Arc<Mutex<Vec<String>>>>,
+    Query(query): Query<ESearchQuery>,
+) -> impl IntoResponse {
+    terms.lock().unwrap().push(query.term.clone());
+    let body = PUBMED_FIXTURES
+        .iter()
+        .find(|(term, _, _)| *term == query.term)
+        .map(|(_, search, _)| *search)
+        .unwrap_or(PUBMED_EMPTY_SEARCH);
+    ([(header::CONTENT_TYPE, "application/json")], body)
+}
+
+// serves the summary whose esearch fixture listed exactly these ids
+async fn pubmed_esummary(Query(query): Query<ESummaryQuery>) -> Response<Body> {
+    let summary = PUBMED_FIXTURES.iter().find_map(|(_, search, summary)| {
+        let search: Value = serde_json::from_str(search).unwrap();
+        let ids: Vec<&str> = search["esearchresult"]["idlist"]
+            .as_array()?
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        (ids.join(",") == query.id).then_some(*summary)
+    });
+    match summary {
+        Some(summary) => ([(header::CONTENT_TYPE, "application/json")], summary).into_response(),
+        None => (StatusCode::NOT_FOUND, "no esummary fixture for these ids").into_response(),
+    }
+}
+
+/// Local stand-in for the Crossref `works` endpoint.
+pub struct MockCrossref {
+    pub url: String,
+    /// every `query` parameter received, in order
+    pub queries: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockCrossref {
+    pub async fn start() -> Self {
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let router = Router::new()
+            .route("/works", get(crossref_works))
+            .with_state(queries.clone());
+        Self {
+            url: serve(router).await,
+            queries,
+        }
+    }
+
+    pub fn queries(&self) -> Vec<String> {
+        self.queries.lock().unwrap().clone()
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct WorksQuery {
+    query: String,
+}
+
+async fn crossref_works(
+    State(queries): State<Arc<Mutex<Vec<String>>>>,
+    Query(query): Query<WorksQuery>,
+) -> impl IntoResponse {
+    queries.lock().unwrap().push(query.query.clone());
+    let body = CROSSREF_FIXTURES
+        .iter()
+        .find(|(term, _)| *term == query.query)
+        .map(|(_, works)| *works)
+        .unwrap_or(CROSSREF_EMPTY_WORKS);
+    ([(header::CONTENT_TYPE, "application/json")], body)
+}
+
+/// Words counted by the mock `/embeddings` endpoint, one dimension each, so that
+/// papers sharing these words come out as similar.
+pub const EMBEDDING_VOCABULARY: &[&str] = &["attention", "transformer", "vision", "language", "llama"];
+
+/// One scripted LLM turn.
+pub enum Reply {
+    /// ask the agent to call a tool with these arguments
+    ToolCall { name: &'static str, arguments: Value },
+    /// answer with fixed text
+    Text(String),
+    /// answer with the content of the most recent tool result, like a model
+    /// told to "return only the raw JSON response from the tool"
+    EchoToolResult,
+}
+
+#[derive(Clone)]
+struct LlmState {
+    script: Arc<Mutex<VecDeque<Reply>>>,
+    requests: Arc<Mutex<Vec<Value>>>,
+    embedded: Arc<Mutex<Vec<String>>>,
+}
+
+/// Scripted OpenAI-compatible LLM serving `/chat/completions` and `/embeddings`.
+pub struct MockLlm {
+    pub url: String,
+    /// chat completion request bodies received, in order
+    pub requests: Arc<Mutex<Vec<Value>>>,
+    /// every text embedded, in order
+    pub embedded: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockLlm {
+    pub async fn start(script: Vec<Reply>) -> Self {
+        let state = LlmState {
+            script: Arc::new(Mutex::new(script.into())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+            embedded: Arc::new(Mutex::new(Vec::new())),
+        };
+        let requests = state.requests.clone();
+        let embedded = state.embedded.clone();
+        let router = Router::new()
+            .route("/chat/completions", post(chat_completions))
+            .route("/embeddings", post(embeddings))
+            .with_state(state);
+        Self {
+            url: serve(router).await,
+            requests,
+            embedded,
+        }
+    }
+
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn embedded(&self) -> Vec<String> {
+        self.embedded.lock().unwrap().clone()
+    }
+}
+
+/// The mock's embedding of `text`: how often each [`EMBEDDING_VOCABULARY`] word occurs in it.
+pub fn embed(text: &str) -> Vec<f64> {
+    let text = text.to_lowercase();
+    EMBEDDING_VOCABULARY
+        .iter()
+        .map(|word| text.matches(word).count() as f64)
+        .collect()
+}
+
+async fn embeddings(State(state): State<LlmState>, Json(request): Json<Value>) -> Json<Value> {
+    // `input` is a single string or a list of them
+    let inputs: Vec<String> = match &request["input"] {
+        Value::Array(inputs) => inputs
+            .iter()
+            .map(|input| input.as_str().unwrap_or_default().to_owned())
+            .collect(),
+        input => vec![input.as_str().unwrap_or_default().to_owned()],
+    };
+    state.embedded.lock().unwrap().extend(inputs.iter().cloned());
+
+    let data: Vec<Value> = inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| json!({ "object": "embedding", "embedding": embed(input), "index": index }))
+        .collect();
+    Json(json!({
+        "object": "list",
+        "data": data,
+        "model": request["model"],
+        "usage": { "prompt_tokens": 10, "total_tokens": 10 }
+    }))
+}
+
+async fn chat_completions(State(state): State<LlmState>, Json(request): Json<Value>) -> Response<Body> {
+    let reply = state.script.lock().unwrap().pop_front();
+    let messages = request["messages"].as_array().cloned().unwrap_or_default();
+    state.requests.lock().unwrap().push(request);
+
+    let Some(reply) = reply else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "mock LLM script exhausted").into_response();
+    };
+
+    let (message, finish_reason) = match reply {
+        Reply::ToolCall { name, arguments } => (
+            json!({
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{
+                    "id": format!("call_{}", messages.len()),
+                    "type": "function",
+                    "function": { "name": name, "arguments": arguments.to_string() }
+                }]
+            }),
+            "tool_calls",
+        ),
+        Reply::Text(text) => (json!({ "role": "assistant", "content": text }), "stop"),
+        Reply::EchoToolResult => {
+            let tool_result = messages
+                .iter()
+                .rev()
+                .find(|message| message["role"] == "tool")
+                .map(|message| message_text(&message["content"]))
+                .unwrap_or_default();
+            (json!({ "role": "assistant", "content": tool_result }), "stop")
+        }
+    };
+
+    Json(json!({
+        "id": "chatcmpl-mock",
+        "object": "chat.completion",
+        "created": 0,
+        "model": "gpt-4",
+        "system_fingerprint": null,
+        "choices": [{
+            "index": 0,
+            "message": message,
+            "logprobs": null,
+            "finish_reason": finish_reason
+        }],
+        "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+    }))
+    .into_response()
+}
+
+// message content is either a plain string or a list of `{ "type": "text", "text": ... }` parts
+pub fn message_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join(""),
+        _ => String::new(),
+    }
+}
+
+/// The real application router wired to the mock servers.
+pub struct TestApp {
+    pub router: Router,
+    pub arxiv: MockArxiv,
+    pub pubmed: MockPubMed,
+    pub crossref: MockCrossref,
+    pub llm: MockLlm,
+    index_path: PathBuf,
+}
+
+impl TestApp {
+    pub async fn start(script: Vec<Reply>) -> Self {
+        Self::with_config(script, ServerConfig::default()).await
+    }
+
+    pub async fn with_config(script: Vec<Reply>, mut config: ServerConfig) -> Self {
+        // every test gets its own local index, removed again on drop
+        let index_path = std::env::temp_dir().join(format!("arxiv-rig-rust-test-{}.json", uuid::Uuid::new_v4()));
+        config.paper_index_path = index_path.clone();
+
+        let arxiv = MockArxiv::start().await;
+        let pubmed = MockPubMed::start().await;
+        let crossref = MockCrossref::start().await;
+        let llm = MockLlm::start(script).await;
+
+        let paper_search = PaperSearchTool::new(
+            ArxivSearchTool::new(format!("{}/api/query", arxiv.url)),
+            PubMedSource::new(&pubmed.url),
+            CrossrefSource::new(&crossref.url),
+        );
+        let openai_client = openai::Client::from_url("test-key", &llm.url);
+        // a recorder that isn't installed globally, so every test gets its own
+        let metrics = PrometheusBuilder::new().build_recorder().handle();
+
+        let state = Arc::new(AppState::new(openai_client, paper_search, config, metrics).unwrap());
+        Self {
+            router: arxiv_rig_rust::app::router(state),
+            arxiv,
+            pubmed,
+            crossref,
+            llm,
+            index_path,
+        }
+    }
+
+    /// Serves the router on a local port, for tests that need a real HTTP client.
+    pub async fn serve(&self) -> String {
+        serve(self.router.clone()).await
+    }
+
+    /// Sends `POST /api/search` through the router in-process.
+    pub async fn search(&self, query: &str, headers: &[(&str, &str)]) -> Response<Body> {
+        let mut request = Request::post("/api/search").header("content-type", "application/json");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = request
+            .body(Body::from(json!({ "query": query }).to_string()))
+            .unwrap();
+        self.router.clone().oneshot(request).await.unwrap()
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.index_path);
+    }
+}
+
+pub async fn body_text(response: Response<Body>) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+/// The usual two-step script: search the fixture feed, then echo the tool output.
+pub fn search_script(query: &str) -> Vec<Reply> {
+    vec![
+        Reply::ToolCall {
+            name: "search_papers",
+            arguments: json!({ "query": query, "sources": ["arxiv"] }),
+        },
+        Reply::EchoToolResult,
+    ]
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use arxiv_rig_rust::auth::hash_api_key;
+use arxiv_rig_rust::config::ServerConfig;
+use arxiv_rig_rust::session::SESSION_HEADER;
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::{body_text, message_text, search_script, Reply, TestApp};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn search_renders_papers_from_the_fixture_feed() {
+    let app = TestApp::start(search_script("transformers")).await;
+
+    let response = app.search("find papers about transformers", &[]).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key(SESSION_HEADER));
+    let html = body_text(response).await;
+    assert!(html.contains("Attention Is All You Need"));
+    assert!(html.contains("Llama 2: Open Foundation and Fine-Tuned Chat Models"));
+
+    assert_eq!(app.arxiv.queries(), vec!["all:transformers".to_string()]);
+    let requests = app.llm.requests();
+    assert_eq!(requests.len(), 2);
+    // the second completion request carries the tool result back to the model
+    let messages = requests[1]["messages"].as_array().unwrap();
+    assert!(messages.iter().any(|message| message["role"] == "tool"));
+}
+
+#[tokio::test]
+async fn follow_up_questions_see_the_session_history() {
+    let mut script = search_script("transformers");
+    script.push(Reply::Text("[]".to_string()));
+    let app = TestApp::start(script).await;
+
+    let first = app.search("find papers about transformers", &[]).await;
+    let session_id = first.headers()[SESSION_HEADER].to_str().unwrap().to_owned();
+
+    let second = app
+        .search("only the ones from 2023", &[(SESSION_HEADER, &session_id)])
+        .await;
+    assert_eq!(second.status(), StatusCode::OK);
+
+    let requests = app.llm.requests();
+    assert_eq!(requests.len(), 3);
+    let history: Vec<String> = requests[2]["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message_text(&message["content"]))
+        .collect();
+    assert!(history.iter().any(|text| text.contains("find papers about transformers")));
+    assert!(history.iter().any(|text| text.contains("Attention Is All You Need")));
+}
+
+#[tokio::test]
+async fn searching_all_sources_merges_duplicates() {
+    let script = vec![
+        Reply::ToolCall {
+            name: "search_papers",
+            arguments: json!({ "query": "transformers" }),
+        },
+        Reply::EchoToolResult,
+    ];
+    let app = TestApp::start(script).await;
+
+    let response = app
+        .search("find papers about transformers", &[("accept", "application/json")])
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = serde_json::from_str(&body_text(response).await).unwrap();
+
+    assert_eq!(app.arxiv.queries(), vec!["all:transformers".to_string()]);
+    assert_eq!(app.pubmed.terms(), vec!["transformers".to_string()]);
+    assert_eq!(app.crossref.queries(), vec!["transformers".to_string()]);
+
+    let papers = body["papers"].as_array().unwrap();
+    let summary: Vec<(&str, &str)> = papers
+        .iter()
+        .map(|paper| (paper["title"].as_str().unwrap(), paper["source"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            // same title as the PubMed entry, which has no DOI
+            ("Attention Is All You Need", "arxiv, pubmed"),
+            // same DOI as the Crossref entry, in different case
+            ("Llama 2: Open Foundation and Fine-Tuned Chat Models", "arxiv, crossref"),
+            ("Transformers in Vision: A Survey.", "pubmed, crossref"),
+        ]
+    );
+    // PubMed summaries have no abstract, so the Crossref one fills it in
+    assert!(papers[2]["abstract_text"]
+        .as_str()
+        .unwrap()
+        .starts_with("Astounding results from Transformer models"));
+    assert_eq!(papers[2]["id"], "pubmed:35345781");
+}
+
+#[tokio::test]
+async fn unknown_session_ids_start_a_session_under_a_new_id() {
+    let app = TestApp::start(search_script("transformers")).await;
+
+    let response = app
+        .search("find papers about transformers", &[(SESSION_HEADER, "made-up-id")])
+        .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let session_id = response.headers()[SESSION_HEADER].to_str().unwrap();
+    assert_ne!(session_id, "made-up-id");
+    let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(cookie.contains(session_id));
+}
+
+#[tokio::test]
+async fn requests_over_the_ip_limit_get_429_with_retry_after() {
+    let config = ServerConfig {
+        requests_per_minute_per_ip: 1,
+        ..ServerConfig::default()
+    };
+    let app = TestApp::with_config(vec![Reply::Text("[]".to_string())], config).await;
+
+    let first = app.search("anything", &[]).await;
+    assert_eq!(first.status(), StatusCode::OK);
+
+    let second = app.search("anything else", &[]).await;
+    assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = second.headers()[header::RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after >= 1);
+    // the limited request never reached the LLM
+    assert_eq!(app.llm.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn api_key_is_required_when_configured() {
+    let config = ServerConfig {
+        api_key_hashes: [hash_api_key("test-secret")].into(),
+        ..ServerConfig::default()
+    };
+    let app = TestApp::with_config(vec![Reply::Text("[]".to_string())], config).await;
+
+    let missing = app.search("anything", &[]).await;
+    assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+
+    let wrong = app.search("anything", &[("x-api-key", "not-the-key")]).await;
+    assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+
+    let bearer = app
+        .search("anything", &[("authorization", "Bearer test-secret")])
+        .await;
+    assert_eq!(bearer.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn metrics_require_the_api_key_when_configured() {
+    let config = ServerConfig {
+        api_key_hashes: [hash_api_key("test-secret")].into(),
+        ..ServerConfig::default()
+    };
+    let app = TestApp::with_config(Vec::new(), config).await;
+    let metrics = |key: Option<&str>| {
+        let mut request = Request::get("/metrics");
+        if let Some(key) = key {
+            request = request.header("x-api-key", key);
+        }
+        app.router.clone().oneshot(request.body(Body::empty()).unwrap())
+    };
+
+    assert_eq!(metrics(None).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(metrics(Some("test-secret")).await.unwrap().status(), StatusCode::OK);
+}
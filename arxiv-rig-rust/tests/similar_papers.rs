@@ -0,0 +1,145 @@
+mod common;
+
+use std::time::Duration;
+
+use arxiv_rig_rust::config::{PaperIndexKind, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, Response, StatusCode};
+use common::{body_text, embed, Reply, TestApp};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+const ATTENTION: &str = "arxiv:1706.03762";
+
+fn search_all_sources() -> Vec<Reply> {
+    vec![
+        Reply::ToolCall {
+            name: "search_papers",
+            arguments: json!({ "query": "transformers" }),
+        },
+        Reply::EchoToolResult,
+    ]
+}
+
+async fn get(app: &TestApp, uri: &str) -> Response<Body> {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    app.router.clone().oneshot(request).await.unwrap()
+}
+
+// papers are embedded in the background after a search, so wait for them
+async fn wait_until_indexed(app: &TestApp, id: &str) {
+    for _ in 0..100 {
+        if get(app, &format!("/api/papers/{id}/similar")).await.status() == StatusCode::OK {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("{id} was never indexed");
+}
+
+async fn similar(app: &TestApp, uri: &str) -> Vec<(String, f64)> {
+    let response = get(app, uri).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let papers: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    papers
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|paper| (paper["id"].as_str().unwrap().to_owned(), paper["score"].as_f64().unwrap()))
+        .collect()
+}
+
+fn cosine(a: &[f64], b: &[f64]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f64]| v.iter().map(|x| x * x).sum::<f64>().sqrt();
+    dot / (norm(a) * norm(b))
+}
+
+#[tokio::test]
+async fn searched_papers_are_embedded_and_ranked_by_cosine_similarity() {
+    let app = TestApp::start(search_all_sources()).await;
+    let response = app.search("find papers about transformers", &[]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    wait_until_indexed(&app, ATTENTION).await;
+
+    // title and abstract of each of the three merged papers
+    let embedded = app.llm.embedded();
+    assert_eq!(embedded.len(), 3);
+    assert!(embedded[0].starts_with("Attention Is All You Need\n\n"));
+
+    let neighbours = similar(&app, &format!("/api/papers/{ATTENTION}/similar")).await;
+    let ids: Vec<&str> = neighbours.iter().map(|(id, _)| id.as_str()).collect();
+    // the vision survey shares "transformer" with the query paper, Llama 2 no vocabulary word at all
+    assert_eq!(ids, ["pubmed:35345781", "arxiv:2307.09288"]);
+
+    let expected = cosine(&embed(&embedded[0]), &embed(&embedded[2]));
+    assert!((neighbours[0].1 - expected).abs() < 1e-9);
+    assert_eq!(neighbours[1].1, 0.0);
+}
+
+#[tokio::test]
+async fn similar_limit_is_clamped() {
+    let app = TestApp::start(search_all_sources()).await;
+    app.search("find papers about transformers", &[]).await;
+    wait_until_indexed(&app, ATTENTION).await;
+
+    let none = similar(&app, &format!("/api/papers/{ATTENTION}/similar?limit=0")).await;
+    assert_eq!(none.len(), 1);
+    let all = similar(&app, &format!("/api/papers/{ATTENTION}/similar?limit=1000")).await;
+    assert_eq!(all.len(), 2);
+}
+
+#[tokio::test]
+async fn unknown_papers_and_a_disabled_index_are_404() {
+    let app = TestApp::start(Vec::new()).await;
+    let response = get(&app, "/api/papers/arxiv:0000.00000/similar").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let config = ServerConfig {
+        paper_index: PaperIndexKind::Disabled,
+        ..ServerConfig::default()
+    };
+    let app = TestApp::with_config(Vec::new(), config).await;
+    let response = get(&app, &format!("/api/papers/{ATTENTION}/similar")).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(body_text(response).await, "Recommendations are disabled");
+}
+
+#[tokio::test]
+async fn the_agent_can_ask_for_similar_papers() {
+    let mut script = search_all_sources();
+    script.extend([
+        Reply::ToolCall {
+            name: "find_similar_papers",
+            // out of range, so clamped to a single paper
+            arguments: json!({ "paper_id": ATTENTION, "limit": 0 }),
+        },
+        Reply::EchoToolResult,
+    ]);
+    let app = TestApp::start(script).await;
+
+    let first = app.search("find papers about transformers", &[]).await;
+    let session_id = first.headers()["x-session-id"].to_str().unwrap().to_owned();
+    wait_until_indexed(&app, ATTENTION).await;
+
+    let response = app
+        .search(
+            "more like the first one",
+            &[("x-session-id", &session_id), ("accept", "application/json")],
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = serde_json::from_str(&body_text(response).await).unwrap();
+    let papers = body["papers"].as_array().unwrap();
+    assert_eq!(papers.len(), 1);
+    assert_eq!(papers[0]["id"], "pubmed:35345781");
+
+    let requests = app.llm.requests();
+    let tools: Vec<&str> = requests[0]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tool| tool["function"]["name"].as_str().unwrap())
+        .collect();
+    assert!(tools.contains(&"find_similar_papers"));
+}
@@ -0,0 +1,162 @@
+//! Client for the arxiv-rig-rust server's HTTP API.
+//!
+//! Kept out of the server crate so that callers don't pull in the agent,
+//! the paper index and the web framework just to make a few HTTP requests.
+
+mod model;
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use reqwest::{header, RequestBuilder, Response, StatusCode};
+
+use crate::model::SearchRequest;
+
+pub use crate::model::{Paper, ResultView, SearchResponse, SimilarPaper};
+
+/// Header carrying the API key, when the server requires one.
+pub const API_KEY_HEADER: &str = "x-api-key";
+/// Header carrying the session id, sent back on every search response.
+pub const SESSION_HEADER: &str = "x-session-id";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error("Missing or invalid API key")]
+    Unauthorized,
+    #[error("Rate limited, retry after {0:?}")]
+    RateLimited(Option<Duration>),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Server error ({status}): {body}")]
+    Server { status: StatusCode, body: String },
+}
+
+/// Typed client for the server's HTTP API (see `/openapi.json`).
+///
+/// The client keeps the session id returned by the first search, so later
+/// calls to [`ArxivClient::search`] continue the same conversation until
+/// [`ArxivClient::reset_session`] is called.
+#[derive(Debug)]
+pub struct ArxivClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    session_id: Mutex<Option<String>>,
+}
+
+impl ArxivClient {
+    /// `base_url` is the server root, e.g. `http://localhost:8003`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+            session_id: Mutex::new(None),
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// The conversation this client is in, if it has searched yet.
+    pub fn session_id(&self) -> Option<String> {
+        self.session_id.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Asks the research assistant and returns the papers in its answer.
+    pub async fn search(&self, query: &str) -> Result<SearchResponse, ClientError> {
+        let response = self
+            .send_search(query, ResultView::default())
+            .header(header::ACCEPT, "application/json")
+            .send()
+            .await?;
+        Ok(self.check(response).await?.json().await?)
+    }
+
+    /// Like [`ArxivClient::search`], but returns the results rendered as HTML.
+    pub async fn search_html(&self, query: &str, view: ResultView) -> Result<String, ClientError> {
+        let response = self
+            .send_search(query, view)
+            .header(header::ACCEPT, "text/html")
+            .send()
+            .await?;
+        Ok(self.check(response).await?.text().await?)
+    }
+
+    /// Ends the current conversation on the server and locally.
+    pub async fn reset_session(&self) -> Result<(), ClientError> {
+        let request = self.request(self.http.delete(format!("{}/api/session", self.base_url)));
+        self.check(request.send().await?).await?;
+        *self.session_id.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+
+    /// Papers similar to one returned by an earlier search, most similar first.
+    pub async fn similar_papers(
+        &self,
+        paper_id: &str,
+        limit: usize,
+    ) -> Result<Vec<SimilarPaper>, ClientError> {
+        let url = format!(
+            "{}/api/papers/{}/similar?limit={limit}",
+            self.base_url,
+            urlencoding::encode(paper_id)
+        );
+        let response = self.request(self.http.get(url)).send().await?;
+        Ok(self.check(response).await?.json().await?)
+    }
+
+    fn send_search(&self, query: &str, view: ResultView) -> RequestBuilder {
+        let request = SearchRequest { query, view };
+        self.request(self.http.post(format!("{}/api/search", self.base_url)))
+            .json(&request)
+    }
+
+    // adds the API key and session id to a request
+    fn request(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(api_key) = &self.api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+        if let Some(session_id) = self.session_id() {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+        request
+    }
+
+    // maps error statuses to `ClientError` and remembers the session id
+    async fn check(&self, response: Response) -> Result<Response, ClientError> {
+        if let Some(session_id) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|value| value.to_str().ok())
+        {
+            *self.session_id.lock().unwrap_or_else(|e| e.into_inner()) = Some(session_id.to_string());
+        }
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        match status {
+            StatusCode::UNAUTHORIZED => Err(ClientError::Unauthorized),
+            StatusCode::TOO_MANY_REQUESTS => {
+                let retry_after = response
+                    .headers()
+                    .get(header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok())
+                    .map(Duration::from_secs);
+                Err(ClientError::RateLimited(retry_after))
+            }
+            StatusCode::NOT_FOUND => Err(ClientError::NotFound(response.text().await?)),
+            _ => Err(ClientError::Server {
+                status,
+                body: response.text().await?,
+            }),
+        }
+    }
+}
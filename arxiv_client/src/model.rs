@@ -0,0 +1,52 @@
+/// A paper as returned by the server.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct Paper {
+    /// Stable identifier: "arxiv:<id>", "doi:<doi>" or "pubmed:<pmid>"
+    #[serde(default)]
+    pub id: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub abstract_text: String,
+    pub url: String,
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub published: String,
+    #[serde(default)]
+    pub doi: Option<String>,
+    /// Which source(s) the paper was found in, e.g. "arxiv" or "arxiv, crossref"
+    #[serde(default)]
+    pub source: String,
+}
+
+/// Search result returned for JSON requests.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct SearchResponse {
+    pub papers: Vec<Paper>,
+    /// The assistant's reply when it answered in prose rather than with papers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// A paper similar to the one asked about, with its cosine similarity score.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct SimilarPaper {
+    pub score: f64,
+    #[serde(flatten)]
+    pub paper: Paper,
+}
+
+/// The ways the server can render search results as HTML.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultView {
+    #[default]
+    Table,
+    Cards,
+    List,
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct SearchRequest<'a> {
+    pub(crate) query: &'a str,
+    pub(crate) view: ResultView,
+}
@@ -0,0 +1,98 @@
+use std::sync::{Arc, Mutex};
+
+use arxiv_client::{ArxivClient, ClientError, ResultView};
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+async fn serve(router: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{addr}")
+}
+
+// every search answers with one paper and records the headers it got
+async fn search_server(seen: Arc<Mutex<Vec<HeaderMap>>>) -> String {
+    let router = Router::new()
+        .route(
+            "/api/search",
+            post(|State(seen): State<Arc<Mutex<Vec<HeaderMap>>>>, headers: HeaderMap, Json(body): Json<Value>| async move {
+                seen.lock().unwrap().push(headers.clone());
+                if headers["accept"] == "text/html" {
+                    return format!("<p>{}</p>", body["view"].as_str().unwrap()).into_response();
+                }
+                let paper = json!({
+                    "id": "arxiv:1706.03762",
+                    "title": "Attention Is All You Need",
+                    "authors": ["Ashish Vaswani"],
+                    "abstract_text": "",
+                    "url": "https://arxiv.org/abs/1706.03762",
+                    "categories": ["cs.CL"],
+                });
+                ([("x-session-id", "server-issued")], Json(json!({ "papers": [paper] }))).into_response()
+            }),
+        )
+        .route("/api/session", delete(|| async { StatusCode::NO_CONTENT }))
+        .route(
+            "/api/papers/{id}/similar",
+            get(|Path(id): Path<String>| async move {
+                (StatusCode::NOT_FOUND, format!("Paper {id} hasn't been indexed yet"))
+            }),
+        )
+        .with_state(seen);
+    serve(router).await
+}
+
+#[tokio::test]
+async fn search_sends_the_api_key_and_keeps_the_session() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let client = ArxivClient::new(search_server(seen.clone()).await + "/").with_api_key("secret");
+
+    let response = client.search("transformers").await.unwrap();
+    assert_eq!(response.papers[0].title, "Attention Is All You Need");
+    assert_eq!(response.papers[0].published, "");
+    assert_eq!(client.session_id().as_deref(), Some("server-issued"));
+
+    let html = client.search_html("more", ResultView::Cards).await.unwrap();
+    assert_eq!(html, "<p>cards</p>");
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen[0]["x-api-key"], "secret");
+    assert!(seen[0].get("x-session-id").is_none());
+    assert_eq!(seen[1]["x-session-id"], "server-issued");
+}
+
+#[tokio::test]
+async fn reset_forgets_the_session() {
+    let client = ArxivClient::new(search_server(Arc::default()).await);
+    client.search("transformers").await.unwrap();
+
+    client.reset_session().await.unwrap();
+    assert!(client.session_id().is_none());
+}
+
+#[tokio::test]
+async fn error_statuses_map_to_client_errors() {
+    let client = ArxivClient::new(search_server(Arc::default()).await);
+    let result = client.similar_papers("doi:10.1145/3505244", 5).await;
+    assert!(
+        matches!(&result, Err(ClientError::NotFound(body)) if body == "Paper doi:10.1145/3505244 hasn't been indexed yet"),
+        "{result:?}"
+    );
+
+    let router = Router::new().route(
+        "/api/search",
+        post(|| async { (StatusCode::TOO_MANY_REQUESTS, [("retry-after", "7")], "slow down") }),
+    );
+    let client = ArxivClient::new(serve(router).await);
+    let result = client.search("transformers").await;
+    assert!(
+        matches!(result, Err(ClientError::RateLimited(Some(wait))) if wait.as_secs() == 7),
+        "{result:?}"
+    );
+}
@@ -4,7 +4,7 @@ use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
-use std::env;
+use tripadvisor_client::{TripadvisorClient, TripadvisorError};
 
 #[derive(Deserialize)]
 pub struct FlightSearchArgs {
@@ -36,17 +36,17 @@ pub struct FlightOption {
 
 #[derive(Debug, thiserror::Error)]
 pub enum FlightSearchError {
-    #[error("HTTP request failed: {0}")]
-    HttpRequestFailed(String),
+    #[error("Tripadvisor request failed: {0}")]
+    Tripadvisor(#[from] TripadvisorError),
     #[error("Invalid response structure")]
     InvalidResponse,
     #[error("API error: {0}")]
     ApiError(String),
-    #[error("Missing API key")]
-    MissingApiKey,
 }
 
-pub struct FlightSearchTool;
+pub struct FlightSearchTool {
+    pub client: TripadvisorClient,
+}
 
 impl Tool for FlightSearchTool {
     const NAME: &'static str = "search_flights";
@@ -73,9 +73,6 @@ impl Tool for FlightSearchTool {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        // Fetch the API Key
-        let api_key = env::var("RAPIDAPI_KEY").map_err(|_| FlightSearchError::MissingApiKey)?;
-
         // Set Default Values
         // If the user doesn't provide a date, we'll default to 30 days from now.
         let date = args.date.unwrap_or_else(|| {
@@ -92,31 +89,10 @@ impl Tool for FlightSearchTool {
         query_params.insert("classOfService", "ECONOMY".to_string());
 
         // Make the API Request
-        let client = reqwest::Client::new();
-        let response = client
-            .get("https://tripadvisor16.p.rapidapi.com/api/v1/flights/searchFlights")
-            .headers({
-                let mut headers = reqwest::header::HeaderMap::new();
-                headers.insert(
-                    "X-RapidAPI-Host",
-                    "tripadvisor16.p.rapidapi.com".parse().unwrap(),
-                );
-                headers.insert("X-RapidAPI-Key", api_key.parse().unwrap());
-                headers
-            })
-            .query(&query_params)
-            .send()
-            .await
-            .map_err(|e| FlightSearchError::HttpRequestFailed(e.to_string()))?;
-
-        // Parse and Format the Response
-        let text = response
-            .text()
-            .await
-            .map_err(|e| FlightSearchError::HttpRequestFailed(e.to_string()))?;
-
-        let _data: Value = serde_json::from_str(&text)
-            .map_err(|e| FlightSearchError::HttpRequestFailed(e.to_string()))?;
+        let _data: Value = self
+            .client
+            .get("/api/v1/flights/searchFlights", &query_params)
+            .await?;
 
         let flight_options: Vec<FlightOption> = Vec::new();
 
@@ -4,6 +4,7 @@ use dotenv::dotenv;
 use rig::completion::Prompt;
 use rig::providers::openai::Client;
 use rig::client::{CompletionClient, ProviderClient};
+use tripadvisor_client::TripadvisorClient;
 use std::error::Error;
 
 #[tokio::main]
@@ -14,7 +15,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let agent = openai_client
         .agent("gpt-4")
         .preamble("You are a helpful assistant that can find flights for users.")
-        .tool(FlightSearchTool)
+        .tool(FlightSearchTool { client: TripadvisorClient::from_env()? })
         .build();
 
     let response = agent
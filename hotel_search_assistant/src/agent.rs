@@ -0,0 +1,110 @@
+use std::sync::Arc;
+use chrono::NaiveDate;
+use rig::agent::Agent;
+use rig::client::CompletionClient;
+use rig::providers::openai;
+use rig::tool::Tool;
+use tripadvisor_client::TripadvisorClient;
+use crate::attraction_search_tool::AttractionSearchTool;
+use crate::audit::{AuditLog, Audited};
+use crate::conversation::Conversation;
+use crate::currency::RateProvider;
+use crate::hotel_details_tool::HotelDetailsTool;
+use crate::hotel_search_tool::HotelSearchTool;
+use crate::location_cache::LocationCache;
+use crate::location_search_tool::LocationSearchTool;
+use crate::model::HotelResult;
+use crate::restaurant_search_tool::RestaurantSearchTool;
+use crate::shortlist::Shortlist;
+use crate::shortlist_tools::{ShortlistAddTool, ShortlistCompareTool, ShortlistRemoveTool};
+use crate::tool_error::{HotelSearchError, Recoverable};
+
+pub type HotelAgent = Agent<<openai::Client as CompletionClient>::CompletionModel>;
+
+/// What every session's agent shares: the API clients, the location cache
+/// and the exchange rates.
+#[derive(Clone)]
+pub struct AgentResources {
+    pub openai_client: openai::Client,
+    // one client for all tools, so they share the API quota tracking
+    pub tripadvisor: TripadvisorClient,
+    pub location_cache: Arc<LocationCache>,
+    pub rates: Arc<dyn RateProvider>,
+}
+
+/// Builds the hotel agent for one session, with its preamble and tools. The CLI
+/// and the web server both use it, so they always offer the same assistant.
+pub fn build_agent(resources: &AgentResources, shortlist: Shortlist, audit: AuditLog) -> HotelAgent {
+    let today = crate::dates::today();
+    let tripadvisor = &resources.tripadvisor;
+    resources
+        .openai_client
+        .agent("gpt-4-turbo") // Recommended for better tool use in multi-turn
+        .preamble(&preamble(today))
+        .tool(agent_tool(LocationSearchTool::new(tripadvisor.clone(), resources.location_cache.clone()), &audit))
+        .tool(agent_tool(
+            HotelSearchTool {
+                client: tripadvisor.clone(),
+                rates: resources.rates.clone(),
+                shortlist: shortlist.clone(),
+            },
+            &audit,
+        ))
+        .tool(agent_tool(HotelDetailsTool { client: tripadvisor.clone(), shortlist: shortlist.clone() }, &audit))
+        .tool(agent_tool(RestaurantSearchTool { client: tripadvisor.clone() }, &audit))
+        .tool(agent_tool(AttractionSearchTool { client: tripadvisor.clone() }, &audit))
+        .tool(agent_tool(ShortlistAddTool { shortlist: shortlist.clone() }, &audit))
+        .tool(agent_tool(ShortlistRemoveTool { shortlist: shortlist.clone() }, &audit))
+        .tool(agent_tool(ShortlistCompareTool { shortlist }, &audit))
+        .build()
+}
+
+// every tool reports its failures to the model and its calls to the audit log
+fn agent_tool<T>(tool: T, audit: &AuditLog) -> Audited<Recoverable<T>>
+where
+    T: Tool<Error = HotelSearchError>,
+{
+    Audited {
+        tool: Recoverable(tool),
+        audit: audit.clone(),
+    }
+}
+
+fn preamble(today: NaiveDate) -> String {
+    format!("You are a helpful assistant that finds hotels. \
+        Search results are shown to the user as a list, so don't repeat every hotel; \
+        summarize the options and answer questions about them, referring to hotels by name. \
+        When providing hotel details, include its overall rating (with number of reviews), \
+        price, key features, and its specific location or neighborhood within the city. \
+        Do not invent information. If a direct booking link is not available, \
+        mention the booking provider name if possible. \
+        For questions about one hotel (amenities such as parking, the address, photos), use get_hotel_details \
+        with the hotel's id from the search. For what guests say about a hotel, call it in reviews mode, \
+        with the topic the user asked about, and quote the returned review snippets; never make up review comments. \
+         Keep responses concise unless more details are requested. \
+         Today is {}. Pass stay dates to the tools as the user said them (e.g. \"next Friday\" or \"Aug 8-14\"); \
+         the tools work out the exact dates. If a tool returns an error, it says which argument \
+         was wrong and gives a next_step; follow it, e.g. explain invalid stay dates and ask the user for new ones. \
+        If search_location needs confirmation, list its candidates (name and region) and ask which one the user means \
+        before searching for hotels. \
+        Search results include the total for the whole stay and all rooms; use it when the user asks what a stay costs, \
+        and pass the currency the user asks for (e.g. EUR) to search_hotel. \
+        For searches near a landmark or address (\"within 1 km of the Eiffel Tower\"), search the city's geoId \
+        and pass the landmark as near and the distance as radiusKm. \
+        When the user wants to keep a hotel in mind, add it with shortlist_add, noting what they liked about it; \
+        use shortlist_compare to compare shortlisted hotels or to find the one the user describes. \
+        To help plan the rest of a trip, use search_restaurants and search_attractions with the same geoId \
+        as the hotel search, and point out places in or near the neighbourhood of the hotels the user likes. \
+        If user didn't specify number of rooms, you should default to 1.
+        ", today)
+}
+
+/// The hotel lists returned by searches while answering the latest message,
+/// one per search.
+pub fn latest_search_results(conversation: &Conversation) -> Vec<Vec<HotelResult>> {
+    conversation
+        .latest_tool_outputs(HotelSearchTool::NAME)
+        .iter()
+        .filter_map(|output| serde_json::from_str(output).ok())
+        .collect()
+}
@@ -0,0 +1,155 @@
+use crate::tool_error::HotelSearchError;
+use crate::model::{ApiAttraction, AttractionResult, AttractionSearchArgs, AttractionSearchData};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde_json::json;
+use std::collections::HashMap;
+use tripadvisor_client::TripadvisorClient;
+
+pub struct AttractionSearchTool {
+    pub client: TripadvisorClient,
+}
+
+impl Tool for AttractionSearchTool {
+    const NAME: &'static str = "search_attractions";
+    type Error = HotelSearchError;
+    type Args = AttractionSearchArgs;
+    type Output = Vec<AttractionResult>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Search for things to do in a location: sights, museums, tours, parks and so on. \
+                Returns the attractions with their rating, review count, categories, whether they're open now \
+                and their neighbourhood. The category and open now filters only apply to the one page of \
+                results fetched, so a filtered page can come back short or empty; request the next page for \
+                more matches.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "geoId": {
+                        "type": "integer",
+                        "description": "The geographic ID of the location to search in. Obtained from search_location, the same as for hotels."
+                    },
+                    "category": {
+                        "type": "string",
+                        "description": "Kind of attraction to include, e.g. \"Museums\", \"Parks\", \"Tours\", \"Nightlife\" or \"Shopping\"."
+                    },
+                    "openNow": {
+                        "type": "boolean",
+                        "description": "Only include attractions that are open right now."
+                    },
+                    "page": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Results page, starting at 1. Filters are applied to each page separately."
+                    }
+                },
+                "required": ["geoId"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let mut query_params = HashMap::new();
+        query_params.insert("geoId", args.geo_id.to_string());
+        query_params.insert("page", args.page.unwrap_or(1).max(1).to_string());
+        tracing::debug!(geo_id = args.geo_id, "searching attractions");
+
+        let response_data: AttractionSearchData = self
+            .client
+            .get("/api/v1/attraction/searchAttractions", &query_params)
+            .await?;
+
+        Ok(filter_attractions(response_data.data, &args))
+    }
+}
+
+// Filtered here, as for restaurants, so only the page fetched is filtered.
+// "museum" should match "Art Museums".
+fn filter_attractions(page: Vec<ApiAttraction>, args: &AttractionSearchArgs) -> Vec<AttractionResult> {
+    let category = args
+        .category
+        .as_deref()
+        .map(|category| category.trim().trim_end_matches('s').to_lowercase())
+        .filter(|category| !category.is_empty());
+    page.into_iter()
+        .map(AttractionResult::from)
+        .filter(|attraction| match &category {
+            Some(category) => attraction
+                .categories
+                .iter()
+                .any(|name| name.to_lowercase().contains(category.as_str())),
+            None => true,
+        })
+        .filter(|attraction| !args.open_now || attraction.open_now == Some(true))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(attractions: &[(&str, &[&str], &str)]) -> Vec<ApiAttraction> {
+        attractions
+            .iter()
+            .map(|(id, categories, status)| {
+                serde_json::from_value(json!({
+                    "locationId": id,
+                    "name": format!("Attraction {}", id),
+                    "subcategories": categories,
+                    "currentOpenStatusCategory": status
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn lisbon() -> Vec<ApiAttraction> {
+        page(&[
+            ("1", &["Art Museums", "Specialty Museums"], "OPEN"),
+            ("2", &["Churches & Cathedrals"], "CLOSED"),
+            ("3", &["Parks"], "UNKNOWN"),
+        ])
+    }
+
+    fn args(filters: serde_json::Value) -> AttractionSearchArgs {
+        let mut args = json!({ "geoId": 189158 });
+        args.as_object_mut().unwrap().extend(filters.as_object().unwrap().clone());
+        serde_json::from_value(args).unwrap()
+    }
+
+    fn ids(attractions: Vec<AttractionResult>) -> Vec<String> {
+        attractions.into_iter().map(|attraction| attraction.id).collect()
+    }
+
+    #[test]
+    fn without_filters_the_whole_page_is_returned() {
+        assert_eq!(ids(filter_attractions(lisbon(), &args(json!({})))), ["1", "2", "3"]);
+        assert_eq!(ids(filter_attractions(lisbon(), &args(json!({ "category": "  " })))).len(), 3);
+    }
+
+    #[test]
+    fn categories_match_singular_or_plural_ignoring_case() {
+        for category in ["museum", "Museums", " MUSEUM "] {
+            assert_eq!(ids(filter_attractions(lisbon(), &args(json!({ "category": category })))), ["1"], "{category}");
+        }
+        assert_eq!(ids(filter_attractions(lisbon(), &args(json!({ "category": "cathedral" })))), ["2"]);
+    }
+
+    #[test]
+    fn open_now_skips_closed_and_unknown_hours() {
+        assert_eq!(ids(filter_attractions(lisbon(), &args(json!({ "openNow": true })))), ["1"]);
+        let open_churches = args(json!({ "category": "church", "openNow": true }));
+        assert!(filter_attractions(lisbon(), &open_churches).is_empty());
+    }
+
+    #[test]
+    fn filters_apply_to_the_page_fetched_only() {
+        let args = args(json!({ "category": "park", "page": 2 }));
+        let second_page = page(&[("4", &["Aquariums"], "OPEN")]);
+
+        // nothing on this page matches, and no other page is looked at
+        assert!(filter_attractions(second_page, &args).is_empty());
+    }
+}
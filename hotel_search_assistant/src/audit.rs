@@ -0,0 +1,427 @@
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use chrono::{DateTime, Utc};
+use rig::completion::{Message, ToolDefinition};
+use rig::message::AssistantContent;
+use rig::tool::Tool;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing_subscriber::filter::{EnvFilter, Targets};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+use crate::tool_error::ToolOutcome;
+
+/// Target of the audit log events, the only ones written to the audit file.
+pub const AUDIT_TARGET: &str = "tool_audit";
+
+// Arguments longer than this are cut short in the call tree
+const MAX_TREE_ARGUMENTS: usize = 80;
+
+/// Sets up tracing: tool calls are appended to `path` as JSON lines, and
+/// everything else goes to stderr as filtered by `RUST_LOG` (warnings by default).
+pub fn init(path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    let audit = tracing_subscriber::fmt::layer()
+        .json()
+        .flatten_event(true)
+        .with_current_span(false)
+        .with_span_list(false)
+        .with_target(false)
+        .with_level(false)
+        // entries carry their own timestamp, from when the call started
+        .without_time()
+        .with_writer(Mutex::new(file))
+        .with_filter(Targets::new().with_target(AUDIT_TARGET, tracing::Level::INFO));
+    let console = tracing_subscriber::fmt::layer()
+        .with_writer(io::stderr)
+        .with_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")));
+    tracing_subscriber::registry()
+        .with(audit)
+        .with(console)
+        .try_init()
+        .map_err(io::Error::other)
+}
+
+/// One tool call as recorded in the audit log.
+#[derive(Debug, Clone)]
+pub struct ToolCallRecord {
+    pub timestamp: DateTime<Utc>,
+    pub session: String,
+    pub tool: String,
+    pub arguments: serde_json::Value,
+    pub latency_ms: u64,
+    // size of the result as sent to the model, in bytes
+    pub result_bytes: usize,
+    pub error: Option<String>,
+}
+
+/// A tool call starting or finishing, as sent to [`AuditLog::watch`].
+#[derive(Debug, Clone)]
+pub enum ToolCallEvent {
+    Started { tool: String, arguments: serde_json::Value },
+    Finished(ToolCallRecord),
+}
+
+/// Records the tool calls of one session. Cheap to clone; clones share the
+/// same records and watcher.
+#[derive(Clone)]
+pub struct AuditLog {
+    session: String,
+    // kept for the CLI's call tree, taken after each turn
+    records: Option<Arc<Mutex<Vec<ToolCallRecord>>>>,
+    // told about calls as they happen, e.g. to stream progress to the browser
+    watcher: Arc<Mutex<Option<mpsc::UnboundedSender<ToolCallEvent>>>>,
+}
+
+impl AuditLog {
+    pub fn new(session: &str) -> Self {
+        Self {
+            session: session.to_string(),
+            records: None,
+            watcher: Arc::default(),
+        }
+    }
+
+    /// Sends the calls to `watcher` as they start and finish, until
+    /// [`AuditLog::unwatch`].
+    pub fn watch(&self, watcher: mpsc::UnboundedSender<ToolCallEvent>) {
+        *self.watcher.lock().unwrap_or_else(|e| e.into_inner()) = Some(watcher);
+    }
+
+    pub fn unwatch(&self) {
+        self.watcher.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+
+    fn notify(&self, event: ToolCallEvent) {
+        if let Some(watcher) = &*self.watcher.lock().unwrap_or_else(|e| e.into_inner()) {
+            // a watcher that went away just misses the progress
+            let _ = watcher.send(event);
+        }
+    }
+
+    /// Also keeps the records in memory until [`AuditLog::take_records`].
+    pub fn keep_records(mut self) -> Self {
+        self.records = Some(Arc::default());
+        self
+    }
+
+    /// The calls recorded since the last time this was called, oldest first.
+    pub fn take_records(&self) -> Vec<ToolCallRecord> {
+        match &self.records {
+            Some(records) => std::mem::take(&mut *records.lock().unwrap_or_else(|e| e.into_inner())),
+            None => Vec::new(),
+        }
+    }
+
+    fn record(&self, record: ToolCallRecord) {
+        tracing::info!(
+            target: AUDIT_TARGET,
+            timestamp = %record.timestamp.to_rfc3339(),
+            session = %record.session,
+            tool = %record.tool,
+            arguments = %record.arguments,
+            latency_ms = record.latency_ms,
+            result_bytes = record.result_bytes,
+            error = record.error.as_deref(),
+            "tool call"
+        );
+        self.notify(ToolCallEvent::Finished(record.clone()));
+        if let Some(records) = &self.records {
+            records.lock().unwrap_or_else(|e| e.into_inner()).push(record);
+        }
+    }
+}
+
+/// Wraps a tool so that every call to it is recorded in the audit log, with
+/// the arguments as the model sent them. It wraps [`Recoverable`] tools, which
+/// take their arguments as JSON, so calls with arguments the tool rejects are
+/// recorded too.
+///
+/// [`Recoverable`]: crate::tool_error::Recoverable
+pub struct Audited<T> {
+    pub tool: T,
+    pub audit: AuditLog,
+}
+
+impl<T, O> Tool for Audited<T>
+where
+    T: Tool<Args = serde_json::Value, Output = ToolOutcome<O>>,
+    O: Serialize,
+{
+    const NAME: &'static str = T::NAME;
+    type Error = T::Error;
+    type Args = serde_json::Value;
+    type Output = ToolOutcome<O>;
+
+    async fn definition(&self, prompt: String) -> ToolDefinition {
+        self.tool.definition(prompt).await
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let timestamp = Utc::now();
+        let arguments = args.clone();
+        self.audit.notify(ToolCallEvent::Started {
+            tool: T::NAME.to_string(),
+            arguments: arguments.clone(),
+        });
+        let started = Instant::now();
+        let result = self.tool.call(args).await;
+
+        let result_bytes = match &result {
+            Ok(output) => serde_json::to_string(output).map_or(0, |json| json.len()),
+            Err(_) => 0,
+        };
+        let error = match &result {
+            Ok(ToolOutcome::Failed { message, .. }) => Some(message.clone()),
+            Ok(ToolOutcome::Done(_)) => None,
+            Err(e) => Some(e.to_string()),
+        };
+        self.audit.record(ToolCallRecord {
+            timestamp,
+            session: self.audit.session.clone(),
+            tool: T::NAME.to_string(),
+            arguments,
+            latency_ms: started.elapsed().as_millis() as u64,
+            result_bytes,
+            error,
+        });
+        result
+    }
+}
+
+/// The turn's tool calls as a tree, grouped by the model step that made them:
+///
+/// ```text
+/// turn: 2 steps, 2 tool calls, 1.93 s in tools
+/// ├─ step 1
+/// │  └─ search_location {"query":"Paris"} 0.12 s, 412 B
+/// └─ step 2
+///    └─ search_hotel {"adults":2,"checkIn":"next Friday","geoId":187147} 1.81 s, 8.4 KB
+/// ```
+///
+/// Calls that don't appear in `turn`, as when the turn failed, are listed
+/// after the steps.
+pub fn call_tree(turn: &[Message], records: &[ToolCallRecord]) -> String {
+    let mut unmatched: Vec<Option<&ToolCallRecord>> = records.iter().map(Some).collect();
+    let mut steps: Vec<Vec<&ToolCallRecord>> = Vec::new();
+    for message in turn {
+        let Message::Assistant { content } = message else {
+            continue;
+        };
+        let mut step = Vec::new();
+        for content in content.iter() {
+            let AssistantContent::ToolCall(call) = content else {
+                continue;
+            };
+            let name = call.function.name.as_str();
+            // the same arguments if possible, in case the step called a tool twice
+            let position = unmatched
+                .iter()
+                .position(|record| {
+                    record.is_some_and(|record| record.tool == name && record.arguments == call.function.arguments)
+                })
+                .or_else(|| unmatched.iter().position(|record| record.is_some_and(|record| record.tool == name)));
+            if let Some(record) = position.and_then(|position| unmatched[position].take()) {
+                step.push(record);
+            }
+        }
+        if !step.is_empty() {
+            steps.push(step);
+        }
+    }
+    let unmatched: Vec<&ToolCallRecord> = unmatched.into_iter().flatten().collect();
+
+    let total_ms: u64 = records.iter().map(|record| record.latency_ms).sum();
+    let mut tree = format!(
+        "turn: {} steps, {} tool calls, {:.2} s in tools\n",
+        steps.len(),
+        records.len(),
+        total_ms as f64 / 1000.0
+    );
+    let branches = steps.len() + unmatched.len();
+    for (i, step) in steps.iter().enumerate() {
+        let last_branch = i + 1 == branches;
+        tree.push_str(&format!("{} step {}\n", if last_branch { "└─" } else { "├─" }, i + 1));
+        let indent = if last_branch { "   " } else { "│  " };
+        for (j, record) in step.iter().enumerate() {
+            let branch = if j + 1 == step.len() { "└─" } else { "├─" };
+            tree.push_str(&format!("{}{} {}\n", indent, branch, describe(record)));
+        }
+    }
+    for (i, record) in unmatched.iter().enumerate() {
+        let branch = if steps.len() + i + 1 == branches { "└─" } else { "├─" };
+        tree.push_str(&format!("{} {}\n", branch, describe(record)));
+    }
+    tree
+}
+
+fn describe(record: &ToolCallRecord) -> String {
+    let mut arguments = record.arguments.to_string();
+    if arguments.chars().count() > MAX_TREE_ARGUMENTS {
+        arguments = arguments.chars().take(MAX_TREE_ARGUMENTS).collect::<String>() + "…";
+    }
+    let outcome = match &record.error {
+        Some(error) => format!("error: {}", error),
+        None if record.result_bytes >= 1024 => format!("{:.1} KB", record.result_bytes as f64 / 1024.0),
+        None => format!("{} B", record.result_bytes),
+    };
+    format!(
+        "{} {} {:.2} s, {}",
+        record.tool,
+        arguments,
+        record.latency_ms as f64 / 1000.0,
+        outcome
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rig::message::{ToolResultContent, UserContent};
+    use rig::OneOrMany;
+    use serde::Deserialize;
+    use serde_json::json;
+    use crate::tool_error::{HotelSearchError, Recoverable};
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct EchoArgs {
+        query: String,
+        #[serde(rename = "openNow", default)]
+        open_now: bool,
+    }
+
+    struct EchoTool;
+
+    impl Tool for EchoTool {
+        const NAME: &'static str = "echo";
+        type Error = HotelSearchError;
+        type Args = EchoArgs;
+        type Output = String;
+
+        async fn definition(&self, _prompt: String) -> ToolDefinition {
+            ToolDefinition {
+                name: Self::NAME.to_string(),
+                description: String::new(),
+                parameters: json!({}),
+            }
+        }
+
+        async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+            Ok(format!("{} {}", args.query, args.open_now))
+        }
+    }
+
+    fn record(tool: &str, arguments: serde_json::Value, latency_ms: u64, result_bytes: usize, error: Option<&str>) -> ToolCallRecord {
+        ToolCallRecord {
+            timestamp: Utc::now(),
+            session: "test".to_string(),
+            tool: tool.to_string(),
+            arguments,
+            latency_ms,
+            result_bytes,
+            error: error.map(str::to_string),
+        }
+    }
+
+    fn tool_calls(calls: &[(&str, &str, serde_json::Value)]) -> Message {
+        let content = calls
+            .iter()
+            .map(|(id, name, arguments)| AssistantContent::tool_call(*id, *name, arguments.clone()))
+            .collect::<Vec<_>>();
+        Message::Assistant { content: OneOrMany::many(content).unwrap() }
+    }
+
+    fn tool_result(id: &str) -> Message {
+        Message::User {
+            content: OneOrMany::one(UserContent::tool_result(id, OneOrMany::one(ToolResultContent::text("[]")))),
+        }
+    }
+
+    #[tokio::test]
+    async fn calls_are_recorded_with_the_arguments_as_sent() {
+        let audit = AuditLog::new("test").keep_records();
+        let (watcher, mut events) = mpsc::unbounded_channel();
+        audit.watch(watcher);
+        let tool = Audited { tool: Recoverable(EchoTool), audit: audit.clone() };
+
+        tool.call(json!({ "query": "Lisbon" })).await.unwrap();
+        // rejected before the tool runs, but still recorded
+        tool.call(json!({ "openNow": true })).await.unwrap();
+
+        let records = audit.take_records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].tool, "echo");
+        assert_eq!(records[0].arguments, json!({ "query": "Lisbon" }));
+        assert_eq!(records[0].result_bytes, "\"Lisbon false\"".len());
+        assert!(records[0].error.is_none());
+        assert_eq!(records[1].arguments, json!({ "openNow": true }));
+        assert_eq!(records[1].error.as_deref(), Some("Invalid query: missing field `query`"));
+        assert!(audit.take_records().is_empty());
+
+        assert!(matches!(events.recv().await, Some(ToolCallEvent::Started { tool, .. }) if tool == "echo"));
+        assert!(matches!(events.recv().await, Some(ToolCallEvent::Finished(record)) if record.error.is_none()));
+        assert!(matches!(events.recv().await, Some(ToolCallEvent::Started { .. })));
+        assert!(matches!(events.recv().await, Some(ToolCallEvent::Finished(record)) if record.error.is_some()));
+        audit.unwatch();
+        tool.call(json!({ "query": "Porto" })).await.unwrap();
+        // the watcher was dropped, so nothing more arrives
+        assert!(events.recv().await.is_none());
+    }
+
+    #[test]
+    fn the_tree_groups_calls_by_step() {
+        let friday = json!({ "geoId": 187147, "checkIn": "Friday" });
+        let someday = json!({ "geoId": 187147, "checkIn": "someday" });
+        let turn = [
+            Message::user("Hotels in Paris on Friday"),
+            tool_calls(&[("call_1", "search_location", json!({ "query": "Paris" }))]),
+            tool_result("call_1"),
+            tool_calls(&[("call_2", "search_hotel", friday.clone()), ("call_3", "search_hotel", someday.clone())]),
+            tool_result("call_2"),
+            tool_result("call_3"),
+            Message::assistant("Here are some hotels."),
+        ];
+        // the hotel searches ran concurrently and finished in the other order
+        let records = [
+            record("search_location", json!({ "query": "Paris" }), 120, 412, None),
+            record("search_hotel", someday, 900, 0, Some("Invalid checkIn: someday")),
+            record("search_hotel", friday, 1810, 8602, None),
+            record("shortlist_compare", json!({}), 4, 30, None),
+        ];
+
+        assert_eq!(
+            call_tree(&turn, &records),
+            "turn: 2 steps, 4 tool calls, 2.83 s in tools\n\
+             ├─ step 1\n\
+             │  └─ search_location {\"query\":\"Paris\"} 0.12 s, 412 B\n\
+             ├─ step 2\n\
+             │  ├─ search_hotel {\"checkIn\":\"Friday\",\"geoId\":187147} 1.81 s, 8.4 KB\n\
+             │  └─ search_hotel {\"checkIn\":\"someday\",\"geoId\":187147} 0.90 s, error: Invalid checkIn: someday\n\
+             └─ shortlist_compare {} 0.00 s, 30 B\n"
+        );
+    }
+
+    #[test]
+    fn calls_of_a_failed_turn_are_listed_without_steps() {
+        let records = [
+            record("search_location", json!({ "query": "Paris" }), 100, 412, None),
+            record("search_hotel", json!({ "query": "x".repeat(100) }), 2000, 0, Some("Tripadvisor request failed")),
+        ];
+
+        let tree = call_tree(&[], &records);
+        let lines: Vec<&str> = tree.lines().collect();
+        assert_eq!(lines[0], "turn: 0 steps, 2 tool calls, 2.10 s in tools");
+        assert_eq!(lines[1], "├─ search_location {\"query\":\"Paris\"} 0.10 s, 412 B");
+        let arguments = format!("{}…", &format!("{{\"query\":\"{}\"}}", "x".repeat(100))[..MAX_TREE_ARGUMENTS]);
+        assert_eq!(lines[2], format!("└─ search_hotel {} 2.00 s, error: Tripadvisor request failed", arguments));
+        assert_eq!(call_tree(&[], &[]), "turn: 0 steps, 0 tool calls, 0.00 s in tools\n");
+    }
+}
@@ -0,0 +1,261 @@
+use rig::agent::Agent;
+use rig::completion::{CompletionModel, Message, Prompt, PromptError};
+use rig::message::{AssistantContent, ToolResultContent, UserContent};
+
+/// How many tool calls the agent may chain while answering a single message.
+const MAX_TOOL_STEPS: usize = 5;
+/// Older turns are dropped from the history.
+pub const MAX_CONVERSATION_TURNS: usize = 10;
+
+/// The conversation with the agent, kept across loop iterations in the CLI and
+/// per session in the web server.
+///
+/// The history holds every message sent to and from the model, including tool
+/// calls and their results, so follow-ups like "what about the second hotel?"
+/// can be answered from earlier searches. Only the most recent `max_turns`
+/// turns are kept, a turn being one user message plus everything the agent did
+/// to answer it.
+pub struct Conversation {
+    history: Vec<Message>,
+    max_turns: usize,
+}
+
+impl Conversation {
+    pub fn new(max_turns: usize) -> Self {
+        Self {
+            history: Vec::new(),
+            max_turns,
+        }
+    }
+
+    /// Continues a saved conversation.
+    pub fn resume(history: Vec<Message>, max_turns: usize) -> Self {
+        let mut conversation = Self { history, max_turns };
+        conversation.trim();
+        conversation
+    }
+
+    /// The latest message and everything the agent did to answer it.
+    pub fn latest_turn(&self) -> &[Message] {
+        let turn_start = self
+            .history
+            .iter()
+            .rposition(is_user_prompt)
+            .unwrap_or(0);
+        &self.history[turn_start..]
+    }
+
+    /// Sends the user's message with the conversation so far and records the
+    /// exchange in the history.
+    pub async fn send<M: CompletionModel>(
+        &mut self,
+        agent: &Agent<M>,
+        user_input: &str,
+    ) -> Result<String, PromptError> {
+        let history_len = self.history.len();
+        let response = agent
+            .prompt(user_input)
+            .with_history(&mut self.history)
+            .multi_turn(MAX_TOOL_STEPS)
+            .await;
+
+        self.record(history_len, response)
+    }
+
+    // keeps the exchange if the agent answered, and otherwise rolls the history
+    // back to `history_len`
+    fn record<E>(&mut self, history_len: usize, response: Result<String, E>) -> Result<String, E> {
+        match response {
+            Ok(response) => {
+                self.trim();
+                Ok(response)
+            }
+            Err(e) => {
+                // a failed turn may end on a tool call without its result,
+                // which the API would reject on the next message
+                self.history.truncate(history_len);
+                Err(e)
+            }
+        }
+    }
+
+    /// What calls to `tool` returned while answering the latest message, as the
+    /// JSON the tool produced.
+    pub fn latest_tool_outputs(&self, tool: &str) -> Vec<String> {
+        let turn = self.latest_turn();
+
+        let call_ids: Vec<&str> = turn
+            .iter()
+            .filter_map(|message| match message {
+                Message::Assistant { content } => Some(content.iter()),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|content| match content {
+                AssistantContent::ToolCall(call) if call.function.name == tool => Some(call.id.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        turn.iter()
+            .filter_map(|message| match message {
+                Message::User { content } => Some(content.iter()),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|content| match content {
+                UserContent::ToolResult(result) if call_ids.contains(&result.id.as_str()) => Some(result),
+                _ => None,
+            })
+            .flat_map(|result| result.content.iter())
+            .filter_map(|content| match content {
+                ToolResultContent::Text(text) => Some(text.text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // drops the oldest turns beyond the limit. Whole turns are removed so that
+    // a tool call is never separated from its result.
+    fn trim(&mut self) {
+        let turn_starts: Vec<usize> = self
+            .history
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| is_user_prompt(message))
+            .map(|(i, _)| i)
+            .collect();
+        if turn_starts.len() > self.max_turns {
+            let cut = turn_starts[turn_starts.len() - self.max_turns];
+            self.history.drain(..cut);
+        }
+    }
+}
+
+// a user message carrying text starts a new turn; tool results are also sent
+// as user messages but belong to the turn before them
+fn is_user_prompt(message: &Message) -> bool {
+    match message {
+        Message::User { content } => content
+            .iter()
+            .any(|c| matches!(c, UserContent::Text(_))),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rig::OneOrMany;
+
+    fn tool_call(id: &str, name: &str) -> Message {
+        Message::Assistant {
+            content: OneOrMany::one(AssistantContent::tool_call(id, name, serde_json::json!({}))),
+        }
+    }
+
+    fn tool_result(id: &str, output: &str) -> Message {
+        Message::User {
+            content: OneOrMany::one(UserContent::tool_result(id, OneOrMany::one(ToolResultContent::text(output)))),
+        }
+    }
+
+    // a user message, one tool call with its result and the agent's answer
+    fn turn(n: usize) -> Vec<Message> {
+        vec![
+            Message::user(format!("question {n}")),
+            tool_call(&format!("call_{n}"), "search_hotels"),
+            tool_result(&format!("call_{n}"), &format!("hotels {n}")),
+            Message::assistant(format!("answer {n}")),
+        ]
+    }
+
+    fn text(message: &Message) -> Option<&str> {
+        match message {
+            Message::User { content } => content.iter().find_map(|c| match c {
+                UserContent::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn trim_drops_the_oldest_whole_turns() {
+        let history: Vec<Message> = (1..=3).flat_map(turn).collect();
+
+        let conversation = Conversation::resume(history, 2);
+
+        assert_eq!(conversation.history.len(), 8);
+        assert_eq!(text(&conversation.history[0]), Some("question 2"));
+        // the first message kept is the prompt, not a tool result cut off from its call
+        assert!(is_user_prompt(&conversation.history[0]));
+        assert_eq!(conversation.latest_tool_outputs("search_hotels"), ["hotels 3"]);
+    }
+
+    #[test]
+    fn trim_keeps_histories_within_the_limit() {
+        let history: Vec<Message> = (1..=2).flat_map(turn).collect();
+
+        let conversation = Conversation::resume(history, 2);
+
+        assert_eq!(conversation.history.len(), 8);
+    }
+
+    #[test]
+    fn latest_turn_starts_at_the_last_prompt() {
+        let history: Vec<Message> = (1..=2).flat_map(turn).collect();
+        let conversation = Conversation::resume(history, 10);
+
+        let latest = conversation.latest_turn();
+
+        assert_eq!(latest.len(), 4);
+        assert_eq!(text(&latest[0]), Some("question 2"));
+    }
+
+    #[test]
+    fn latest_tool_outputs_are_limited_to_the_latest_turn_and_tool() {
+        let mut history = turn(1);
+        history.extend([
+            Message::user("and near the beach?"),
+            tool_call("call_2", "search_hotels"),
+            tool_call("call_3", "search_locations"),
+            tool_result("call_2", "beach hotels"),
+            tool_result("call_3", "locations"),
+            Message::assistant("Here are some beach hotels."),
+        ]);
+        let conversation = Conversation::resume(history, 10);
+
+        assert_eq!(conversation.latest_tool_outputs("search_hotels"), ["beach hotels"]);
+        assert_eq!(conversation.latest_tool_outputs("search_locations"), ["locations"]);
+        assert!(conversation.latest_tool_outputs("hotel_details").is_empty());
+    }
+
+    #[test]
+    fn a_failed_turn_is_removed_from_the_history() {
+        let mut conversation = Conversation::resume(turn(1), 10);
+        let history_len = conversation.history.len();
+        // the agent failed after calling a tool, before its result was added
+        conversation.history.push(Message::user("question 2"));
+        conversation.history.push(tool_call("call_2", "search_hotels"));
+
+        let result = conversation.record(history_len, Err("rate limited"));
+
+        assert_eq!(result, Err("rate limited"));
+        assert_eq!(conversation.history.len(), history_len);
+        assert_eq!(text(&conversation.latest_turn()[0]), Some("question 1"));
+    }
+
+    #[test]
+    fn a_successful_turn_is_kept_and_the_history_trimmed() {
+        let mut conversation = Conversation::resume(turn(1), 1);
+        let history_len = conversation.history.len();
+        conversation.history.extend(turn(2));
+
+        let result = conversation.record::<()>(history_len, Ok("answer 2".to_string()));
+
+        assert_eq!(result, Ok("answer 2".to_string()));
+        assert_eq!(conversation.history.len(), 4);
+        assert_eq!(text(&conversation.history[0]), Some("question 2"));
+    }
+}
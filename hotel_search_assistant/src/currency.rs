@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::path::Path;
+use serde::Deserialize;
+use crate::model::Price;
+
+/// Source of exchange rates used to show prices in the user's currency.
+pub trait RateProvider: Send + Sync {
+    /// How many units of `to` one unit of `from` buys, if known.
+    fn rate(&self, from: &str, to: &str) -> Option<f64>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RateError {
+    #[error("Could not read the exchange rate file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid exchange rate file: {0}")]
+    Invalid(#[from] serde_json::Error),
+}
+
+/// Fixed rates against one base currency, e.g. loaded from
+/// `{"base": "USD", "rates": {"EUR": 0.92, "GBP": 0.79}}`.
+#[derive(Debug, Default, Deserialize)]
+pub struct RateTable {
+    base: String,
+    rates: HashMap<String, f64>,
+}
+
+impl RateTable {
+    pub fn load(path: &Path) -> Result<Self, RateError> {
+        let json = std::fs::read_to_string(path)?;
+        let mut table: RateTable = serde_json::from_str(&json)?;
+        table.base = table.base.to_uppercase();
+        table.rates = table
+            .rates
+            .into_iter()
+            .filter(|(_, rate)| *rate > 0.0)
+            .map(|(currency, rate)| (currency.to_uppercase(), rate))
+            .collect();
+        Ok(table)
+    }
+
+    pub fn rate_count(&self) -> usize {
+        self.rates.len()
+    }
+
+    // units of `currency` per unit of the base currency
+    fn per_base(&self, currency: &str) -> Option<f64> {
+        if currency == self.base {
+            return Some(1.0);
+        }
+        self.rates.get(currency).copied()
+    }
+}
+
+impl RateProvider for RateTable {
+    fn rate(&self, from: &str, to: &str) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        // cross rates go through the base currency
+        Some(self.per_base(to)? / self.per_base(from)?)
+    }
+}
+
+/// The price in `currency`, or `None` when there's no rate for it.
+pub fn convert(price: &Price, currency: &str, rates: &dyn RateProvider) -> Option<Price> {
+    let rate = rates.rate(&price.currency, currency)?;
+    Some(Price {
+        amount: (price.amount * rate * 100.0).round() / 100.0,
+        currency: currency.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates() -> RateTable {
+        RateTable {
+            base: "USD".to_string(),
+            rates: HashMap::from([("EUR".to_string(), 0.9), ("GBP".to_string(), 0.8), ("INR".to_string(), 83.0)]),
+        }
+    }
+
+    fn price(amount: f64, currency: &str) -> Price {
+        Price {
+            amount,
+            currency: currency.to_string(),
+        }
+    }
+
+    #[test]
+    fn rates_against_the_base_and_across_it() {
+        let rates = rates();
+        assert_eq!(rates.rate("USD", "USD"), Some(1.0));
+        assert_eq!(rates.rate("USD", "EUR"), Some(0.9));
+        assert_eq!(rates.rate("EUR", "USD"), Some(1.0 / 0.9));
+        // EUR to GBP goes through USD
+        assert!((rates.rate("EUR", "GBP").unwrap() - 0.8 / 0.9).abs() < 1e-12);
+        assert!((rates.rate("GBP", "INR").unwrap() - 103.75).abs() < 1e-9);
+        assert_eq!(rates.rate("XYZ", "XYZ"), Some(1.0));
+        assert_eq!(rates.rate("USD", "XYZ"), None);
+        assert_eq!(rates.rate("XYZ", "EUR"), None);
+    }
+
+    #[test]
+    fn converts_to_whole_cents() {
+        let rates = rates();
+        assert_eq!(convert(&price(100.0, "USD"), "EUR", &rates), Some(price(90.0, "EUR")));
+        // 123.45 / 0.9 = 137.1666…
+        assert_eq!(convert(&price(123.45, "EUR"), "USD", &rates), Some(price(137.17, "USD")));
+        // 10 * 0.8 / 0.9 = 8.888…
+        assert_eq!(convert(&price(10.0, "EUR"), "GBP", &rates), Some(price(8.89, "GBP")));
+        assert_eq!(convert(&price(99.99, "USD"), "USD", &rates), Some(price(99.99, "USD")));
+        assert_eq!(convert(&price(5000.0, "INR"), "CHF", &rates), None);
+    }
+
+    #[test]
+    fn loads_rates_case_insensitively_and_drops_invalid_ones() {
+        let path = std::env::temp_dir().join(format!("rates-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"{"base": "usd", "rates": {"eur": 0.9, "gbp": 0, "jpy": -1}}"#).unwrap();
+        let rates = RateTable::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(rates.rate_count(), 1);
+        assert_eq!(rates.rate("USD", "EUR"), Some(0.9));
+        assert_eq!(rates.rate("USD", "GBP"), None);
+    }
+}
@@ -0,0 +1,479 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+
+/// Longest stay a search may ask for.
+pub const MAX_NIGHTS: i64 = 30;
+
+// full names and the usual abbreviations; prefixes like "maybe" don't count
+const MONTHS: [&[&str]; 12] = [
+    &["january", "jan"],
+    &["february", "feb"],
+    &["march", "mar"],
+    &["april", "apr"],
+    &["may"],
+    &["june", "jun"],
+    &["july", "jul"],
+    &["august", "aug"],
+    &["september", "sept", "sep"],
+    &["october", "oct"],
+    &["november", "nov"],
+    &["december", "dec"],
+];
+const WEEKDAYS: [(&[&str], Weekday); 7] = [
+    (&["monday", "mon"], Weekday::Mon),
+    (&["tuesday", "tues", "tue"], Weekday::Tue),
+    (&["wednesday", "wed"], Weekday::Wed),
+    (&["thursday", "thurs", "thur", "thu"], Weekday::Thu),
+    (&["friday", "fri"], Weekday::Fri),
+    (&["saturday", "sat"], Weekday::Sat),
+    (&["sunday", "sun"], Weekday::Sun),
+];
+// separators between the two ends of a range, tried in order
+const RANGE_SEPARATORS: [&str; 6] = [" to ", " until ", " through ", " - ", "–", "-"];
+
+/// Today's date, or the one in `HOTEL_ASSISTANT_TODAY` (YYYY-MM-DD) when set.
+///
+/// Stay dates are worked out from today and end up in the Tripadvisor query,
+/// so recordings only replay on the day they were made unless it's pinned.
+pub fn today() -> NaiveDate {
+    match std::env::var("HOTEL_ASSISTANT_TODAY") {
+        Ok(text) => NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").unwrap_or_else(|_| {
+            tracing::warn!("ignoring HOTEL_ASSISTANT_TODAY={text:?}, expected YYYY-MM-DD");
+            Local::now().date_naive()
+        }),
+        Err(_) => Local::now().date_naive(),
+    }
+}
+
+/// Reasons stay dates are rejected. The messages are meant to be shown to the
+/// user as they are.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum DateError {
+    #[error("Could not understand the date \"{0}\"")]
+    Unrecognised(String),
+    #[error("The check-in date {0} is in the past")]
+    InPast(NaiveDate),
+    #[error("The check-out date {check_out} must be after the check-in date {check_in}")]
+    NoNights { check_in: NaiveDate, check_out: NaiveDate },
+    #[error("Stays can be at most {MAX_NIGHTS} nights, this one is {0} nights")]
+    TooLong(i64),
+}
+
+/// A validated hotel stay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StayDates {
+    pub check_in: NaiveDate,
+    pub check_out: NaiveDate,
+}
+
+impl StayDates {
+    pub fn new(check_in: NaiveDate, check_out: NaiveDate, today: NaiveDate) -> Result<Self, DateError> {
+        if check_in < today {
+            return Err(DateError::InPast(check_in));
+        }
+        let nights = (check_out - check_in).num_days();
+        if nights <= 0 {
+            return Err(DateError::NoNights { check_in, check_out });
+        }
+        if nights > MAX_NIGHTS {
+            return Err(DateError::TooLong(nights));
+        }
+        Ok(Self { check_in, check_out })
+    }
+
+    pub fn nights(&self) -> u32 {
+        (self.check_out - self.check_in).num_days() as u32
+    }
+}
+
+/// Works out the stay from the check-in and check-out the user gave, relative
+/// to `today`.
+///
+/// `check_in` can describe the whole stay ("Aug 8-14", "3 nights from the 10th",
+/// "this weekend"), in which case `check_out` may be left out. Otherwise the stay
+/// defaults to one night, starting tomorrow when there's no check-in either.
+/// Dates without a year are the next occurrence on or after today (for check-in)
+/// or on or after check-in (for check-out).
+pub fn parse_stay(check_in: Option<&str>, check_out: Option<&str>, today: NaiveDate) -> Result<StayDates, DateError> {
+    let (start, range_end) = match check_in {
+        Some(text) => parse_range(text, today)?,
+        None => (today + Duration::days(1), None),
+    };
+    let end = match check_out {
+        Some(text) => parse_date(text, today, start).ok_or_else(|| DateError::Unrecognised(text.to_string()))?,
+        None => range_end.unwrap_or(start + Duration::days(1)),
+    };
+    StayDates::new(start, end, today)
+}
+
+// A start date, and the end date if the text describes a whole stay.
+fn parse_range(text: &str, today: NaiveDate) -> Result<(NaiveDate, Option<NaiveDate>), DateError> {
+    let unrecognised = || DateError::Unrecognised(text.to_string());
+    let normalised = normalise(text);
+
+    if let Some(weekend) = parse_weekend(&normalised, today) {
+        return Ok(weekend);
+    }
+
+    // "3 nights from the 10th", "aug 8 for 3 nights"
+    if let Some((nights, start)) = split_nights(&normalised) {
+        // checked before adding, so a huge count can't overflow the date
+        if nights <= 0 {
+            return Err(unrecognised());
+        }
+        if nights > MAX_NIGHTS {
+            return Err(DateError::TooLong(nights));
+        }
+        let start = parse_date(start, today, today).ok_or_else(unrecognised)?;
+        return Ok((start, Some(start + Duration::days(nights))));
+    }
+
+    if let Some(date) = parse_date(&normalised, today, today) {
+        return Ok((date, None));
+    }
+
+    // "aug 8-14", "aug 8 to sep 2"
+    for separator in RANGE_SEPARATORS {
+        let Some((left, right)) = normalised.split_once(separator) else {
+            continue;
+        };
+        let Some(start) = parse_date(left, today, today) else {
+            continue;
+        };
+        let end = match right.trim().parse::<u32>() {
+            // only a day: same month as the start, or the month after
+            Ok(day) => next_day_of_month(day, start),
+            Err(_) => parse_date(right, today, start),
+        };
+        if let Some(end) = end {
+            return Ok((start, Some(end)));
+        }
+    }
+
+    Err(unrecognised())
+}
+
+/// Parses a single date. `not_before` is used to pick the year (or month) when
+/// the text doesn't give one.
+fn parse_date(text: &str, today: NaiveDate, not_before: NaiveDate) -> Option<NaiveDate> {
+    let text = normalise(text);
+    let text = text.as_str();
+
+    match text {
+        "today" | "tonight" => return Some(today),
+        "tomorrow" => return Some(today + Duration::days(1)),
+        _ => {}
+    }
+    for format in ["%Y-%m-%d", "%Y/%m/%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+            return Some(date);
+        }
+    }
+    // "08-08", "8/8"
+    if let Some((month, day)) = text.split_once(['-', '/'])
+        && let (Ok(month), Ok(day)) = (month.parse(), day.parse())
+    {
+        return next_occurrence(month, day, not_before);
+    }
+    if let Some(date) = parse_weekday(text, today) {
+        return Some(date);
+    }
+
+    // "august 8", "8 aug", "aug 8 2025"
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let months: Vec<u32> = words.iter().filter_map(|word| month_number(word)).collect();
+    // anything else, e.g. a second date, means this isn't a single date
+    if months.len() > 1
+        || words.iter().any(|word| month_number(word).is_none() && word.parse::<u32>().is_err())
+    {
+        return None;
+    }
+    let month = months.first().copied();
+    let mut numbers = words.iter().filter_map(|word| word.parse::<u32>().ok());
+    let day = numbers.next();
+    let year = numbers.find(|number| *number > 31);
+    match (month, day, year) {
+        (Some(month), Some(day), Some(year)) => NaiveDate::from_ymd_opt(year as i32, month, day),
+        (Some(month), Some(day), None) => next_occurrence(month, day, not_before),
+        // "the 10th"
+        (None, Some(day), None) if words.len() == 1 => next_day_of_month(day, not_before),
+        _ => None,
+    }
+}
+
+// "this weekend" is Friday to Sunday, starting today if the weekend has begun.
+// On a Sunday it's the coming weekend.
+fn parse_weekend(text: &str, today: NaiveDate) -> Option<(NaiveDate, Option<NaiveDate>)> {
+    let weeks_ahead = match text {
+        "this weekend" | "weekend" => 0,
+        "next weekend" => 1,
+        _ => return None,
+    };
+    let friday = match today.weekday() {
+        Weekday::Sat => today - Duration::days(1),
+        Weekday::Sun => today + Duration::days(5),
+        weekday => today + Duration::days((4 - weekday.num_days_from_monday() as i64).rem_euclid(7)),
+    } + Duration::weeks(weeks_ahead);
+    let sunday = friday + Duration::days(2);
+    Some((friday.max(today), Some(sunday)))
+}
+
+// "friday" and "this friday" may be today, "next friday" is the first one after today
+fn parse_weekday(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let (name, skip_today) = match text.split_once(' ') {
+        Some(("next", name)) => (name, true),
+        Some(("this", name)) => (name, false),
+        None => (text, false),
+        _ => return None,
+    };
+    let weekday = WEEKDAYS
+        .iter()
+        .find(|(names, _)| names.contains(&name))
+        .map(|(_, weekday)| *weekday)?;
+    let mut days = (weekday.num_days_from_monday() as i64 - today.weekday().num_days_from_monday() as i64).rem_euclid(7);
+    if days == 0 && skip_today {
+        days = 7;
+    }
+    Some(today + Duration::days(days))
+}
+
+// Returns the number of nights and the rest of the text, for "3 nights from
+// the 10th" and "aug 8 for 3 nights".
+fn split_nights(text: &str) -> Option<(i64, &str)> {
+    if let Some((nights, start)) = text.split_once(" nights from ").or_else(|| text.split_once(" night from ")) {
+        return Some((nights.trim().parse().ok()?, start));
+    }
+    let (start, nights) = text.split_once(" for ")?;
+    let nights = nights.strip_suffix(" nights").or_else(|| nights.strip_suffix(" night"))?;
+    Some((nights.trim().parse().ok()?, start))
+}
+
+fn month_number(word: &str) -> Option<u32> {
+    MONTHS
+        .iter()
+        .position(|names| names.contains(&word))
+        .map(|index| index as u32 + 1)
+}
+
+// the first month/day on or after `not_before`
+fn next_occurrence(month: u32, day: u32, not_before: NaiveDate) -> Option<NaiveDate> {
+    // a leap day may be several years away
+    (not_before.year()..not_before.year() + 8)
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+        .find(|date| *date >= not_before)
+}
+
+// the first date with this day of the month on or after `not_before`
+fn next_day_of_month(day: u32, not_before: NaiveDate) -> Option<NaiveDate> {
+    let mut year = not_before.year();
+    let mut month = not_before.month();
+    for _ in 0..12 {
+        if let Some(date) = NaiveDate::from_ymd_opt(year, month, day).filter(|date| *date >= not_before) {
+            return Some(date);
+        }
+        (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    }
+    None
+}
+
+// lowercases, drops filler words and commas, and turns "8th" into "8"
+fn normalise(text: &str) -> String {
+    text.to_lowercase()
+        .replace(',', " ")
+        .split_whitespace()
+        .filter(|word| !matches!(*word, "the" | "of" | "on"))
+        .map(|word| {
+            let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            let suffix = &word[digits.len()..];
+            if !digits.is_empty()
+                && digits.chars().all(|c| c.is_ascii_digit())
+                && matches!(suffix, "st" | "nd" | "rd" | "th")
+            {
+                digits
+            } else {
+                word
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn stay(check_in: Option<&str>, check_out: Option<&str>, today: &str) -> Result<(NaiveDate, NaiveDate), DateError> {
+        parse_stay(check_in, check_out, date(today)).map(|stay| (stay.check_in, stay.check_out))
+    }
+
+    // (check-in, check-out, expected check-in, expected check-out), all on Wednesday 2025-08-06
+    const STAYS: &[(Option<&str>, Option<&str>, &str, &str)] = &[
+        (None, None, "2025-08-07", "2025-08-08"),
+        (Some("today"), None, "2025-08-06", "2025-08-07"),
+        (Some("tomorrow"), Some("Aug 10"), "2025-08-07", "2025-08-10"),
+        (Some("2025-08-20"), Some("2025-08-22"), "2025-08-20", "2025-08-22"),
+        (Some("Aug 8-14"), None, "2025-08-08", "2025-08-14"),
+        (Some("August 8th to 14th"), None, "2025-08-08", "2025-08-14"),
+        (Some("Aug 8 – Aug 14"), None, "2025-08-08", "2025-08-14"),
+        // the end day is in the next month
+        (Some("Aug 28-3"), None, "2025-08-28", "2025-09-03"),
+        (Some("Aug 28 to Sep 2"), None, "2025-08-28", "2025-09-02"),
+        (Some("8/20"), Some("8/23"), "2025-08-20", "2025-08-23"),
+        (Some("the 10th"), None, "2025-08-10", "2025-08-11"),
+        (Some("3 nights from the 10th"), None, "2025-08-10", "2025-08-13"),
+        (Some("aug 8 for 2 nights"), None, "2025-08-08", "2025-08-10"),
+        (Some("1 night from tomorrow"), None, "2025-08-07", "2025-08-08"),
+        (Some("30 nights from tomorrow"), None, "2025-08-07", "2025-09-06"),
+        (Some("friday"), Some("sunday"), "2025-08-08", "2025-08-10"),
+        (Some("next Friday"), None, "2025-08-08", "2025-08-09"),
+        (Some("this wednesday"), None, "2025-08-06", "2025-08-07"),
+        (Some("next wednesday"), None, "2025-08-13", "2025-08-14"),
+        // no year: the next occurrence, so early August is next year
+        (Some("Aug 1"), Some("Aug 3"), "2026-08-01", "2026-08-03"),
+    ];
+
+    #[test]
+    fn parses_stays() {
+        for (check_in, check_out, expected_in, expected_out) in STAYS {
+            assert_eq!(
+                stay(*check_in, *check_out, "2025-08-06"),
+                Ok((date(expected_in), date(expected_out))),
+                "{check_in:?} / {check_out:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn next_weekday_skips_today() {
+        // a Friday
+        assert_eq!(stay(Some("friday"), None, "2025-08-08").unwrap().0, date("2025-08-08"));
+        assert_eq!(stay(Some("next friday"), None, "2025-08-08").unwrap().0, date("2025-08-15"));
+    }
+
+    #[test]
+    fn accepts_full_and_abbreviated_names() {
+        let today = date("2025-08-06");
+        for name in ["thursday", "thurs", "thur", "thu"] {
+            assert_eq!(parse_weekday(name, today), Some(date("2025-08-07")), "{name}");
+        }
+        assert_eq!(parse_weekday("next tues", today), Some(date("2025-08-12")));
+        for (word, month) in [("september", 9), ("sept", 9), ("sep", 9), ("may", 5), ("jan", 1)] {
+            assert_eq!(month_number(word), Some(month), "{word}");
+        }
+        assert_eq!(stay(Some("sept 10"), None, "2025-08-06").unwrap().0, date("2025-09-10"));
+    }
+
+    #[test]
+    fn rejects_words_that_only_start_like_a_name() {
+        let today = date("2025-08-06");
+        for text in ["next month", "wedding", "friends", "this sunset", "next mont"] {
+            assert_eq!(parse_weekday(text, today), None, "{text}");
+        }
+        for word in ["maybe", "marching", "junk", "decade", "ma"] {
+            assert_eq!(month_number(word), None, "{word}");
+        }
+        assert_eq!(
+            stay(Some("next month"), None, "2025-08-06"),
+            Err(DateError::Unrecognised("next month".to_string()))
+        );
+        assert_eq!(
+            stay(Some("maybe 10"), None, "2025-08-06"),
+            Err(DateError::Unrecognised("maybe 10".to_string()))
+        );
+    }
+
+    #[test]
+    fn weekends_from_each_weekday() {
+        // Monday 2025-08-04 to Sunday 2025-08-10
+        let expected = [
+            ("2025-08-04", "2025-08-08", "2025-08-10"),
+            ("2025-08-05", "2025-08-08", "2025-08-10"),
+            ("2025-08-06", "2025-08-08", "2025-08-10"),
+            ("2025-08-07", "2025-08-08", "2025-08-10"),
+            ("2025-08-08", "2025-08-08", "2025-08-10"),
+            // the weekend has begun, so it starts today
+            ("2025-08-09", "2025-08-09", "2025-08-10"),
+            // too late for this one, so the coming weekend
+            ("2025-08-10", "2025-08-15", "2025-08-17"),
+        ];
+        for (today, friday, sunday) in expected {
+            assert_eq!(stay(Some("this weekend"), None, today), Ok((date(friday), date(sunday))), "{today}");
+        }
+        assert_eq!(
+            stay(Some("next weekend"), None, "2025-08-06"),
+            Ok((date("2025-08-15"), date("2025-08-17")))
+        );
+    }
+
+    #[test]
+    fn stays_roll_over_into_the_next_year() {
+        let today = "2025-12-20";
+        assert_eq!(stay(Some("Dec 30 to Jan 2"), None, today), Ok((date("2025-12-30"), date("2026-01-02"))));
+        assert_eq!(stay(Some("Dec 28-3"), None, today), Ok((date("2025-12-28"), date("2026-01-03"))));
+        assert_eq!(stay(Some("Jan 3-5"), None, today), Ok((date("2026-01-03"), date("2026-01-05"))));
+        assert_eq!(stay(Some("Dec 31"), Some("Jan 1"), today), Ok((date("2025-12-31"), date("2026-01-01"))));
+        assert_eq!(stay(Some("the 2nd"), None, today), Ok((date("2026-01-02"), date("2026-01-03"))));
+    }
+
+    #[test]
+    fn rejects_past_dates() {
+        assert_eq!(
+            stay(Some("2025-08-01"), Some("2025-08-03"), "2025-08-06"),
+            Err(DateError::InPast(date("2025-08-01")))
+        );
+        assert_eq!(
+            stay(Some("yesterday"), None, "2025-08-06"),
+            Err(DateError::Unrecognised("yesterday".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_stays_without_nights() {
+        assert_eq!(
+            stay(Some("2025-08-10"), Some("2025-08-10"), "2025-08-06"),
+            Err(DateError::NoNights {
+                check_in: date("2025-08-10"),
+                check_out: date("2025-08-10"),
+            })
+        );
+        assert_eq!(
+            stay(Some("0 nights from tomorrow"), None, "2025-08-06"),
+            Err(DateError::Unrecognised("0 nights from tomorrow".to_string()))
+        );
+        assert_eq!(
+            stay(Some("-2 nights from tomorrow"), None, "2025-08-06"),
+            Err(DateError::Unrecognised("-2 nights from tomorrow".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_long_stays_without_overflowing() {
+        assert_eq!(stay(Some("31 nights from tomorrow"), None, "2025-08-06"), Err(DateError::TooLong(31)));
+        assert_eq!(
+            stay(Some("1000000000 nights from tomorrow"), None, "2025-08-06"),
+            Err(DateError::TooLong(1_000_000_000))
+        );
+        assert_eq!(
+            stay(Some("tomorrow for 9223372036854775807 nights"), None, "2025-08-06"),
+            Err(DateError::TooLong(i64::MAX))
+        );
+        assert_eq!(
+            stay(Some("2025-08-07"), Some("2025-12-25"), "2025-08-06"),
+            Err(DateError::TooLong(140))
+        );
+    }
+
+    #[test]
+    fn rejects_unrecognised_text() {
+        for text in ["soon", "aug", "aug 8 and 9", "the 32nd", "feb 30"] {
+            assert_eq!(
+                stay(Some(text), None, "2025-08-06"),
+                Err(DateError::Unrecognised(text.to_string())),
+                "{text}"
+            );
+        }
+    }
+}
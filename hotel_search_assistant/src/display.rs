@@ -0,0 +1,66 @@
+use std::fmt;
+
+use crate::model::{HotelResult, Price};
+
+/// Renders hotel search results for the terminal.
+pub fn format_hotels(hotels: &[HotelResult]) -> String {
+    if hotels.is_empty() {
+        return "No hotels found matching your criteria.\n".to_string();
+    }
+
+    let mut output = String::from("🏨 Hotel options:\n\n");
+    for (i, hotel) in hotels.iter().enumerate() {
+        output.push_str(&format!("{}. {}\n", i + 1, hotel.name));
+
+        if let Some(rating) = hotel.rating {
+            match hotel.review_count {
+                Some(count) => output.push_str(&format!("   Rating: {}★ ({} reviews)\n", rating, count)),
+                None => output.push_str(&format!("   Rating: {}★\n", rating)),
+            }
+        }
+        if !hotel.features.is_empty() {
+            output.push_str(&format!("   • Features: {}\n", hotel.features.join(", ")));
+        }
+        if let Some(neighbourhood) = &hotel.neighbourhood {
+            output.push_str(&format!("   • Location: {}\n", neighbourhood));
+        }
+        if let Some(distance) = hotel.distance_km {
+            output.push_str(&format!("   • Distance: {:.1} km\n", distance));
+        } else if hotel.distance_unknown {
+            output.push_str("   • Distance: unknown\n");
+        }
+
+        match (&hotel.price, &hotel.provider) {
+            (Some(price), _) => {
+                output.push_str(&format!("   • Price: {} per night", price));
+                if let Some(original) = &hotel.original_price {
+                    output.push_str(&format!(" (listed as {})", original));
+                }
+                output.push('\n');
+                if let Some(total) = &hotel.total {
+                    output.push_str(&format!(
+                        "   • Total: {} for {} {}, {} {}\n",
+                        total,
+                        hotel.nights,
+                        if hotel.nights == 1 { "night" } else { "nights" },
+                        hotel.rooms,
+                        if hotel.rooms == 1 { "room" } else { "rooms" }
+                    ));
+                }
+            }
+            (None, Some(provider)) => output.push_str(&format!(
+                "   • Price: Not available directly. Check on {}.\n",
+                provider
+            )),
+            (None, None) => output.push_str("   • Price: Not available. Please check provider websites.\n"),
+        }
+        output.push('\n');
+    }
+    output
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} {}", self.amount, self.currency)
+    }
+}
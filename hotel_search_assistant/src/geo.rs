@@ -0,0 +1,114 @@
+use serde_json::{json, Value};
+use crate::model::{Coordinates, HotelResult};
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Great-circle distance between two points, in kilometres.
+pub fn haversine_km(from: &Coordinates, to: &Coordinates) -> f64 {
+    let (lat1, lat2) = (from.latitude.to_radians(), to.latitude.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (to.longitude - from.longitude).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// The mean of the points, close enough to their centre within a city.
+pub fn centre<'a>(points: impl IntoIterator<Item = &'a Coordinates>) -> Option<Coordinates> {
+    let (count, latitude, longitude) = points
+        .into_iter()
+        .fold((0, 0.0, 0.0), |(count, latitude, longitude), point| {
+            (count + 1, latitude + point.latitude, longitude + point.longitude)
+        });
+    (count > 0).then(|| Coordinates {
+        latitude: latitude / count as f64,
+        longitude: longitude / count as f64,
+    })
+}
+
+/// The hotels as a GeoJSON FeatureCollection, for mapping tools. Hotels
+/// without coordinates are left out.
+pub fn to_geojson(hotels: &[HotelResult]) -> Value {
+    let features: Vec<Value> = hotels
+        .iter()
+        .filter_map(|hotel| {
+            let point = hotel.coordinates.as_ref()?;
+            Some(json!({
+                "type": "Feature",
+                // GeoJSON puts longitude first
+                "geometry": { "type": "Point", "coordinates": [point.longitude, point.latitude] },
+                "properties": {
+                    "id": hotel.id,
+                    "name": hotel.name,
+                    "rating": hotel.rating,
+                    "review_count": hotel.review_count,
+                    "price": hotel.price.as_ref().map(|price| price.to_string()),
+                    "total": hotel.total.as_ref().map(|total| total.to_string()),
+                    "neighbourhood": hotel.neighbourhood,
+                    "distance_km": hotel.distance_km,
+                },
+            }))
+        })
+        .collect();
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::Price;
+    use super::*;
+
+    fn point(latitude: f64, longitude: f64) -> Coordinates {
+        Coordinates { latitude, longitude }
+    }
+
+    fn hotel(id: &str, coordinates: Option<Coordinates>) -> HotelResult {
+        HotelResult {
+            id: id.to_string(),
+            name: format!("Hotel {}", id),
+            rating: Some(4.5),
+            review_count: Some(10),
+            price: Some(Price { amount: 120.0, currency: "EUR".to_string() }),
+            coordinates,
+            distance_km: Some(0.42),
+            ..HotelResult::default()
+        }
+    }
+
+    #[test]
+    fn haversine_distances() {
+        let eiffel_tower = point(48.8584, 2.2945);
+        let louvre = point(48.8606, 2.3376);
+        assert_eq!(haversine_km(&eiffel_tower, &eiffel_tower), 0.0);
+        assert!((haversine_km(&eiffel_tower, &louvre) - 3.16).abs() < 0.01);
+        assert_eq!(haversine_km(&eiffel_tower, &louvre), haversine_km(&louvre, &eiffel_tower));
+        // Paris to New York
+        let new_york = point(40.7128, -74.0060);
+        assert!((haversine_km(&eiffel_tower, &new_york) - 5837.0).abs() < 5.0);
+        // a quarter of the way around the equator
+        assert!((haversine_km(&point(0.0, 0.0), &point(0.0, 90.0)) - EARTH_RADIUS_KM * std::f64::consts::FRAC_PI_2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn centre_is_the_mean_point() {
+        let middle = centre(&[point(48.0, 2.0), point(49.0, 3.0)]).unwrap();
+        assert_eq!((middle.latitude, middle.longitude), (48.5, 2.5));
+        assert!(centre(std::iter::empty()).is_none());
+    }
+
+    #[test]
+    fn geojson_puts_longitude_first() {
+        let geojson = to_geojson(&[hotel("1", Some(point(48.8584, 2.2945))), hotel("2", None)]);
+
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        // hotels without coordinates can't be mapped
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0]["type"], "Feature");
+        assert_eq!(features[0]["geometry"]["type"], "Point");
+        assert_eq!(features[0]["geometry"]["coordinates"], json!([2.2945, 48.8584]));
+        assert_eq!(features[0]["properties"]["id"], "1");
+        assert_eq!(features[0]["properties"]["price"], "120.00 EUR");
+        assert_eq!(features[0]["properties"]["total"], Value::Null);
+        assert_eq!(features[0]["properties"]["distance_km"], 0.42);
+    }
+}
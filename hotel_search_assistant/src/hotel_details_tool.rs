@@ -0,0 +1,331 @@
+use crate::tool_error::HotelSearchError;
+use crate::model::{
+    ApiReview, CategoryRating, DetailsMode, HotelDetails, HotelDetailsArgs,
+    HotelDetailsData, HotelDetailsOutput, ReviewSnippet, ReviewSummary,
+};
+use crate::dates::{parse_stay, today, StayDates};
+use crate::shortlist::Shortlist;
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde_json::json;
+use std::collections::HashMap;
+use tripadvisor_client::TripadvisorClient;
+
+const MAX_PHOTOS: usize = 5;
+const MAX_SNIPPETS: usize = 5;
+const SNIPPET_LENGTH: usize = 300;
+// photo URLs come as templates with the size left open
+const PHOTO_WIDTH: &str = "800";
+const PHOTO_HEIGHT: &str = "600";
+
+pub struct HotelDetailsTool {
+    pub client: TripadvisorClient,
+    // details fetched are kept for the shortlist comparison
+    pub shortlist: Shortlist,
+}
+
+impl Tool for HotelDetailsTool {
+    const NAME: &'static str = "get_hotel_details";
+    type Error = HotelSearchError;
+    type Args = HotelDetailsArgs;
+    type Output = HotelDetailsOutput;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Get the details of one hotel from an earlier search: description, amenities, \
+                address, coordinates, photos and review scores by category. In reviews mode it returns \
+                review snippets instead, to quote when summarising what reviewers say.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "hotelId": {
+                        "type": "string",
+                        "description": "The hotel's id, as returned by search_hotel."
+                    },
+                    "checkIn": {
+                        "type": "string",
+                        "description": "The check-in date, in the same formats as search_hotel. Defaults to tomorrow."
+                    },
+                    "checkOut": {
+                        "type": "string",
+                        "description": "The check-out date, in the same formats as search_hotel. Defaults to one night."
+                    },
+                    "mode": {
+                        "type": "string",
+                        "enum": ["details", "reviews"],
+                        "description": "\"details\" for facts about the hotel (amenities, parking, location), \"reviews\" for what guests say about it."
+                    },
+                    "topic": {
+                        "type": "string",
+                        "description": "In reviews mode, what the user asked about (e.g. \"noise\" or \"breakfast\"). Only reviews mentioning it are returned."
+                    }
+                },
+                "required": ["hotelId"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let stay = parse_stay(args.check_in.as_deref(), args.check_out.as_deref(), today())
+            .map_err(|e| HotelSearchError::invalid_stay(e, args.check_out.as_deref()))?;
+
+        tracing::debug!(hotel_id = %args.hotel_id, "fetching hotel details");
+        let data = fetch_details(&self.client, &args.hotel_id, &stay).await?;
+
+        let output = output(args.hotel_id, args.mode, args.topic, data);
+        if let HotelDetailsOutput::Details(details) = &output {
+            self.shortlist.record_details(details);
+        }
+        Ok(output)
+    }
+}
+
+/// Calls the getHotelDetails endpoint for one hotel and stay.
+pub async fn fetch_details(
+    client: &TripadvisorClient,
+    hotel_id: &str,
+    stay: &StayDates,
+) -> Result<HotelDetailsData, HotelSearchError> {
+    let mut query_params = HashMap::new();
+    query_params.insert("id", hotel_id.to_string());
+    query_params.insert("checkIn", stay.check_in.format("%Y-%m-%d").to_string());
+    query_params.insert("checkOut", stay.check_out.format("%Y-%m-%d").to_string());
+
+    Ok(client.get("/api/v1/hotels/getHotelDetails", &query_params).await?)
+}
+
+fn output(id: String, mode: DetailsMode, topic: Option<String>, data: HotelDetailsData) -> HotelDetailsOutput {
+    match mode {
+        DetailsMode::Details => HotelDetailsOutput::Details(details(id, data)),
+        DetailsMode::Reviews => HotelDetailsOutput::Reviews(review_summary(id, data, topic)),
+    }
+}
+
+fn details(id: String, data: HotelDetailsData) -> HotelDetails {
+    let category_ratings = category_ratings(&data);
+    HotelDetails {
+        id,
+        name: data.title,
+        rating: data.rating,
+        review_count: data.number_reviews,
+        ranking: data.ranking_details,
+        description: data
+            .about
+            .map(|about| {
+                about
+                    .content
+                    .into_iter()
+                    .map(|block| block.content)
+                    .filter(|content| !content.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            })
+            .unwrap_or_default(),
+        amenities: data
+            .amenities_screen
+            .into_iter()
+            .flat_map(|group| group.content)
+            .collect(),
+        address: data.location.and_then(|location| location.address),
+        coordinates: data.geo_point,
+        photos: data
+            .photos
+            .into_iter()
+            .take(MAX_PHOTOS)
+            .map(|photo| {
+                photo
+                    .url_template
+                    .replace("{width}", PHOTO_WIDTH)
+                    .replace("{height}", PHOTO_HEIGHT)
+            })
+            .collect(),
+        category_ratings,
+    }
+}
+
+fn review_summary(id: String, data: HotelDetailsData, topic: Option<String>) -> ReviewSummary {
+    let category_ratings = category_ratings(&data);
+    let reviews = data.reviews.map(|reviews| reviews.content).unwrap_or_default();
+    let snippets = reviews
+        .iter()
+        .filter_map(|review| review_snippet(review, topic.as_deref()))
+        .take(MAX_SNIPPETS)
+        .collect();
+
+    ReviewSummary {
+        id,
+        name: data.title,
+        rating: data.rating,
+        review_count: data.number_reviews,
+        category_ratings,
+        topic,
+        snippets,
+    }
+}
+
+fn category_ratings(data: &HotelDetailsData) -> Vec<CategoryRating> {
+    data.reviews
+        .iter()
+        .flat_map(|reviews| &reviews.rating_categories)
+        .map(|category| CategoryRating {
+            category: category.title.clone(),
+            rating: category.rating,
+        })
+        .collect()
+}
+
+// With a topic, only reviews mentioning it are used, and the snippet is the
+// sentence that mentions it. Otherwise the start of the review is used.
+fn review_snippet(review: &ApiReview, topic: Option<&str>) -> Option<ReviewSnippet> {
+    let snippet = match topic {
+        Some(topic) => {
+            let topic = topic.to_lowercase();
+            let sentence = review
+                .text
+                .split_inclusive(['.', '!', '?'])
+                .find(|sentence| sentence.to_lowercase().contains(&topic));
+            match sentence {
+                Some(sentence) => sentence.trim().to_string(),
+                None if review.title.to_lowercase().contains(&topic) => truncate(&review.text),
+                None => return None,
+            }
+        }
+        None => truncate(&review.text),
+    };
+
+    Some(ReviewSnippet {
+        title: review.title.clone(),
+        snippet,
+        rating: review.bubble_rating_text.clone(),
+        published: review.published_date.clone(),
+    })
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(SNIPPET_LENGTH) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn review(title: &str, text: &str) -> ApiReview {
+        ApiReview {
+            title: title.to_string(),
+            text: text.to_string(),
+            bubble_rating_text: Some("5.0".to_string()),
+            published_date: Some("2024-05-01".to_string()),
+        }
+    }
+
+    fn data() -> HotelDetailsData {
+        serde_json::from_value(json!({
+            "title": "Hotel Lutetia",
+            "rating": 4.5,
+            "numberReviews": 1200,
+            "photos": (0..8)
+                .map(|i| json!({ "urlTemplate": format!("https://media.example/{i}.jpg?w={{width}}&h={{height}}") }))
+                .collect::<Vec<_>>(),
+            "about": { "content": [{ "content": "A palace on the Left Bank." }, { "content": "" }] },
+            "amenitiesScreen": [{ "content": ["Pool", "Spa"] }, { "content": ["Free WiFi"] }],
+            "reviews": {
+                "content": [
+                    { "title": "Lovely stay", "text": "Great breakfast. The street was noisy at night." },
+                    { "title": "Quiet rooms", "text": "We slept well." }
+                ],
+                "ratingCategories": [{ "title": "Cleanliness", "rating": 5.0 }]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn topic_snippets_are_the_sentence_mentioning_it() {
+        let review = review("Lovely stay", "Great breakfast. The street was NOISY at night! Staff were kind.");
+
+        let snippet = review_snippet(&review, Some("noisy")).unwrap();
+
+        assert_eq!(snippet.snippet, "The street was NOISY at night!");
+        assert_eq!(snippet.title, "Lovely stay");
+        assert_eq!(snippet.rating.as_deref(), Some("5.0"));
+    }
+
+    #[test]
+    fn reviews_not_mentioning_the_topic_are_skipped() {
+        let review = review("Lovely stay", "Great breakfast.");
+
+        assert!(review_snippet(&review, Some("parking")).is_none());
+    }
+
+    #[test]
+    fn a_topic_in_the_title_uses_the_start_of_the_review() {
+        let review = review("Noisy but central", "Close to everything. Bring earplugs.");
+
+        let snippet = review_snippet(&review, Some("noisy")).unwrap();
+
+        assert_eq!(snippet.snippet, "Close to everything. Bring earplugs.");
+    }
+
+    #[test]
+    fn without_a_topic_every_review_is_used() {
+        let review = review("Lovely stay", "Great breakfast.");
+
+        assert_eq!(review_snippet(&review, None).unwrap().snippet, "Great breakfast.");
+    }
+
+    #[test]
+    fn truncate_keeps_short_text() {
+        assert_eq!(truncate("Très bien"), "Très bien");
+        assert_eq!(truncate(&"a".repeat(SNIPPET_LENGTH)), "a".repeat(SNIPPET_LENGTH));
+    }
+
+    #[test]
+    fn truncate_cuts_on_char_boundaries() {
+        // multi-byte characters, so a byte-based cut would split one
+        let text = "é日".repeat(SNIPPET_LENGTH);
+
+        let truncated = truncate(&text);
+
+        assert_eq!(truncated.chars().count(), SNIPPET_LENGTH + 1);
+        assert!(truncated.starts_with("é日é日"));
+        assert!(truncated.ends_with('…'));
+    }
+
+    #[test]
+    fn photo_urls_are_sized_and_limited() {
+        let details = details("1".to_string(), data());
+
+        assert_eq!(details.photos.len(), MAX_PHOTOS);
+        assert_eq!(details.photos[0], "https://media.example/0.jpg?w=800&h=600");
+    }
+
+    #[test]
+    fn details_mode_returns_the_hotel_facts() {
+        let output = output("1".to_string(), DetailsMode::Details, None, data());
+
+        let HotelDetailsOutput::Details(details) = output else {
+            panic!("expected details");
+        };
+        assert_eq!(details.name, "Hotel Lutetia");
+        assert_eq!(details.description, "A palace on the Left Bank.");
+        assert_eq!(details.amenities, ["Pool", "Spa", "Free WiFi"]);
+        assert_eq!(details.category_ratings[0].category, "Cleanliness");
+    }
+
+    #[test]
+    fn reviews_mode_returns_snippets_for_the_topic() {
+        let output = output("1".to_string(), DetailsMode::Reviews, Some("noisy".to_string()), data());
+
+        let HotelDetailsOutput::Reviews(summary) = output else {
+            panic!("expected reviews");
+        };
+        assert_eq!(summary.topic.as_deref(), Some("noisy"));
+        assert_eq!(summary.snippets.len(), 1);
+        assert_eq!(summary.snippets[0].snippet, "The street was noisy at night.");
+        assert_eq!(summary.category_ratings[0].rating, 5.0);
+    }
+}
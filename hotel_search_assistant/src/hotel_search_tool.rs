@@ -1,39 +1,64 @@
-use crate::model::{ApiError, ApiResponse, HotelSearchArgs, HotelSearchData};
-use crate::utils::parse_and_infer_year;
-use chrono::{Duration, Local, Utc};
+use crate::currency::RateProvider;
+use crate::model::{Coordinates, HotelResult, HotelSearchArgs, HotelSearchData, HotelSort};
+use crate::shortlist::Shortlist;
+use crate::tool_error::HotelSearchError;
+use crate::dates::{parse_stay, today, StayDates};
+use crate::geo::{centre, haversine_km};
+use crate::hotel_details_tool::fetch_details;
+use crate::location_search::geocode;
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde_json::json;
 use std::collections::HashMap;
-use std::env;
+use std::sync::Arc;
+use tripadvisor_client::TripadvisorClient;
 
-pub struct HotelSearchTool;
+// Hotels whose coordinates may be looked up per distance search
+const MAX_COORDINATE_LOOKUPS: usize = 10;
+// Most rooms one search may ask for
+const MAX_ROOMS: u32 = 10;
+// Furthest results page one search may ask for
+const MAX_PAGE: u32 = 10;
+
+pub struct HotelSearchTool {
+    pub client: TripadvisorClient,
+    // converts prices the API returns in another currency than the one requested
+    pub rates: Arc<dyn RateProvider>,
+    // hotels found are kept so they can be shortlisted
+    pub shortlist: Shortlist,
+}
 
 impl Tool for HotelSearchTool {
     const NAME: &'static str = "search_hotel";
     type Error = HotelSearchError;
     type Args = HotelSearchArgs;
-    type Output = String;
+    type Output = Vec<HotelResult>;
 
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: Self::NAME.to_string(),
-            description: "Search for hotel based on the give parameters and location".to_string(),
+            description: "Search for hotel based on the give parameters and location. Returns the hotels \
+                with their rating, review count, price per room and night, total for the whole stay and all rooms, \
+                features, neighbourhood and booking provider. Prices are in the requested currency where an \
+                exchange rate is known; original_price then holds the price as listed.".to_string(),
             parameters: json!({
                             "type": "object",
                             "properties": {
-                                "query": {"type": "string"},
+                                "query": {
+                                    "type": "string",
+                                    "description": "The city or area searched, e.g. \"Paris\". A near landmark is looked up in it."
+                                },
                             "geoId": {
                                 "type": "integer",
                                 "description": "The geographic ID of the location to search in. Obtained from LocationSearchTool."
                             },
                             "checkIn": {
                                 "type": "string",
-                                 "description": "The check-in date. Can be YYYY-MM-DD (e.g., 2025-08-08) or MM-DD / Month Day (e.g., 08-08 or August 8th). If the year is omitted, the program will infer the next upcoming occurrence of that date."
+                                 "description": "The check-in date, or the whole stay, as the user said it. Accepts ISO dates (2025-08-08), month and day (08-08, August 8th), relative dates (tomorrow, next Friday, the 10th) and ranges (Aug 8-14, 3 nights from the 10th, this weekend). If the year is omitted, the next upcoming occurrence of that date is used."
                             },
                             "checkOut": {
                                 "type": "string",
-                                "description": "The check-out date. Can be YYYY-MM-DD (e.g., 2025-08-14) or MM-DD / Month Day (e.g., 08-14 or August 14th). If the year is omitted, the program will infer the next upcoming occurrence of that date."
+                                "description": "The check-out date, in the same formats. Leave it out when checkIn already describes the whole stay; otherwise the stay defaults to one night."
                              },
                             "adults": {
                                 "type": "integer",
@@ -46,31 +71,68 @@ impl Tool for HotelSearchTool {
                             },
                             "rooms": {
                                 "type": "integer",
+                                "minimum": 1,
+                                "maximum": MAX_ROOMS,
                                 "description": "The number of rooms needed."
                             },
+                            "priceMin": {
+                                "type": "integer",
+                                "description": "Lowest nightly price to include, in the search currency."
+                            },
+                            "priceMax": {
+                                "type": "integer",
+                                "description": "Highest nightly price to include, in the search currency."
+                            },
+                            "minRating": {
+                                "type": "integer",
+                                "minimum": 1,
+                                "maximum": 5,
+                                "description": "Only include hotels rated at least this many bubbles by travellers."
+                            },
+                            "hotelClass": {
+                                "type": "array",
+                                "items": {"type": "integer", "minimum": 1, "maximum": 5},
+                                "description": "Star classes to include, e.g. [4, 5] for four and five star hotels."
+                            },
+                            "amenities": {
+                                "type": "array",
+                                "items": {"type": "string"},
+                                "description": "Amenities the hotel must have, e.g. [\"free_wifi\", \"pool\", \"free_parking\", \"breakfast_included\"]."
+                            },
+                            "sort": {
+                                "type": "string",
+                                "enum": ["price", "rating", "distance"],
+                                "description": "Sort order: lowest price first, best traveller rating first, or closest first (to near if given, otherwise the city centre)."
+                            },
+                            "currency": {
+                                "type": "string",
+                                "description": "ISO 4217 currency code for prices and totals, e.g. USD or EUR. Defaults to USD."
+                            },
+                            "near": {
+                                "type": "string",
+                                "description": "A landmark or address to search around, e.g. \"Eiffel Tower\". Each hotel then gets its distance_km from it."
+                            },
+                            "radiusKm": {
+                                "type": "number",
+                                "description": "With near, only include hotels within this many kilometres of it, closest first. Hotels whose distance could not be measured are listed last with distance_unknown set; they may be outside the radius."
+                            },
+                            "page": {
+                                "type": "integer",
+                                "minimum": 1,
+                                "maximum": MAX_PAGE,
+                                "description": "Results page, starting at 1. Request the next page when the user wants more hotels than the first page shows."
+                            },
                         },
-                        "required": ["query", "checkIn", "checkOut", "adults"],
+                        "required": ["query", "checkIn", "adults"],
                             }),
         }
     }
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        // Fetch the API Key
-        let api_key = env::var("RAPIDAPI_KEY").map_err(|_| HotelSearchError::MissingApiKey)?;
-        let today = Local::now().date_naive(); // Get today's date
-        // Build Query Parameters
-        let check_in_date = if let Some(ci_str) = args.check_in {
-            parse_and_infer_year(&ci_str, today)? // Custom parsing function
-        } else {
-            today + Duration::days(1) // Default to tomorrow
-        };
-
-        let check_out_date = if let Some(co_str) = args.check_out {
-            parse_and_infer_year(&co_str, today)? // Custom parsing function
-        } else {
-            check_in_date + Duration::days(1) // Default to day after check-in
-        };
-        let check_in = check_in_date.format("%Y-%m-%d").to_string();
-        let check_out = check_out_date.format("%Y-%m-%d").to_string();
+        let stay = parse_stay(args.check_in.as_deref(), args.check_out.as_deref(), today())
+            .map_err(|e| HotelSearchError::invalid_stay(e, args.check_out.as_deref()))?;
+        validate(&args)?;
+        let check_in = stay.check_in.format("%Y-%m-%d").to_string();
+        let check_out = stay.check_out.format("%Y-%m-%d").to_string();
         let adults = args.adults.unwrap_or_else(|| 1);
         let children_ages = args.children_ages.unwrap_or_else(|| Vec::new());
 
@@ -79,131 +141,243 @@ impl Tool for HotelSearchTool {
         query_params.insert("checkIn", check_in);
         query_params.insert("checkOut", check_out);
         query_params.insert("adults", adults.to_string());
-        query_params.insert(
-            "childrenAges",
-            children_ages
-                .iter()
-                .map(|age| age.to_string())
-                .collect::<Vec<_>>()
-                .join(","),
-        );
+        query_params.insert("childrenAges", join(&children_ages));
         query_params.insert("rooms", args.rooms.map_or(String::new(), |v| v.to_string()));
-        println!(
-            "Sending query params to Tripadvisor:\n{}",
-            serde_json::to_string_pretty(&query_params)
-                .unwrap_or_else(|_| format!("{:?}", query_params))
-        );
-        // Make the API Request
-        let client = reqwest::Client::new();
-        let response = client
-            .get("https://tripadvisor16.p.rapidapi.com/api/v1/hotels/searchHotels")
-            .headers({
-                let mut headers = reqwest::header::HeaderMap::new();
-                headers.insert(
-                    "X-RapidAPI-Host",
-                    "tripadvisor16.p.rapidapi.com".parse().unwrap(),
-                );
-                headers.insert("X-RapidAPI-Key", api_key.parse().unwrap());
-                headers
-            })
-            .query(&query_params)
-            .send()
-            .await
-            .map_err(|e| HotelSearchError::HttpRequestFailed(e.to_string()))?;
-        let status = response.status();
-        // Parse and Format the Response
-        let raw_text = response
-            .text()
-            .await
-            .map_err(|e| HotelSearchError::HttpRequestFailed(e.to_string()))?;
-
-        // Check if the HTTP status code indicates success (2xx range)
-        if !status.is_success() {
-            // It's an HTTP error (e.g., 400, 500). Try to parse the error body.
-            let api_error: ApiError = serde_json::from_str(&raw_text).map_err(|e| {
-                // If the error response itself can't be parsed, log original text
-                eprintln!(
-                    "Failed to parse API error response (HTTP status {}): {}. Raw Text: {}",
-                    status, e, raw_text
-                );
-                HotelSearchError::HttpRequestFailed(format!(
-                    "API returned HTTP error ({}). Could not parse error details. Raw: {}",
-                    status, raw_text
-                ))
-            })?;
-
-            // Return a specific error message based on the API's error response
-            return Err(HotelSearchError::HttpRequestFailed(format!(
-                "Tripadvisor API Error (HTTP Status: {}): {}",
-                status, api_error.message
-            )));
+
+        if let Some(min) = args.price_min {
+            query_params.insert("priceMin", min.to_string());
+        }
+        if let Some(max) = args.price_max {
+            query_params.insert("priceMax", max.to_string());
+        }
+        if let Some(rating) = args.min_rating {
+            query_params.insert("rating", rating.to_string());
+        }
+        if let Some(classes) = args.hotel_class.filter(|classes| !classes.is_empty()) {
+            query_params.insert("class", join(&classes));
+        }
+        if let Some(amenities) = args.amenities.filter(|amenities| !amenities.is_empty()) {
+            query_params.insert("amenity", amenities.join(","));
         }
+        if let Some(sort) = args.sort {
+            query_params.insert("sort", sort.api_value().to_string());
+        }
+        let currency = args.currency.map_or("USD".to_string(), |c| c.trim().to_uppercase());
+        query_params.insert("currencyCode", currency.clone());
+        query_params.insert("pageNumber", args.page.unwrap_or(1).to_string());
+        tracing::debug!(?query_params, "searching hotels");
+        let response_data: HotelSearchData = self
+            .client
+            .get("/api/v1/hotels/searchHotels", &query_params)
+            .await?;
 
-        let response_data: ApiResponse<HotelSearchData> =
-            serde_json::from_str(&raw_text).map_err(|e| {
-                // Log the JSON parsing error and the text that caused it
-                eprintln!(
-                    "Failed to parse JSON response: {} \nRaw Text: {}",
-                    e, raw_text
-                );
-                HotelSearchError::HttpRequestFailed(format!("Failed to parse API response: {}", e))
-            })?;
-
-        let mut output = String::new();
-        output.push_str("🏨 Here are some hotel options:\n\n");
-
-        if response_data.data.data.is_empty() {
-            output.push_str("No hotels found matching your criteria.");
-        } else {
-            for (i, option) in response_data.data.data.iter().enumerate() {
-                output.push_str(&format!("{}. {}\n", i + 1, option.title));
-
-                if let Some(rating) = &option.bubble_rating {
-                    output.push_str(&format!(
-                        "   Rating: {}★ ({} reviews)\n",
-                        rating.rating, rating.count
-                    ));
-                }
-
-                if let Some(info) = &option.primary_info {
-                    output.push_str(&format!("   • Features: {}\n", info));
-                }
-                if let Some(info) = &option.secondary_info {
-                    output.push_str(&format!("   • Location: {}\n", info));
-                }
-
-                if let Some(price) = &option.price_for_display {
-                    output.push_str(&format!("   • Price: {}\n", price));
-                } else if let Some(provider) = &option.provider {
-                    output.push_str(&format!(
-                        "   • Price: Not available directly. Check on {}.\n",
-                        provider
-                    ));
-                } else {
-                    output.push_str("   • Price: Not available. Please check provider websites.\n");
-                }
-
-                output.push('\n');
-
-                if i >= 4 {
-                    output.push_str("...and more options available.\n");
-                    break;
-                }
+        let nights = stay.nights();
+        let rooms = args.rooms.unwrap_or(1).max(1);
+        let mut hotels: Vec<HotelResult> = response_data
+            .data
+            .into_iter()
+            .map(|option| {
+                let mut hotel = HotelResult::from(option);
+                hotel.cost_stay(nights, rooms, &currency, self.rates.as_ref());
+                hotel
+            })
+            .collect();
+        if let Some(near) = args.near.as_deref() {
+            // the hotels found mark out the search area the landmark has to be in
+            let area = centre(hotels.iter().filter_map(|hotel| hotel.coordinates.as_ref()));
+            let landmark = geocode(&self.client, near, args.query.as_deref(), area.as_ref())
+                .await?
+                .ok_or_else(|| {
+                    HotelSearchError::invalid_argument("near", format!("no coordinates found for {} near the hotels searched", near))
+                })?;
+            self.measure_distances(&mut hotels, &landmark, &stay).await;
+            if let Some(radius) = args.radius_km {
+                // hotels that couldn't be measured are kept, marked as such,
+                // rather than silently dropped
+                hotels.retain(|hotel| hotel.distance_km.is_none_or(|distance| distance <= radius));
+            }
+            if args.radius_km.is_some() || args.sort == Some(HotelSort::Distance) {
+                // hotels without coordinates last
+                hotels.sort_by(|a, b| {
+                    let a = a.distance_km.unwrap_or(f64::INFINITY);
+                    let b = b.distance_km.unwrap_or(f64::INFINITY);
+                    a.total_cmp(&b)
+                });
             }
         }
+        self.shortlist.record_found(&hotels);
+        Ok(hotels)
+    }
+}
 
-        Ok(output) // Return the formatted string
+// rejects arguments outside the ranges the tool definition gives, before any
+// request is made
+fn validate(args: &HotelSearchArgs) -> Result<(), HotelSearchError> {
+    if let Some(rooms) = args.rooms
+        && !(1..=MAX_ROOMS).contains(&rooms)
+    {
+        return Err(HotelSearchError::invalid_argument(
+            "rooms",
+            format!("must be between 1 and {}, got {}", MAX_ROOMS, rooms),
+        ));
+    }
+    if let Some(radius) = args.radius_km
+        && (radius <= 0.0 || args.near.is_none())
+    {
+        return Err(HotelSearchError::invalid_argument(
+            "radiusKm",
+            format!("{} must be positive and needs near, the place to measure from", radius),
+        ));
+    }
+    if let (Some(min), Some(max)) = (args.price_min, args.price_max)
+        && min > max
+    {
+        return Err(HotelSearchError::invalid_argument(
+            "priceMin",
+            format!("{} is greater than priceMax ({})", min, max),
+        ));
+    }
+    if let Some(rating) = args.min_rating
+        && !(1..=5).contains(&rating)
+    {
+        return Err(HotelSearchError::invalid_argument(
+            "minRating",
+            format!("must be between 1 and 5, got {}", rating),
+        ));
     }
+    if let Some(page) = args.page
+        && !(1..=MAX_PAGE).contains(&page)
+    {
+        return Err(HotelSearchError::invalid_argument(
+            "page",
+            format!("must be between 1 and {}, got {}", MAX_PAGE, page),
+        ));
+    }
+    Ok(())
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum HotelSearchError {
-    #[error("HTTP request failed: {0}")]
-    HttpRequestFailed(String),
-    #[error("Invalid response structure: {0}")]
-    InvalidResponse(String),
-    #[error("API error: {0}")]
-    ApiError(String),
-    #[error("Missing API key")]
-    MissingApiKey,
+impl HotelSearchTool {
+    // Search results don't always have coordinates; those are looked up in the
+    // hotel details, for a limited number of hotels as each costs a request.
+    async fn measure_distances(&self, hotels: &mut [HotelResult], landmark: &Coordinates, stay: &StayDates) {
+        let mut lookups = 0;
+        for hotel in hotels.iter_mut() {
+            if hotel.coordinates.is_none() && lookups < MAX_COORDINATE_LOOKUPS {
+                lookups += 1;
+                hotel.coordinates = match fetch_details(&self.client, &hotel.id, stay).await {
+                    Ok(details) => details.geo_point,
+                    Err(e) => {
+                        tracing::debug!(hotel_id = %hotel.id, error = %e, "no coordinates for hotel");
+                        None
+                    }
+                };
+            }
+            hotel.distance_km = hotel
+                .coordinates
+                .as_ref()
+                .map(|coordinates| (haversine_km(landmark, coordinates) * 100.0).round() / 100.0);
+            hotel.distance_unknown = hotel.distance_km.is_none();
+        }
+    }
+}
+
+// comma-separated list, as the API expects for multi-valued parameters
+fn join(values: &[u32]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(extra: serde_json::Value) -> HotelSearchArgs {
+        let mut args = json!({ "query": "Paris", "geoId": 187147, "checkIn": "2030-06-01", "adults": 2 });
+        args.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(args).unwrap()
+    }
+
+    // what the model is told: the error message and the field it should correct
+    fn rejection(extra: serde_json::Value) -> (String, String) {
+        let error = validate(&args(extra)).unwrap_err();
+        (error.to_string(), error.next_step())
+    }
+
+    #[test]
+    fn arguments_within_range_are_accepted() {
+        let args = args(json!({
+            "rooms": MAX_ROOMS,
+            "minRating": 4,
+            "priceMin": 100,
+            "priceMax": 100,
+            "near": "Eiffel Tower",
+            "radiusKm": 1.5,
+            "page": MAX_PAGE,
+        }));
+
+        assert!(validate(&args).is_ok());
+    }
+
+    #[test]
+    fn rooms_must_be_in_range() {
+        let (message, next_step) = rejection(json!({ "rooms": 0 }));
+        assert_eq!(message, "Invalid rooms: must be between 1 and 10, got 0");
+        assert!(next_step.starts_with("Correct rooms"));
+
+        let (message, _) = rejection(json!({ "rooms": MAX_ROOMS + 1 }));
+        assert_eq!(message, "Invalid rooms: must be between 1 and 10, got 11");
+    }
+
+    #[test]
+    fn min_rating_must_be_between_one_and_five() {
+        let (message, next_step) = rejection(json!({ "minRating": 6 }));
+
+        assert_eq!(message, "Invalid minRating: must be between 1 and 5, got 6");
+        assert!(next_step.starts_with("Correct minRating"));
+        assert!(validate(&args(json!({ "minRating": 0 }))).is_err());
+    }
+
+    #[test]
+    fn price_min_cannot_exceed_price_max() {
+        let (message, next_step) = rejection(json!({ "priceMin": 200, "priceMax": 100 }));
+
+        assert_eq!(message, "Invalid priceMin: 200 is greater than priceMax (100)");
+        assert!(next_step.starts_with("Correct priceMin"));
+        // either bound alone is fine
+        assert!(validate(&args(json!({ "priceMin": 200 }))).is_ok());
+        assert!(validate(&args(json!({ "priceMax": 100 }))).is_ok());
+    }
+
+    #[test]
+    fn radius_must_be_positive() {
+        for radius in [0.0, -2.0] {
+            let (message, next_step) = rejection(json!({ "near": "Eiffel Tower", "radiusKm": radius }));
+
+            assert_eq!(
+                message,
+                format!("Invalid radiusKm: {} must be positive and needs near, the place to measure from", radius)
+            );
+            assert!(next_step.starts_with("Correct radiusKm"));
+        }
+    }
+
+    #[test]
+    fn radius_needs_a_place_to_measure_from() {
+        let (message, _) = rejection(json!({ "radiusKm": 2.0 }));
+
+        assert_eq!(message, "Invalid radiusKm: 2 must be positive and needs near, the place to measure from");
+    }
+
+    #[test]
+    fn page_must_be_in_range() {
+        let (message, next_step) = rejection(json!({ "page": MAX_PAGE + 1 }));
+        assert_eq!(message, "Invalid page: must be between 1 and 10, got 11");
+        assert!(next_step.starts_with("Correct page"));
+
+        let (message, _) = rejection(json!({ "page": 0 }));
+        assert_eq!(message, "Invalid page: must be between 1 and 10, got 0");
+    }
 }
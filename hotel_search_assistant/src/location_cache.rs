@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use crate::model::LocationSearchResult;
+
+const CACHE_FILE: &str = "location_cache.json";
+/// How long a cached location is used before the API is asked again.
+pub const DEFAULT_TTL_DAYS: i64 = 30;
+/// Longest TTL accepted; a hundred years is as good as forever.
+pub const MAX_TTL_DAYS: i64 = 36_500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub stored_at: DateTime<Utc>,
+    pub result: LocationSearchResult,
+}
+
+/// Location lookups kept across sessions in a JSON file, keyed by the
+/// normalised query, so "London" doesn't cost an API call every time.
+pub struct LocationCache {
+    path: PathBuf,
+    ttl: Duration,
+    entries: Mutex<BTreeMap<String, CacheEntry>>,
+}
+
+impl LocationCache {
+    /// Loads the cache from `dir`. A missing file is an empty cache; an unreadable
+    /// one is reported and replaced on the next write, as it only saves API calls.
+    pub fn open(dir: &Path, ttl: Duration) -> Self {
+        let path = dir.join(CACHE_FILE);
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                tracing::warn!(path = %path.display(), error = %e, "ignoring unreadable location cache");
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Self {
+            path,
+            ttl,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The cached result for the query, unless it has expired.
+    pub fn get(&self, query: &str) -> Option<LocationSearchResult> {
+        let entries = self.entries();
+        let entry = entries.get(&normalise(query))?;
+        (!self.is_expired(entry)).then(|| entry.result.clone())
+    }
+
+    pub fn insert(&self, query: &str, result: LocationSearchResult) -> io::Result<()> {
+        let mut entries = self.entries();
+        entries.insert(normalise(query), CacheEntry { stored_at: Utc::now(), result });
+        self.save(&entries)
+    }
+
+    /// All cached entries, expired ones included, with whether they have expired.
+    pub fn list(&self) -> Vec<(String, CacheEntry, bool)> {
+        self.entries()
+            .iter()
+            .map(|(query, entry)| (query.clone(), entry.clone(), self.is_expired(entry)))
+            .collect()
+    }
+
+    /// Drops the entry for one query; returns whether there was one.
+    pub fn remove(&self, query: &str) -> io::Result<bool> {
+        let mut entries = self.entries();
+        let removed = entries.remove(&normalise(query)).is_some();
+        if removed {
+            self.save(&entries)?;
+        }
+        Ok(removed)
+    }
+
+    /// Drops every entry; returns how many there were.
+    pub fn clear(&self) -> io::Result<usize> {
+        let mut entries = self.entries();
+        let count = entries.len();
+        entries.clear();
+        self.save(&entries)?;
+        Ok(count)
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        Utc::now() - entry.stored_at > self.ttl
+    }
+
+    fn save(&self, entries: &BTreeMap<String, CacheEntry>) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(entries)?)
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, CacheEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// "  New York ,  USA" and "new york, usa" are the same lookup.
+pub fn normalise(query: &str) -> String {
+    query
+        .split(',')
+        .map(|part| part.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(geo_id: u64, name: &str) -> LocationSearchResult {
+        LocationSearchResult {
+            geo_id: Some(geo_id),
+            location_name: name.to_string(),
+            candidates: Vec::new(),
+            needs_confirmation: false,
+        }
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("location-cache-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn normalises_queries() {
+        assert_eq!(normalise("  New York ,  USA"), "new york, usa");
+        assert_eq!(normalise("new york, usa"), "new york, usa");
+        assert_eq!(normalise("Paris,"), "paris");
+        assert_eq!(normalise(" , "), "");
+        assert_eq!(normalise("SÃO  Paulo"), "são paulo");
+    }
+
+    #[test]
+    fn entries_survive_a_reopen() {
+        let dir = temp_dir();
+        let cache = LocationCache::open(&dir, Duration::days(DEFAULT_TTL_DAYS));
+        assert!(cache.get("Paris").is_none());
+        cache.insert("Paris, France", result(187147, "Paris")).unwrap();
+
+        let reopened = LocationCache::open(&dir, Duration::days(DEFAULT_TTL_DAYS));
+        assert_eq!(reopened.get(" paris ,france").unwrap().geo_id, Some(187147));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn expired_entries_are_listed_but_not_returned() {
+        let dir = temp_dir();
+        let entries = BTreeMap::from([
+            ("paris".to_string(), CacheEntry { stored_at: Utc::now() - Duration::days(31), result: result(187147, "Paris") }),
+            ("rome".to_string(), CacheEntry { stored_at: Utc::now() - Duration::days(29), result: result(187791, "Rome") }),
+        ]);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(CACHE_FILE), serde_json::to_string(&entries).unwrap()).unwrap();
+
+        let cache = LocationCache::open(&dir, Duration::days(30));
+        assert!(cache.get("Paris").is_none());
+        assert_eq!(cache.get("Rome").unwrap().geo_id, Some(187791));
+        let listed: Vec<(String, bool)> = cache.list().into_iter().map(|(query, _, expired)| (query, expired)).collect();
+        assert_eq!(listed, [("paris".to_string(), true), ("rome".to_string(), false)]);
+
+        // a longer TTL makes the same entry usable again
+        let cache = LocationCache::open(&dir, Duration::days(60));
+        assert!(cache.get("Paris").is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn remove_and_clear() {
+        let dir = temp_dir();
+        let cache = LocationCache::open(&dir, Duration::days(DEFAULT_TTL_DAYS));
+        cache.insert("Paris", result(187147, "Paris")).unwrap();
+        cache.insert("Rome", result(187791, "Rome")).unwrap();
+        cache.insert("Tokyo", result(298184, "Tokyo")).unwrap();
+
+        assert!(cache.remove(" PARIS ").unwrap());
+        assert!(!cache.remove("Paris").unwrap());
+        assert!(cache.get("Paris").is_none());
+        assert_eq!(LocationCache::open(&dir, Duration::days(DEFAULT_TTL_DAYS)).list().len(), 2);
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert!(cache.list().is_empty());
+        assert!(LocationCache::open(&dir, Duration::days(DEFAULT_TTL_DAYS)).list().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_corrupt_file_starts_an_empty_cache_and_is_replaced() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(CACHE_FILE), "{ not json").unwrap();
+
+        let cache = LocationCache::open(&dir, Duration::days(DEFAULT_TTL_DAYS));
+        assert!(cache.list().is_empty());
+        cache.insert("Paris", result(187147, "Paris")).unwrap();
+
+        let reopened = LocationCache::open(&dir, Duration::days(DEFAULT_TTL_DAYS));
+        assert_eq!(reopened.get("Paris").unwrap().geo_id, Some(187147));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
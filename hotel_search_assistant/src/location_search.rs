@@ -1,43 +1,204 @@
-use std::collections::HashMap;
-use crate::hotel_search_tool::HotelSearchError;
-use crate::model::{LocationSearchArgs, ApiResponse, LocationOption};
+use std::collections::{HashMap, HashSet};
+use crate::geo::haversine_km;
+use crate::tool_error::HotelSearchError;
+use crate::model::{Coordinates, LocationSearchArgs, LocationOption};
+use crate::utils::strip_tags;
+use tripadvisor_client::TripadvisorClient;
 
-pub async fn get_location_from_api(args: LocationSearchArgs, api_key: String) -> Result<Option<LocationOption>, HotelSearchError> {
+/// How many locations are offered to the user when a query is ambiguous.
+const MAX_CANDIDATES: usize = 5;
+/// Farthest a landmark may be from the hotels searched around it.
+const MAX_LANDMARK_DISTANCE_KM: f64 = 50.0;
+
+/// Returns the best matching locations for the query, best match first.
+pub async fn get_location_from_api(client: &TripadvisorClient, args: LocationSearchArgs) -> Result<Vec<LocationOption>, HotelSearchError> {
 
     let mut query_params = HashMap::new();
     query_params.insert("query", args.query);
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://tripadvisor16.p.rapidapi.com/api/v1/hotels/searchLocation")
-        .headers({
-            let mut headers = reqwest::header::HeaderMap::new();
-            headers.insert(
-                "X-RapidApi-Host",
-                "tripadvisor16.p.rapidapi.com".parse().unwrap(),
-            );
-            headers.insert("X-RapidAPI-Key", api_key.parse().unwrap());
-            headers
+    let location_options: Vec<LocationOption> = client
+        .get("/api/v1/hotels/searchLocation", &query_params)
+        .await?;
+
+    Ok(location_options.into_iter().take(MAX_CANDIDATES).collect())
+}
+
+/// Coordinates of a landmark or address, e.g. "Eiffel Tower", looked up in
+/// `area` (the city searched) when given. Matches more than
+/// `MAX_LANDMARK_DISTANCE_KM` from `centre` are skipped, so a Paris search
+/// isn't measured from the Eiffel Tower in Las Vegas.
+pub async fn geocode(
+    client: &TripadvisorClient,
+    query: &str,
+    area: Option<&str>,
+    centre: Option<&Coordinates>,
+) -> Result<Option<Coordinates>, HotelSearchError> {
+    let area = area.map(str::trim).filter(|area| !area.is_empty());
+    if let Some(area) = area
+        && !query.to_lowercase().contains(&area.to_lowercase())
+    {
+        let scoped = search_landmark(client, &format!("{}, {}", query, area), centre).await?;
+        if scoped.is_some() {
+            return Ok(scoped);
+        }
+    }
+    search_landmark(client, query, centre).await
+}
+
+async fn search_landmark(
+    client: &TripadvisorClient,
+    query: &str,
+    centre: Option<&Coordinates>,
+) -> Result<Option<Coordinates>, HotelSearchError> {
+    let mut query_params = HashMap::new();
+    query_params.insert("query", query.to_string());
+
+    let location_options: Vec<LocationOption> = client
+        .get("/api/v1/hotels/searchLocation", &query_params)
+        .await?;
+
+    Ok(nearby_landmark(location_options, centre))
+}
+
+// the first match with coordinates that isn't too far from `centre`
+fn nearby_landmark(options: Vec<LocationOption>, centre: Option<&Coordinates>) -> Option<Coordinates> {
+    options
+        .into_iter()
+        .filter_map(|option| option.geo_point)
+        .find(|point| centre.is_none_or(|centre| haversine_km(centre, point) <= MAX_LANDMARK_DISTANCE_KM))
+}
+
+/// Picks the location the query most likely means, or `None` when the user
+/// should be asked to choose.
+///
+/// Candidates named like the query are compared by region and country (their
+/// secondary text). If they differ, as for Paris, France and Paris, Texas, the
+/// query is ambiguous. A query that already names the region or country
+/// ("Paris, France") only considers candidates in it.
+pub fn resolve_location<'a>(query: &str, candidates: &'a [LocationOption]) -> Option<&'a LocationOption> {
+    let mut parts = query.split(',').map(|part| part.trim().to_lowercase());
+    let place = parts.next().unwrap_or_default();
+    let qualifiers: Vec<String> = parts.filter(|part| !part.is_empty()).collect();
+
+    let matching: Vec<&LocationOption> = candidates
+        .iter()
+        .filter(|candidate| strip_tags(&candidate.title).trim().to_lowercase() == place)
+        .filter(|candidate| {
+            let region = candidate.secondary_text.to_lowercase();
+            qualifiers.iter().all(|qualifier| region.contains(qualifier))
         })
-        .query(&query_params)
-        .send()
-        .await
-        .map_err(|e| HotelSearchError::HttpRequestFailed(e.to_string()))?;
+        .collect();
+    let regions: HashSet<String> = matching
+        .iter()
+        .map(|candidate| candidate.secondary_text.to_lowercase())
+        .collect();
+
+    match matching.first() {
+        Some(best) if regions.len() == 1 => Some(best),
+        Some(_) => None,
+        // nothing named exactly like the query, e.g. "NYC": trust the API's ranking,
+        // unless the region or country the user gave doesn't match any candidate
+        None if qualifiers.is_empty() || candidates.len() == 1 => candidates.first(),
+        None => None,
+    }
+}
 
-    // Parse and Format the Response
-    let text = response
-        .text()
-        .await
-        .map_err(|e| HotelSearchError::HttpRequestFailed(e.to_string()))?;
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
 
-    let api_response: ApiResponse<Vec<LocationOption>> = serde_json::from_str(&text)
-        .map_err(|e| HotelSearchError::HttpRequestFailed(e.to_string()))?;
+    fn landmark(title: &str, latitude: f64, longitude: f64) -> LocationOption {
+        serde_json::from_value(json!({
+            "title": title,
+            "geoId": 1,
+            "documentId": "",
+            "trackingItems": "",
+            "geoPoint": { "latitude": latitude, "longitude": longitude },
+        }))
+        .unwrap()
+    }
 
-    if !api_response.status {
-        return Err(HotelSearchError::ApiError(api_response.message));
+    fn candidate(title: &str, geo_id: u64, secondary_text: &str) -> LocationOption {
+        serde_json::from_value(json!({
+            "title": title,
+            "geoId": geo_id,
+            "documentId": "",
+            "trackingItems": "",
+            "secondaryText": secondary_text,
+        }))
+        .unwrap()
     }
 
-    let location_options = api_response.data;
+    fn paris_candidates() -> Vec<LocationOption> {
+        vec![
+            candidate("<b>Paris</b>", 187147, "Ile-de-France, France"),
+            candidate("<b>Paris</b>", 55871, "Texas, United States"),
+            candidate("Disneyland Paris", 1182377, "Ile-de-France, France"),
+        ]
+    }
 
-    Ok(location_options.into_iter().next())
-}
\ No newline at end of file
+    #[test]
+    fn places_with_the_same_name_in_different_regions_are_ambiguous() {
+        assert!(resolve_location("Paris", &paris_candidates()).is_none());
+        assert!(resolve_location(" paris ", &paris_candidates()).is_none());
+    }
+
+    #[test]
+    fn a_region_or_country_in_the_query_picks_the_candidate() {
+        let candidates = paris_candidates();
+
+        assert_eq!(resolve_location("Paris, France", &candidates).unwrap().geo_id, 187147);
+        assert_eq!(resolve_location("Paris, Texas", &candidates).unwrap().geo_id, 55871);
+        assert_eq!(resolve_location("paris, texas, united states", &candidates).unwrap().geo_id, 55871);
+    }
+
+    #[test]
+    fn a_single_match_by_name_is_picked_over_the_api_ranking() {
+        let candidates = vec![
+            candidate("Lisbon District", 189100, "Portugal"),
+            candidate("<b>Lisbon</b>", 189158, "Lisbon District, Portugal"),
+        ];
+
+        assert_eq!(resolve_location("Lisbon", &candidates).unwrap().geo_id, 189158);
+    }
+
+    #[test]
+    fn without_a_match_by_name_the_first_candidate_is_used() {
+        let candidates = vec![
+            candidate("New York City", 60763, "New York, United States"),
+            candidate("New York", 28953, "United States"),
+        ];
+
+        assert_eq!(resolve_location("NYC", &candidates).unwrap().geo_id, 60763);
+        assert!(resolve_location("NYC", &[]).is_none());
+    }
+
+    #[test]
+    fn an_unmatched_qualifier_with_several_candidates_is_ambiguous() {
+        // Paris isn't in Germany, so neither candidate can be assumed
+        assert!(resolve_location("Paris, Germany", &paris_candidates()).is_none());
+
+        let single = vec![candidate("New York City", 60763, "New York, United States")];
+        assert_eq!(resolve_location("NYC, USA", &single).unwrap().geo_id, 60763);
+    }
+
+    #[test]
+    fn landmarks_far_from_the_search_area_are_skipped() {
+        let paris = Coordinates { latitude: 48.8566, longitude: 2.3522 };
+        let options = || {
+            vec![
+                landmark("Eiffel Tower Viewing Deck", 36.1125, -115.1721),
+                landmark("Eiffel Tower", 48.8584, 2.2945),
+            ]
+        };
+
+        let point = nearby_landmark(options(), Some(&paris)).unwrap();
+        assert_eq!((point.latitude, point.longitude), (48.8584, 2.2945));
+        // without a search area the API's best match is used
+        assert_eq!(nearby_landmark(options(), None).unwrap().latitude, 36.1125);
+        // nothing near the search area at all
+        let far_only = vec![landmark("Eiffel Tower Viewing Deck", 36.1125, -115.1721)];
+        assert!(nearby_landmark(far_only, Some(&paris)).is_none());
+    }
+}
@@ -1,12 +1,22 @@
 use rig::completion::ToolDefinition;
 use rig::tool::Tool;
 use serde_json::json;
-use crate::hotel_search_tool::HotelSearchError;
-use crate::location_search::get_location_from_api;
-use crate::model::{LocationSearchArgs, LocationOption, LocationSearchResult};
-use std::env;
+use crate::tool_error::HotelSearchError;
+use crate::location_cache::{normalise, LocationCache};
+use crate::location_search::{get_location_from_api, resolve_location};
+use crate::model::{LocationCandidate, LocationSearchArgs, LocationSearchResult};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tripadvisor_client::TripadvisorClient;
 
-pub struct LocationSearchTool;
+pub struct LocationSearchTool {
+    client: TripadvisorClient,
+    // lookups from earlier sessions
+    cache: Arc<LocationCache>,
+    // locations the user picked when asked, keyed by the normalised query,
+    // so the same question isn't asked twice in a session
+    confirmed: Mutex<HashMap<String, LocationCandidate>>,
+}
 
 impl Tool for LocationSearchTool {
     const NAME: &'static str = "search_location";
@@ -17,7 +27,10 @@ impl Tool for LocationSearchTool {
     async fn definition(&self, _prompt: String) -> ToolDefinition {
         ToolDefinition {
             name: Self::NAME.to_string(),
-            description: "Finds the geographic ID for a given city or place name. Required before searching for hotels.".to_string(),
+            description: "Finds the geographic ID for a given city or place name. Required before searching for hotels. \
+                If the name could mean several places, no geoId is returned and needs_confirmation is true: \
+                ask the user which of the candidates they mean, then call this tool again with the same query \
+                and the chosen candidate's geoId as confirmedGeoId.".to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {
@@ -25,6 +38,10 @@ impl Tool for LocationSearchTool {
                         "type": "string",
                         "description": "The name of the city, region, or place to find the geographic ID for (e.g., 'London', 'Paris, France', 'New York City')."
                     },
+                    "confirmedGeoId": {
+                        "type": "integer",
+                        "description": "The geoId of the candidate the user picked, after the tool asked for confirmation."
+                    },
                 },
                 "required": ["query"]
             }),
@@ -32,18 +49,87 @@ impl Tool for LocationSearchTool {
     }
 
     async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
-        println!("LocationSearchTool: Calling get_location_from_api for query: {}", args.query);
-        let api_key = env::var("RAPIDAPI_KEY").map_err(|_| HotelSearchError::MissingApiKey)?;
+        let key = normalise(&args.query);
+        let remembered = self.confirmed().get(&key).cloned();
+        if let Some(location) = remembered {
+            tracing::debug!(geo_id = location.geo_id, query = %args.query, "using the confirmed location");
+            return Ok(resolved(args.query, location));
+        }
 
-        let location_opt = get_location_from_api(args.clone(), api_key).await?;
+        let mut result = match self.cache.get(&args.query) {
+            Some(cached) => {
+                tracing::debug!(query = %args.query, "using the cached location");
+                cached
+            }
+            None => {
+                tracing::debug!(query = %args.query, "looking up the location");
+                let result = self.lookup(&args).await?;
+                if let Err(e) = self.cache.insert(&args.query, result.clone()) {
+                    tracing::warn!(path = %self.cache.path().display(), error = %e, "could not write the location cache");
+                }
+                result
+            }
+        };
+        result.location_name = args.query.clone(); // Return the original query so LLM knows what geoId maps to
+
+        if let Some(geo_id) = args.confirmed_geo_id {
+            let location = result
+                .candidates
+                .into_iter()
+                .find(|candidate| candidate.geo_id == geo_id)
+                .ok_or_else(|| HotelSearchError::invalid_argument(
+                    "confirmedGeoId",
+                    format!("{} is not one of the candidates for {}", geo_id, args.query),
+                ))?;
+            tracing::debug!(geo_id, query = %args.query, "user confirmed the location");
+            self.confirmed().insert(key, location.clone());
+            return Ok(resolved(args.query, location));
+        }
+
+        match result.geo_id {
+            Some(geo_id) => tracing::debug!(geo_id, query = %args.query, "found the location"),
+            None => tracing::debug!(query = %args.query, "location is ambiguous, asking the user"),
+        }
+        Ok(result)
+    }
+}
 
-        let location = location_opt.ok_or(HotelSearchError::InvalidResponse("Location not found from API for input query".to_string()))?; // Or a more specific error
+impl LocationSearchTool {
+    pub fn new(client: TripadvisorClient, cache: Arc<LocationCache>) -> Self {
+        Self {
+            client,
+            cache,
+            confirmed: Mutex::default(),
+        }
+    }
 
-        println!("LocationSearchTool: Found geoId: {} for {}", location.geo_id, args.query);
+    async fn lookup(&self, args: &LocationSearchArgs) -> Result<LocationSearchResult, HotelSearchError> {
+        let options = get_location_from_api(&self.client, args.clone()).await?;
+        if options.is_empty() {
+            return Err(HotelSearchError::LocationNotFound { query: args.query.clone() });
+        }
 
-        Ok(LocationSearchResult {
-            geo_id: location.geo_id,
-            location_name: args.query, // Return the original query so LLM knows what geoId maps to
+        Ok(match resolve_location(&args.query, &options) {
+            Some(location) => resolved(args.query.clone(), location.into()),
+            None => LocationSearchResult {
+                geo_id: None,
+                location_name: args.query.clone(),
+                candidates: options.iter().map(LocationCandidate::from).collect(),
+                needs_confirmation: true,
+            },
         })
     }
-}
\ No newline at end of file
+
+    fn confirmed(&self) -> std::sync::MutexGuard<'_, HashMap<String, LocationCandidate>> {
+        self.confirmed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn resolved(query: String, location: LocationCandidate) -> LocationSearchResult {
+    LocationSearchResult {
+        geo_id: Some(location.geo_id),
+        location_name: query, // Return the original query so LLM knows what geoId maps to
+        candidates: vec![location],
+        needs_confirmation: false,
+    }
+}
@@ -1,50 +1,124 @@
+mod agent;
+mod attraction_search_tool;
+mod audit;
+mod conversation;
+mod currency;
+mod dates;
+mod display;
+mod geo;
+mod hotel_details_tool;
 mod hotel_search_tool;
+mod location_cache;
 mod location_search;
 mod location_search_tool;
 mod model;
+mod restaurant_search_tool;
+mod server;
+mod sessions;
+mod shortlist;
+mod shortlist_tools;
+mod tool_error;
 mod utils;
 
 use std::io;
 use std::io::Write;
-use crate::hotel_search_tool::HotelSearchTool;
-use crate::location_search_tool::LocationSearchTool;
+use std::path::PathBuf;
+use std::sync::Arc;
+use crate::agent::AgentResources;
+use crate::audit::AuditLog;
+use crate::conversation::{Conversation, MAX_CONVERSATION_TURNS};
+use crate::currency::RateTable;
+use crate::location_cache::LocationCache;
+use crate::model::HotelResult;
+use crate::sessions::{SavedSession, SessionStore};
+use crate::shortlist::{comparison_table, Shortlist};
 use dotenv::dotenv;
-use rig::client::{CompletionClient, ProviderClient};
-use rig::completion::{Message, Prompt};
+use rig::client::ProviderClient;
 use rig::providers::openai::Client;
-use chrono::Datelike;
+use tripadvisor_client::{Mode, QuotaUsage, TripadvisorClient};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
 
-    let openai_client = Client::from_env();
-    let current_year = chrono::Utc::now().year();
-
-    let preamble = format!("You are a helpful assistant that finds hotels. \
-        When providing hotel details, for each hotel, include its name, overall rating (with number of reviews), \
-        price, key features, and its specific location or neighborhood within the city. \
-        Do not invent information. If a direct booking link is not available, \
-        mention the booking provider name if possible. You cannot provide specific positive or \
-        negative comments from reviews, only the overall rating. \
-         Keep responses concise unless more details are requested. \
-         The current year is {}. If the user provides a date without a year, assume it's for {} if the date is in the future, \
-         or {} if the date has already passed in {}. Provide dates in YYYY-MM-DD format.\
-        If user didn't specify number of rooms, you should default to 1.
-        ", current_year, current_year, current_year + 1, current_year);
-    // The agent instance itself doesn't inherently store ongoing chat history across prompt calls.
-    // We'll manage the history explicitly.
-    let agent = openai_client
-        .agent("gpt-4-turbo") // Recommended for better tool use in multi-turn
-        .preamble(&preamble)
-        .tool(LocationSearchTool)
-        .tool(HotelSearchTool)
-        .build();
-
-    let mut chat_history: Vec<Message> = Vec::new();
-    const MAX_CONVERSATION_TURNS: usize = 10; // Limit total conversation turns
-
-    println!("Welcome to the Hotel Finder. Type 'exit' to quit.");
+    let location_cache = LocationCache::open(&data_dir(), location_cache_ttl());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("cache") {
+        return run_cache_command(&location_cache, &args[1..]);
+    }
+
+    let session_store = SessionStore::new(data_dir().join("sessions"));
+    if args.first().map(String::as_str) == Some("--list-sessions") {
+        return list_sessions(&session_store);
+    }
+    // `--export <id> [file]` writes the session as a Markdown transcript
+    if args.first().map(String::as_str) == Some("--export") {
+        let id = args.get(1).ok_or("Usage: hotel_search_assistant --export <session id> [file]")?;
+        let transcript = session_store.load(id)?.to_markdown();
+        match args.get(2) {
+            Some(file) => std::fs::write(file, transcript)?,
+            None => print!("{}", transcript),
+        }
+        return Ok(());
+    }
+    let mut session = match args.iter().position(|arg| arg == "--resume") {
+        Some(i) => {
+            let id = args.get(i + 1).ok_or("Usage: hotel_search_assistant --resume <session id>")?;
+            session_store.load(id)?
+        }
+        None => SavedSession::start(),
+    };
+
+    // tool calls are appended to the audit log, in CLI and server mode
+    let audit_path = data_dir().join("tool_calls.jsonl");
+    if let Err(e) = audit::init(&audit_path) {
+        println!("Tool calls are not logged, {} could not be opened: {}", audit_path.display(), e);
+    }
+
+    let resources = AgentResources {
+        openai_client: Client::from_env(),
+        tripadvisor: TripadvisorClient::from_env()?,
+        location_cache: Arc::new(location_cache),
+        rates: Arc::new(load_exchange_rates()),
+    };
+    let tripadvisor = resources.tripadvisor.clone();
+    match tripadvisor.mode() {
+        Mode::Record(dir) => println!("Recording Tripadvisor responses to {}", dir.display()),
+        Mode::Replay(dir) => println!("Replaying Tripadvisor responses from {}", dir.display()),
+        Mode::Live => {}
+    }
+
+    // `hotel_search_assistant serve [address]` runs the web front-end instead of the CLI
+    if args.first().map(String::as_str) == Some("serve") {
+        let address = args
+            .get(1)
+            .cloned()
+            .or_else(|| std::env::var("HOTEL_ASSISTANT_ADDRESS").ok())
+            .unwrap_or_else(|| "127.0.0.1:3000".to_string());
+        return server::serve(resources, &address).await;
+    }
+
+    let shortlist = Shortlist::restore(&session.hotels, session.shortlist.clone());
+    // `--trace` shows the tool calls behind each answer
+    let trace = args.iter().any(|arg| arg == "--trace");
+    let audit = AuditLog::new(&session.id);
+    let audit = if trace { audit.keep_records() } else { audit };
+    let agent = agent::build_agent(&resources, shortlist.clone(), audit.clone());
+
+    // The agent itself is stateless, so the conversation keeps the history,
+    // tool results included, and sends it along with every message.
+    let mut conversation = Conversation::resume(session.history.clone(), MAX_CONVERSATION_TURNS);
+
+    println!("Welcome to the Hotel Finder. Type '/shortlist' to compare your shortlist, \
+        '/geojson [file]' to export the latest results for a map, 'exit' to quit.");
+    if session.history.is_empty() {
+        println!("Session {}", session.id);
+    } else {
+        println!("Resuming session {}: {}", session.id, session.title());
+    }
+
+    // hotels from the latest searches, for the GeoJSON export
+    let mut latest_hotels: Vec<HotelResult> = Vec::new();
 
     loop {
         print!("\nUser: ");
@@ -54,60 +128,157 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let user_input = user_input.trim();
 
         if user_input.eq_ignore_ascii_case("exit") {
+            print_quota(&tripadvisor.quota());
+            if !session.history.is_empty() {
+                println!("Continue this session with --resume {}", session.id);
+            }
             println!("Goodbye!");
             break;
         }
 
-        // Add user's message to history
-        chat_history.push(Message::user(user_input));
+        if user_input.eq_ignore_ascii_case("/shortlist") {
+            print!("{}", comparison_table(&shortlist.entries()));
+            continue;
+        }
+        if let Some(file) = user_input.strip_prefix("/geojson") {
+            let file = Some(file.trim()).filter(|file| !file.is_empty()).unwrap_or("hotels.geojson");
+            let geojson = geo::to_geojson(&latest_hotels);
+            let count = geojson["features"].as_array().map_or(0, Vec::len);
+            match std::fs::write(file, serde_json::to_string_pretty(&geojson)?) {
+                Ok(()) => println!("Wrote {} hotels with coordinates to {}", count, file),
+                Err(e) => println!("Could not write {}: {}", file, e),
+            }
+            continue;
+        }
 
-        let mut agent_response_content = String::new(); // To accumulate agent's full textual response
+        println!("Agent thinking...");
 
-        // Pass the entire history to the agent for context
-        // Note: Rig's `agent.prompt()` takes a single `Message` or `&str`,
-        // it doesn't directly take a `Vec<Message>` for ongoing history.
-        // This implies Rig's Agent is more for single complex tasks.
-        // For true persistent history, you would typically use `openai_client.chat().create()`
-        // directly, managing the messages vector yourself.
+        let result = conversation.send(&agent, user_input).await;
+        if trace {
+            // a failed turn isn't in the history, so its calls are listed without steps
+            let turn = if result.is_ok() { conversation.latest_turn() } else { &[] };
+            print!("{}", audit::call_tree(turn, &audit.take_records()));
+        }
+        match result {
+            Ok(response) => {
+                let results = agent::latest_search_results(&conversation);
+                if !results.is_empty() {
+                    latest_hotels = results.iter().flatten().cloned().collect();
+                }
+                for hotels in results {
+                    println!("{}", display::format_hotels(&hotels));
+                    session.remember_hotels(&hotels);
+                }
+                println!("Agent: {}", response);
 
-        // However, for the purpose of demonstrating how to *try* to loop
-        // with the agent and its internal multi-turn, we'll call prompt
-        // with just the latest user input. The agent's `preamble` and tool descriptions
-        // are its primary context. The `multi_turn` helps it make *internal* iterations.
+                // saved after every turn, so nothing is lost if the CLI is killed;
+                // the session keeps the whole history, the conversation only recent turns
+                session.history.extend_from_slice(conversation.latest_turn());
+                session.shortlist = shortlist.entries();
+                if let Err(e) = session_store.save(&mut session) {
+                    println!("Could not save the session: {}", e);
+                }
+            }
+            // keep the session going; the failed turn isn't kept in the history
+            Err(e) => println!("Agent error: {}", e),
+        }
+    }
 
-        // If you need the LLM to remember *prior user/assistant dialogue*,
-        // you would need to use a lower-level chat API call that accepts a `Vec<Message>`
-        // and manage that `Vec<Message>` explicitly yourself, adding each user and assistant
-        // message as it happens.
+    Ok(())
+}
 
-        // For now, let's assume `agent.prompt()` internally has some limited memory
-        // for `multi_turn` but each call technically starts a new "thread" with preamble.
-        // To truly persist memory across `prompt` calls, you'd likely rebuild the agent with history
-        // or use `openai_client.chat().create` directly.
+/// Where the assistant keeps data between sessions, `HOTEL_ASSISTANT_DATA_DIR`
+/// or `.hotel_assistant` in the working directory.
+fn data_dir() -> PathBuf {
+    std::env::var("HOTEL_ASSISTANT_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(".hotel_assistant"))
+}
 
-        // Let's revert to a simpler example for prompt call,
-        // and acknowledge the limitation for full multi-turn conversational memory *between* calls.
-        // The `multi_turn` config is for *one complex prompt*.
+/// Exchange rates from `EXCHANGE_RATES_FILE`, or `exchange_rates.json` in the
+/// data directory. Without them prices stay in the currency the API returns.
+fn load_exchange_rates() -> RateTable {
+    let path = std::env::var("EXCHANGE_RATES_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| data_dir().join("exchange_rates.json"));
+    match RateTable::load(&path) {
+        Ok(rates) => {
+            println!("Loaded {} exchange rates from {}", rates.rate_count(), path.display());
+            rates
+        }
+        Err(e) => {
+            println!("No exchange rates loaded from {} ({}), prices are not converted.", path.display(), e);
+            RateTable::default()
+        }
+    }
+}
 
-        println!("Agent thinking...");
+fn location_cache_ttl() -> chrono::Duration {
+    let days = std::env::var("LOCATION_CACHE_TTL_DAYS")
+        .ok()
+        .and_then(|days| days.trim().parse::<i64>().ok())
+        .unwrap_or(location_cache::DEFAULT_TTL_DAYS);
+    // clamped, since Duration::days panics on huge values
+    chrono::Duration::days(days.clamp(0, location_cache::MAX_TTL_DAYS))
+}
 
-        // Re-call prompt, but the "memory" across loops is not implicit with agent.prompt()
-        // The LLM only "remembers" the history that is passed to it in the current prompt's context.
-        // A true chatbot would build `Vec<Message>` and pass to `openai_client.chat().create`.
-        let response_from_agent = agent
-            .prompt(user_input) // Only sending the latest user input
-            .multi_turn(5) // This multi_turn applies to internal tool-use chain for *this single prompt*
-            .await?;
-
-        println!("Agent: {}", response_from_agent);
-
-        // This loop makes the interaction continuous, but the agent's memory
-        // for *previous user questions/answers from prior loop iterations* is limited
-        // unless explicitly passed back in the chat_history vector to a lower-level API.
-        // For this `rig` Agent struct, the memory is primarily handled by the preamble
-        // and the multi_turn config for a *single complex request*.
-        // If you ask "What about the second hotel?" in the next turn, it might not remember the first search results.
+/// `cache list`, `cache clear` and `cache clear <location>`.
+fn run_cache_command(cache: &LocationCache, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            let entries = cache.list();
+            if entries.is_empty() {
+                println!("The location cache at {} is empty.", cache.path().display());
+            }
+            for (query, entry, expired) in entries {
+                let location = match entry.result.geo_id {
+                    Some(geo_id) => format!("geoId {}", geo_id),
+                    None => format!("{} candidates", entry.result.candidates.len()),
+                };
+                println!(
+                    "{}: {}, cached {}{}",
+                    query,
+                    location,
+                    entry.stored_at.format("%Y-%m-%d %H:%M"),
+                    if expired { " (expired)" } else { "" }
+                );
+            }
+        }
+        Some("clear") if args.len() > 1 => {
+            let query = args[1..].join(" ");
+            if cache.remove(&query)? {
+                println!("Removed {} from the location cache.", query);
+            } else {
+                println!("{} is not in the location cache.", query);
+            }
+        }
+        Some("clear") => println!("Removed {} cached locations.", cache.clear()?),
+        _ => println!("Usage: hotel_search_assistant cache list | cache clear [location]"),
     }
+    Ok(())
+}
 
+fn list_sessions(store: &SessionStore) -> Result<(), Box<dyn std::error::Error>> {
+    let sessions = store.list()?;
+    if sessions.is_empty() {
+        println!("No saved sessions.");
+    }
+    for session in sessions {
+        println!(
+            "{}  {}  {} hotels  {}",
+            session.id,
+            session.updated_at.format("%Y-%m-%d %H:%M"),
+            session.hotels.len(),
+            session.title()
+        );
+    }
     Ok(())
 }
+
+fn print_quota(quota: &QuotaUsage) {
+    print!("Tripadvisor API: {} requests this session", quota.requests_made);
+    if let (Some(remaining), Some(limit)) = (quota.remaining, quota.limit) {
+        print!(", {} of {} left in the current quota period", remaining, limit);
+    }
+    println!();
+}
@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
+use crate::currency::{convert, RateProvider};
+use crate::utils::{parse_price, parse_review_count, strip_list_number, strip_tags};
 
-#[derive(Clone, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HotelSearchArgs {
+    // the city or area searched, e.g. "Paris", which landmarks in `near` are looked up in
+    pub query: Option<String>,
     #[serde(rename = "geoId")]
     pub geo_id: u64,
     #[serde(rename = "checkIn")]
@@ -12,42 +16,96 @@ pub struct HotelSearchArgs {
     #[serde(rename = "childrenAges")]
     pub children_ages: Option<Vec<u32>>,
     pub rooms: Option<u32>,
+    #[serde(rename = "priceMin")]
+    pub price_min: Option<u32>,
+    #[serde(rename = "priceMax")]
+    pub price_max: Option<u32>,
+    #[serde(rename = "minRating")]
+    pub min_rating: Option<u32>,
+    #[serde(rename = "hotelClass")]
+    pub hotel_class: Option<Vec<u32>>,
+    pub amenities: Option<Vec<String>>,
+    pub sort: Option<HotelSort>,
+    pub currency: Option<String>,
+    pub page: Option<u32>,
+    // a landmark or address to search around, e.g. "Eiffel Tower"
+    pub near: Option<String>,
+    #[serde(rename = "radiusKm")]
+    pub radius_km: Option<f64>,
+}
+
+/// Result orderings offered to the agent, mapped to Tripadvisor's sort values.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HotelSort {
+    Price,
+    Rating,
+    Distance,
+}
+
+impl HotelSort {
+    pub fn api_value(self) -> &'static str {
+        match self {
+            HotelSort::Price => "PRICE_LOW_TO_HIGH",
+            HotelSort::Rating => "TRAVELER_RANKED",
+            HotelSort::Distance => "DISTANCE_FROM_CITY_CENTER",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocationSearchArgs {
     pub query: String, // This is what the LLM will provide (e.g., "Vancouver, BC")
+    // the candidate the user picked after being asked "which one?"
+    #[serde(rename = "confirmedGeoId", default)]
+    pub confirmed_geo_id: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LocationOption {
 
-    title: String,
+    pub title: String, // may contain highlighting, e.g. "<b>Paris</b>"
     #[serde(rename = "geoId")]
     pub geo_id: u64,
     #[serde(rename = "documentId")]
     document_id: String,
     #[serde(rename = "trackingItems")]
     tracking_items: String,
-    #[serde(rename = "secondaryText")]
-    secondary_text: String,
+    #[serde(rename = "secondaryText", default)]
+    pub secondary_text: String, // region and country, e.g. "Texas, United States"
+    #[serde(rename = "geoPoint", default)]
+    pub geo_point: Option<Coordinates>, // sent for landmarks and addresses
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LocationSearchResult {
+pub struct LocationCandidate {
     #[serde(rename = "geoId")]
     pub geo_id: u64,
-    pub location_name: String, // So the LLM knows what it found
+    pub title: String,
+    pub secondary_text: String,
 }
 
+impl From<&LocationOption> for LocationCandidate {
+    fn from(option: &LocationOption) -> Self {
+        Self {
+            geo_id: option.geo_id,
+            title: strip_tags(&option.title),
+            secondary_text: option.secondary_text.clone(),
+        }
+    }
+}
 
-#[derive(Debug, Deserialize)]
-pub(crate) struct ApiResponse<T> {
-    pub status: bool,
-    pub message: String,
-    pub data: T,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationSearchResult {
+    // set once the location is resolved; missing while the user has to choose
+    #[serde(rename = "geoId")]
+    pub geo_id: Option<u64>,
+    pub location_name: String, // So the LLM knows what it found
+    pub candidates: Vec<LocationCandidate>,
+    pub needs_confirmation: bool,
 }
 
+
 #[derive(Debug, Deserialize)]
 pub struct HotelSearchData {
     // sortDisclaimer: String, // If you need this
@@ -68,6 +126,8 @@ pub struct HotelOption {
     #[serde(rename = "priceForDisplay")]
     pub price_for_display: Option<String>, // Can be null
     pub provider: Option<String>, // Can be null, e.g. if priceForDisplay is null
+    #[serde(rename = "geoPoint", default)]
+    pub geo_point: Option<Coordinates>, // Not sent for every hotel
     // Add other fields if you need them, e.g., badge, isSponsored, cardPhotos
 }
 
@@ -77,9 +137,699 @@ pub struct BubbleRating {
     pub rating: f32,   // 4.5
 }
 
+
+/// A hotel from a search, in a structured form the agent can reason over
+/// and other callers can reuse.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotelResult {
+    pub id: String,
+    pub name: String,
+    pub rating: Option<f32>,
+    pub review_count: Option<u32>,
+    // per room and night, in the requested currency when it could be converted
+    pub price: Option<Price>,
+    // the price as the API returned it, when it had to be converted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_price: Option<Price>,
+    pub nights: u32,
+    pub rooms: u32,
+    // price for all nights and rooms
+    pub total: Option<Price>,
+    pub features: Vec<String>,
+    pub neighbourhood: Option<String>,
+    // booking provider, useful when no price is shown
+    pub provider: Option<String>,
+    pub coordinates: Option<Coordinates>,
+    // from the landmark the search was near, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_km: Option<f64>,
+    // set when the search was near a landmark but this hotel's coordinates
+    // couldn't be found, so it may be farther away than asked for
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub distance_unknown: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Price {
+    pub amount: f64,
+    pub currency: String, // ISO 4217 code where the symbol is known, e.g. "USD"
+}
+
+impl From<HotelOption> for HotelResult {
+    fn from(option: HotelOption) -> Self {
+        let rating = option.bubble_rating.as_ref().map(|rating| rating.rating);
+        let review_count = option
+            .bubble_rating
+            .as_ref()
+            .and_then(|rating| parse_review_count(&rating.count));
+        let price = option.price_for_display.as_deref().and_then(parse_price);
+        Self {
+            id: option.id,
+            name: strip_list_number(&option.title),
+            rating,
+            review_count,
+            price: price.clone(),
+            total: price,
+            features: option.primary_info.into_iter().collect(),
+            neighbourhood: option.secondary_info,
+            provider: option.provider,
+            coordinates: option.geo_point,
+            ..Self::default()
+        }
+    }
+}
+
+// a one-night stay in one room, as search results are until `cost_stay`
+impl Default for HotelResult {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            rating: None,
+            review_count: None,
+            price: None,
+            original_price: None,
+            nights: 1,
+            rooms: 1,
+            total: None,
+            features: Vec::new(),
+            neighbourhood: None,
+            provider: None,
+            coordinates: None,
+            distance_km: None,
+            distance_unknown: false,
+        }
+    }
+}
+
+impl HotelResult {
+    /// Works out the total for the stay, with prices converted to `currency`
+    /// where `rates` knows the exchange rate.
+    pub fn cost_stay(&mut self, nights: u32, rooms: u32, currency: &str, rates: &dyn RateProvider) {
+        self.nights = nights;
+        self.rooms = rooms;
+        if let Some(price) = self.price.take() {
+            let price = match convert(&price, currency, rates) {
+                Some(converted) if converted.currency != price.currency => {
+                    self.original_price = Some(price);
+                    converted
+                }
+                _ => price,
+            };
+            let total = price.amount * f64::from(nights) * f64::from(rooms);
+            self.total = Some(Price {
+                amount: (total * 100.0).round() / 100.0,
+                currency: price.currency.clone(),
+            });
+            self.price = Some(price);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShortlistAddArgs {
+    #[serde(rename = "hotelId")]
+    pub hotel_id: String,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShortlistRemoveArgs {
+    #[serde(rename = "hotelId")]
+    pub hotel_id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShortlistCompareArgs {
+    #[serde(rename = "hotelIds")]
+    pub hotel_ids: Option<Vec<String>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HotelDetailsArgs {
+    #[serde(rename = "hotelId")]
+    pub hotel_id: String,
+    #[serde(rename = "checkIn")]
+    pub check_in: Option<String>,
+    #[serde(rename = "checkOut")]
+    pub check_out: Option<String>,
+    #[serde(default)]
+    pub mode: DetailsMode,
+    // what the user asked about, e.g. "noise", used to pick review snippets
+    pub topic: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetailsMode {
+    #[default]
+    Details,
+    Reviews,
+}
+
+// Response of the getHotelDetails endpoint. Most sections can be missing
+// for smaller properties, so everything defaults to empty.
+#[derive(Debug, Deserialize)]
+pub struct HotelDetailsData {
+    #[serde(default)]
+    pub title: String,
+    pub rating: Option<f32>,
+    #[serde(rename = "numberReviews")]
+    pub number_reviews: Option<u32>,
+    #[serde(rename = "rankingDetails")]
+    pub ranking_details: Option<String>,
+    #[serde(default)]
+    pub photos: Vec<ApiPhoto>,
+    pub about: Option<ApiAbout>,
+    #[serde(rename = "amenitiesScreen", default)]
+    pub amenities_screen: Vec<ApiAmenityGroup>,
+    pub location: Option<ApiLocation>,
+    #[serde(rename = "geoPoint")]
+    pub geo_point: Option<Coordinates>,
+    pub reviews: Option<ApiReviews>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiPhoto {
+    #[serde(rename = "urlTemplate")]
+    pub url_template: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiAbout {
+    #[serde(default)]
+    pub content: Vec<ApiTextBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiTextBlock {
+    #[serde(default)]
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiAmenityGroup {
+    #[serde(default)]
+    pub content: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiLocation {
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiReviews {
+    #[serde(default)]
+    pub content: Vec<ApiReview>,
+    #[serde(rename = "ratingCategories", alias = "subratings", default)]
+    pub rating_categories: Vec<ApiRatingCategory>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApiReview {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(rename = "bubbleRatingText")]
+    pub bubble_rating_text: Option<String>,
+    #[serde(rename = "publishedDate")]
+    pub published_date: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
-pub struct ApiError {
-    pub status: bool, // Will be `false` for errors
-    pub timestamp: u64,
-    pub message: String,
-}
\ No newline at end of file
+pub struct ApiRatingCategory {
+    #[serde(alias = "name")]
+    pub title: String,
+    #[serde(alias = "value")]
+    pub rating: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Everything the agent needs to answer questions about one hotel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotelDetails {
+    pub id: String,
+    pub name: String,
+    pub rating: Option<f32>,
+    pub review_count: Option<u32>,
+    pub ranking: Option<String>,
+    pub description: String,
+    pub amenities: Vec<String>,
+    pub address: Option<String>,
+    pub coordinates: Option<Coordinates>,
+    pub photos: Vec<String>,
+    pub category_ratings: Vec<CategoryRating>,
+}
+
+/// An aggregate review score, e.g. "Cleanliness" 4.5.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryRating {
+    pub category: String,
+    pub rating: f32,
+}
+
+/// Reviews of one hotel, with snippets the agent can quote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewSummary {
+    pub id: String,
+    pub name: String,
+    pub rating: Option<f32>,
+    pub review_count: Option<u32>,
+    pub category_ratings: Vec<CategoryRating>,
+    pub topic: Option<String>,
+    pub snippets: Vec<ReviewSnippet>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewSnippet {
+    pub title: String,
+    pub snippet: String,
+    pub rating: Option<String>,
+    pub published: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HotelDetailsOutput {
+    Details(HotelDetails),
+    Reviews(ReviewSummary),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RestaurantSearchArgs {
+    #[serde(rename = "geoId")]
+    pub geo_id: u64,
+    pub cuisines: Option<Vec<String>>,
+    #[serde(rename = "priceLevel")]
+    pub price_level: Option<PriceLevel>,
+    #[serde(rename = "openNow", default)]
+    pub open_now: bool,
+    pub page: Option<u32>,
+}
+
+/// Tripadvisor's dollar-sign price levels for restaurants.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceLevel {
+    Cheap,     // "$"
+    Moderate,  // "$$ - $$$"
+    Expensive, // "$$$$"
+}
+
+impl PriceLevel {
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag.trim() {
+            "$" => Some(PriceLevel::Cheap),
+            "$$ - $$$" | "$$-$$$" | "$$" | "$$$" => Some(PriceLevel::Moderate),
+            "$$$$" => Some(PriceLevel::Expensive),
+            _ => None,
+        }
+    }
+}
+
+// Response of the searchRestaurants endpoint
+#[derive(Debug, Deserialize)]
+pub struct RestaurantSearchData {
+    #[serde(default)]
+    pub data: Vec<ApiRestaurant>,
+}
+
+// The id comes as restaurantsId or locationId depending on the endpoint, and
+// some responses send both, so each is read on its own.
+#[derive(Debug, Deserialize)]
+pub struct ApiRestaurant {
+    #[serde(rename = "restaurantsId", default, deserialize_with = "optional_id")]
+    pub restaurants_id: Option<String>,
+    #[serde(rename = "locationId", default, deserialize_with = "optional_id")]
+    pub location_id: Option<String>,
+    pub name: String,
+    #[serde(rename = "averageRating")]
+    pub average_rating: Option<f32>,
+    #[serde(rename = "userReviewCount")]
+    pub user_review_count: Option<u32>,
+    #[serde(rename = "priceTag")]
+    pub price_tag: Option<String>, // e.g. "$$ - $$$"
+    #[serde(rename = "establishmentTypeAndCuisineTags", default)]
+    pub cuisine_tags: Vec<String>,
+    #[serde(rename = "currentOpenStatusCategory")]
+    pub open_status: Option<String>, // "OPEN" or "CLOSED"
+    #[serde(rename = "parentGeoName")]
+    pub parent_geo_name: Option<String>,
+}
+
+/// A restaurant from a search, in the same shape as hotel results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestaurantResult {
+    pub id: String,
+    pub name: String,
+    pub rating: Option<f32>,
+    pub review_count: Option<u32>,
+    pub cuisines: Vec<String>,
+    pub price_level: Option<PriceLevel>,
+    pub open_now: Option<bool>,
+    pub neighbourhood: Option<String>,
+}
+
+impl From<ApiRestaurant> for RestaurantResult {
+    fn from(restaurant: ApiRestaurant) -> Self {
+        Self {
+            id: restaurant.restaurants_id.or(restaurant.location_id).unwrap_or_default(),
+            name: restaurant.name,
+            rating: restaurant.average_rating,
+            review_count: restaurant.user_review_count,
+            cuisines: restaurant.cuisine_tags,
+            price_level: restaurant.price_tag.as_deref().and_then(PriceLevel::from_tag),
+            open_now: open_now(restaurant.open_status.as_deref()),
+            neighbourhood: restaurant.parent_geo_name,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AttractionSearchArgs {
+    #[serde(rename = "geoId")]
+    pub geo_id: u64,
+    pub category: Option<String>,
+    #[serde(rename = "openNow", default)]
+    pub open_now: bool,
+    pub page: Option<u32>,
+}
+
+// Response of the searchAttractions endpoint
+#[derive(Debug, Deserialize)]
+pub struct AttractionSearchData {
+    #[serde(default)]
+    pub data: Vec<ApiAttraction>,
+}
+
+// Attractions come in two shapes, with locationId, name, subcategories and
+// parentGeoName or with id, title, primaryInfo and secondaryInfo. Responses
+// may mix them, so every key is read on its own and the first shape preferred.
+#[derive(Debug, Deserialize)]
+pub struct ApiAttraction {
+    #[serde(rename = "locationId", default, deserialize_with = "optional_id")]
+    pub location_id: Option<String>,
+    #[serde(default, deserialize_with = "optional_id")]
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    #[serde(rename = "averageRating")]
+    pub average_rating: Option<f32>,
+    #[serde(rename = "userReviewCount")]
+    pub user_review_count: Option<u32>,
+    #[serde(default, deserialize_with = "categories")]
+    pub subcategories: Vec<String>, // e.g. ["Museums", "Art Museums"]
+    #[serde(rename = "primaryInfo", default, deserialize_with = "categories")]
+    pub primary_info: Vec<String>,
+    #[serde(rename = "currentOpenStatusCategory")]
+    pub open_status: Option<String>,
+    #[serde(rename = "parentGeoName")]
+    pub parent_geo_name: Option<String>,
+    #[serde(rename = "secondaryInfo")]
+    pub secondary_info: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttractionResult {
+    pub id: String,
+    pub name: String,
+    pub rating: Option<f32>,
+    pub review_count: Option<u32>,
+    pub categories: Vec<String>,
+    pub open_now: Option<bool>,
+    pub neighbourhood: Option<String>,
+}
+
+impl From<ApiAttraction> for AttractionResult {
+    fn from(attraction: ApiAttraction) -> Self {
+        let categories = if attraction.subcategories.is_empty() {
+            attraction.primary_info
+        } else {
+            attraction.subcategories
+        };
+        Self {
+            id: attraction.location_id.or(attraction.id).unwrap_or_default(),
+            name: strip_list_number(&attraction.name.or(attraction.title).unwrap_or_default()),
+            rating: attraction.average_rating,
+            review_count: attraction.user_review_count,
+            categories,
+            open_now: open_now(attraction.open_status.as_deref()),
+            neighbourhood: attraction.parent_geo_name.or(attraction.secondary_info),
+        }
+    }
+}
+
+fn open_now(status: Option<&str>) -> Option<bool> {
+    match status? {
+        "OPEN" => Some(true),
+        "CLOSED" => Some(false),
+        _ => None, // e.g. "UNKNOWN" when no hours are listed
+    }
+}
+
+// ids come as numbers from some endpoints and strings from others
+fn id_string<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(id) => Ok(id),
+        serde_json::Value::Number(id) => Ok(id.to_string()),
+        other => Err(serde::de::Error::custom(format!("unexpected id {}", other))),
+    }
+}
+
+// for ids sent under one of several keys; a missing key is None, a null id an error
+fn optional_id<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    id_string(deserializer).map(Some)
+}
+
+// a list of category names, or a single string such as "Art Museums • Parks"
+fn categories<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(text) => text.split([',', '•']).map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect(),
+        serde_json::Value::Array(items) => items
+            .into_iter()
+            .filter_map(|item| match item {
+                serde_json::Value::String(name) => Some(name),
+                // {"name": "Museums"} objects
+                serde_json::Value::Object(object) => object.get("name").and_then(|n| n.as_str()).map(str::to_string),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // one US dollar buys 0.9 euros
+    struct UsdToEur;
+
+    impl RateProvider for UsdToEur {
+        fn rate(&self, from: &str, to: &str) -> Option<f64> {
+            match (from, to) {
+                _ if from == to => Some(1.0),
+                ("USD", "EUR") => Some(0.9),
+                ("EUR", "USD") => Some(1.0 / 0.9),
+                _ => None,
+            }
+        }
+    }
+
+    fn hotel(id: &str, price: Option<(f64, &str)>) -> HotelResult {
+        let price = price.map(|(amount, currency)| Price {
+            amount,
+            currency: currency.to_string(),
+        });
+        HotelResult {
+            id: id.to_string(),
+            name: format!("Hotel {}", id),
+            price: price.clone(),
+            total: price,
+            ..HotelResult::default()
+        }
+    }
+
+    #[test]
+    fn totals_cover_every_night_and_room() {
+        let mut hotel = hotel("1", Some((95.1, "EUR")));
+        hotel.cost_stay(3, 2, "EUR", &UsdToEur);
+        assert_eq!((hotel.nights, hotel.rooms), (3, 2));
+        assert_eq!(hotel.price.as_ref().unwrap().amount, 95.1);
+        // not 570.5999…
+        assert_eq!(hotel.total.as_ref().unwrap().amount, 570.6);
+        assert!(hotel.original_price.is_none());
+    }
+
+    #[test]
+    fn converted_prices_keep_the_original() {
+        let mut hotel = hotel("1", Some((200.0, "USD")));
+        hotel.cost_stay(2, 1, "EUR", &UsdToEur);
+        assert_eq!(hotel.price.as_ref().unwrap().currency, "EUR");
+        assert_eq!(hotel.price.as_ref().unwrap().amount, 180.0);
+        assert_eq!(hotel.total.as_ref().unwrap().amount, 360.0);
+        assert_eq!(hotel.original_price.as_ref().unwrap().amount, 200.0);
+    }
+
+    #[test]
+    fn unconvertible_prices_stay_in_their_currency() {
+        let mut hotel = hotel("1", Some((5000.0, "INR")));
+        hotel.cost_stay(2, 1, "EUR", &UsdToEur);
+        assert_eq!(hotel.total.as_ref().unwrap().currency, "INR");
+        assert_eq!(hotel.total.as_ref().unwrap().amount, 10000.0);
+        assert!(hotel.original_price.is_none());
+    }
+
+    #[test]
+    fn hotels_without_a_price_have_no_total() {
+        let mut hotel = hotel("1", None);
+        hotel.cost_stay(4, 2, "EUR", &UsdToEur);
+        assert!(hotel.price.is_none());
+        assert!(hotel.total.is_none());
+        assert_eq!((hotel.nights, hotel.rooms), (4, 2));
+    }
+
+    #[test]
+    fn large_stays_do_not_overflow() {
+        let mut hotel = hotel("1", Some((100.0, "EUR")));
+        hotel.cost_stay(u32::MAX, u32::MAX, "EUR", &UsdToEur);
+        assert!(hotel.total.as_ref().unwrap().amount > 1e20);
+    }
+
+    #[test]
+    fn cheapest_total_for_four_nights_in_eur() {
+        let mut hotels = vec![
+            hotel("a", Some((120.0, "USD"))),
+            hotel("b", Some((105.0, "EUR"))),
+            hotel("c", None),
+            hotel("d", Some((115.0, "USD"))),
+        ];
+        for hotel in &mut hotels {
+            hotel.cost_stay(4, 1, "EUR", &UsdToEur);
+        }
+        let cheapest = hotels
+            .iter()
+            .filter_map(|hotel| Some((hotel, hotel.total.as_ref()?)))
+            .filter(|(_, total)| total.currency == "EUR")
+            .min_by(|(_, a), (_, b)| a.amount.total_cmp(&b.amount))
+            .unwrap();
+        // 115 USD is 103.50 EUR a night
+        assert_eq!(cheapest.0.id, "d");
+        assert_eq!(cheapest.1.amount, 414.0);
+    }
+
+    #[test]
+    fn restaurant_ids_may_be_numbers_or_strings() {
+        let data: RestaurantSearchData = serde_json::from_value(serde_json::json!({
+            "data": [
+                {
+                    "restaurantsId": 12345678,
+                    "name": "Taberna da Rua das Flores",
+                    "averageRating": 4.5,
+                    "userReviewCount": 2874,
+                    "priceTag": "$$ - $$$",
+                    "establishmentTypeAndCuisineTags": ["Portuguese", "Seafood"],
+                    "currentOpenStatusCategory": "OPEN",
+                    "parentGeoName": "Lisbon"
+                },
+                { "locationId": "87654321", "name": "Cafe A Brasileira", "currentOpenStatusCategory": "UNKNOWN" }
+            ]
+        }))
+        .unwrap();
+
+        let restaurants: Vec<RestaurantResult> = data.data.into_iter().map(RestaurantResult::from).collect();
+        assert_eq!(restaurants[0].id, "12345678");
+        assert_eq!(restaurants[0].price_level, Some(PriceLevel::Moderate));
+        assert_eq!(restaurants[0].open_now, Some(true));
+        assert_eq!(restaurants[1].id, "87654321");
+        assert!(restaurants[1].cuisines.is_empty());
+        assert_eq!(restaurants[1].open_now, None);
+
+        let invalid = serde_json::from_value::<ApiRestaurant>(serde_json::json!({ "restaurantsId": null, "name": "?" }));
+        assert!(invalid.unwrap_err().to_string().contains("unexpected id null"));
+    }
+
+    #[test]
+    fn restaurants_with_both_id_keys_use_restaurants_id() {
+        let restaurant: ApiRestaurant = serde_json::from_value(serde_json::json!({
+            "restaurantsId": 12345678,
+            "locationId": "87654321",
+            "name": "Taberna da Rua das Flores"
+        }))
+        .unwrap();
+
+        assert_eq!(RestaurantResult::from(restaurant).id, "12345678");
+    }
+
+    #[test]
+    fn attractions_with_both_shapes_prefer_the_search_fields() {
+        let attraction: ApiAttraction = serde_json::from_value(serde_json::json!({
+            "locationId": 189164,
+            "id": "195286",
+            "name": "Oceanario de Lisboa",
+            "title": "1. Oceanario",
+            "subcategories": ["Aquariums"],
+            "primaryInfo": "Nature & Wildlife Areas",
+            "parentGeoName": "Parque das Nacoes",
+            "secondaryInfo": "Lisbon"
+        }))
+        .unwrap();
+
+        let attraction = AttractionResult::from(attraction);
+        assert_eq!(attraction.id, "189164");
+        assert_eq!(attraction.name, "Oceanario de Lisboa");
+        assert_eq!(attraction.categories, ["Aquariums"]);
+        assert_eq!(attraction.neighbourhood.as_deref(), Some("Parque das Nacoes"));
+
+        // empty subcategories fall back to primaryInfo
+        let attraction: ApiAttraction = serde_json::from_value(serde_json::json!({
+            "locationId": 1,
+            "name": "Viewpoint",
+            "subcategories": [],
+            "primaryInfo": "Lookouts"
+        }))
+        .unwrap();
+        assert_eq!(AttractionResult::from(attraction).categories, ["Lookouts"]);
+    }
+
+    #[test]
+    fn attraction_categories_come_as_lists_objects_or_text() {
+        let data: AttractionSearchData = serde_json::from_value(serde_json::json!({
+            "data": [
+                {
+                    "locationId": 189164,
+                    "name": "Oceanario de Lisboa",
+                    "averageRating": 4.5,
+                    "subcategories": [{ "name": "Aquariums" }, "Nature & Wildlife Areas", 7],
+                    "currentOpenStatusCategory": "CLOSED",
+                    "parentGeoName": "Parque das Nacoes"
+                },
+                {
+                    "id": "195286",
+                    "title": "2. Jeronimos Monastery",
+                    "primaryInfo": "Churches & Cathedrals • Historic Sites, Architectural Buildings",
+                    "secondaryInfo": "Belem"
+                },
+                { "locationId": 1, "name": "Viewpoint", "subcategories": null }
+            ]
+        }))
+        .unwrap();
+
+        let attractions: Vec<AttractionResult> = data.data.into_iter().map(AttractionResult::from).collect();
+        assert_eq!(attractions[0].id, "189164");
+        assert_eq!(attractions[0].categories, ["Aquariums", "Nature & Wildlife Areas"]);
+        assert_eq!(attractions[0].open_now, Some(false));
+        assert_eq!(attractions[1].id, "195286");
+        assert_eq!(attractions[1].name, "Jeronimos Monastery");
+        assert_eq!(attractions[1].categories, ["Churches & Cathedrals", "Historic Sites", "Architectural Buildings"]);
+        assert_eq!(attractions[1].neighbourhood.as_deref(), Some("Belem"));
+        assert!(attractions[2].categories.is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::tool_error::HotelSearchError;
+use crate::model::{ApiRestaurant, RestaurantResult, RestaurantSearchArgs, RestaurantSearchData};
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde_json::json;
+use std::collections::HashMap;
+use tripadvisor_client::TripadvisorClient;
+
+pub struct RestaurantSearchTool {
+    pub client: TripadvisorClient,
+}
+
+impl Tool for RestaurantSearchTool {
+    const NAME: &'static str = "search_restaurants";
+    type Error = HotelSearchError;
+    type Args = RestaurantSearchArgs;
+    type Output = Vec<RestaurantResult>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Search for restaurants in a location. Returns the restaurants with their rating, \
+                review count, cuisines, price level, whether they're open now and their neighbourhood. \
+                The cuisine, price level and open now filters only apply to the one page of results fetched, \
+                so a filtered page can come back short or empty; request the next page for more matches.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "geoId": {
+                        "type": "integer",
+                        "description": "The geographic ID of the location to search in. Obtained from search_location, the same as for hotels."
+                    },
+                    "cuisines": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Cuisines or kinds of place to include, e.g. [\"Italian\", \"Vegetarian Friendly\", \"Cafe\"]. A restaurant matching any of them is included."
+                    },
+                    "priceLevel": {
+                        "type": "string",
+                        "enum": ["cheap", "moderate", "expensive"],
+                        "description": "Only include restaurants at this price level: cheap ($), moderate ($$ - $$$) or expensive ($$$$)."
+                    },
+                    "openNow": {
+                        "type": "boolean",
+                        "description": "Only include restaurants that are open right now."
+                    },
+                    "page": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Results page, starting at 1. Filters are applied to each page separately."
+                    }
+                },
+                "required": ["geoId"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let mut query_params = HashMap::new();
+        query_params.insert("locationId", args.geo_id.to_string());
+        query_params.insert("page", args.page.unwrap_or(1).max(1).to_string());
+        tracing::debug!(geo_id = args.geo_id, "searching restaurants");
+
+        let response_data: RestaurantSearchData = self
+            .client
+            .get("/api/v1/restaurant/searchRestaurants", &query_params)
+            .await?;
+
+        Ok(filter_restaurants(response_data.data, &args))
+    }
+}
+
+// The API has no parameters for these, so only the page fetched is filtered
+// and a page may come back with fewer restaurants, or none.
+fn filter_restaurants(page: Vec<ApiRestaurant>, args: &RestaurantSearchArgs) -> Vec<RestaurantResult> {
+    let cuisines: Vec<String> = args
+        .cuisines
+        .iter()
+        .flatten()
+        .map(|cuisine| cuisine.trim().to_lowercase())
+        .filter(|cuisine| !cuisine.is_empty())
+        .collect();
+    page.into_iter()
+        .map(RestaurantResult::from)
+        .filter(|restaurant| {
+            cuisines.is_empty()
+                || restaurant.cuisines.iter().any(|tag| {
+                    let tag = tag.to_lowercase();
+                    cuisines.iter().any(|cuisine| tag.contains(cuisine.as_str()))
+                })
+        })
+        .filter(|restaurant| args.price_level.is_none() || restaurant.price_level == args.price_level)
+        .filter(|restaurant| !args.open_now || restaurant.open_now == Some(true))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(restaurants: &[(&str, &[&str], &str, &str)]) -> Vec<ApiRestaurant> {
+        restaurants
+            .iter()
+            .map(|(id, cuisines, price_tag, status)| {
+                serde_json::from_value(json!({
+                    "restaurantsId": id,
+                    "name": format!("Restaurant {}", id),
+                    "establishmentTypeAndCuisineTags": cuisines,
+                    "priceTag": price_tag,
+                    "currentOpenStatusCategory": status
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn lisbon() -> Vec<ApiRestaurant> {
+        page(&[
+            ("1", &["Portuguese", "Seafood"], "$$ - $$$", "OPEN"),
+            ("2", &["Japanese", "Sushi"], "$$$$", "CLOSED"),
+            ("3", &["Seafood", "Mediterranean"], "$", "UNKNOWN"),
+        ])
+    }
+
+    fn args(filters: serde_json::Value) -> RestaurantSearchArgs {
+        let mut args = json!({ "geoId": 189158 });
+        args.as_object_mut().unwrap().extend(filters.as_object().unwrap().clone());
+        serde_json::from_value(args).unwrap()
+    }
+
+    fn ids(restaurants: Vec<RestaurantResult>) -> Vec<String> {
+        restaurants.into_iter().map(|restaurant| restaurant.id).collect()
+    }
+
+    #[test]
+    fn without_filters_the_whole_page_is_returned() {
+        assert_eq!(ids(filter_restaurants(lisbon(), &args(json!({})))), ["1", "2", "3"]);
+        // blank cuisines don't filter
+        assert_eq!(ids(filter_restaurants(lisbon(), &args(json!({ "cuisines": [" "] })))).len(), 3);
+    }
+
+    #[test]
+    fn cuisines_match_any_tag_ignoring_case() {
+        let seafood_or_sushi = args(json!({ "cuisines": ["seafood", "SUSHI"] }));
+        let mediterranean = args(json!({ "cuisines": ["mediterr"] }));
+
+        assert_eq!(ids(filter_restaurants(lisbon(), &seafood_or_sushi)), ["1", "2", "3"]);
+        assert_eq!(ids(filter_restaurants(lisbon(), &mediterranean)), ["3"]);
+    }
+
+    #[test]
+    fn price_level_and_open_now_narrow_the_page() {
+        assert_eq!(ids(filter_restaurants(lisbon(), &args(json!({ "priceLevel": "cheap" })))), ["3"]);
+        // a restaurant with unknown hours isn't assumed to be open
+        assert_eq!(ids(filter_restaurants(lisbon(), &args(json!({ "openNow": true })))), ["1"]);
+        let both = args(json!({ "cuisines": ["seafood"], "priceLevel": "moderate", "openNow": true }));
+        assert_eq!(ids(filter_restaurants(lisbon(), &both)), ["1"]);
+    }
+
+    #[test]
+    fn filters_apply_to_the_page_fetched_only() {
+        let args = args(json!({ "cuisines": ["sushi"], "page": 2 }));
+        let second_page = page(&[("4", &["Italian", "Pizza"], "$$ - $$$", "OPEN")]);
+
+        // nothing on this page matches, and no other page is looked at
+        assert!(filter_restaurants(second_page, &args).is_empty());
+    }
+}
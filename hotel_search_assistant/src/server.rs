@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use rig::completion::PromptError;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use crate::agent::{build_agent, latest_search_results, AgentResources, HotelAgent};
+use crate::audit::{AuditLog, ToolCallEvent};
+use crate::conversation::{Conversation, MAX_CONVERSATION_TURNS};
+use crate::geo::to_geojson;
+use crate::model::HotelResult;
+use crate::shortlist::Shortlist;
+
+// Sessions that haven't been used for this long are dropped
+const SESSION_TTL: Duration = Duration::from_secs(30 * 60);
+// Most sessions kept at once; the least recently used one makes room for a new one
+const MAX_SESSIONS: usize = 1000;
+
+struct AppState {
+    resources: AgentResources,
+    sessions: Mutex<HashMap<String, SessionEntry>>,
+    max_sessions: usize,
+}
+
+// each session gets its own agent, since the tools keep per-session state
+// such as the locations the user confirmed and the shortlist
+struct ChatSession {
+    agent: HotelAgent,
+    // the agent's audit log, watched while a streamed message is answered
+    audit: AuditLog,
+    conversation: Conversation,
+    // from the latest searches, for the GeoJSON export
+    hotels: Vec<HotelResult>,
+}
+
+struct SessionEntry {
+    session: Arc<Mutex<ChatSession>>,
+    last_seen: Instant,
+}
+
+#[derive(Deserialize)]
+struct ChatRequest {
+    // omitted to start a new conversation; unknown or expired ids start one too
+    session_id: Option<String>,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ChatResponse {
+    session_id: String,
+    reply: String,
+    // hotels found while answering this message
+    hotels: Vec<HotelResult>,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ChatError {
+    #[error("The message is empty")]
+    EmptyMessage,
+    #[error("The agent failed to answer: {0}")]
+    Agent(#[from] PromptError),
+}
+
+impl IntoResponse for ChatError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ChatError::EmptyMessage => StatusCode::BAD_REQUEST,
+            ChatError::Agent(_) => StatusCode::BAD_GATEWAY,
+        };
+        (status, Json(serde_json::json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+/// Serves the chat page and API on `address` until the process is stopped.
+pub async fn serve(resources: AgentResources, address: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let state = Arc::new(AppState {
+        resources,
+        sessions: Mutex::default(),
+        max_sessions: MAX_SESSIONS,
+    });
+    let listener = TcpListener::bind(address).await?;
+    println!("Hotel Finder listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
+
+fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(index))
+        .route("/api/chat", post(chat))
+        .route("/api/chat/stream", post(chat_stream))
+        .route("/api/sessions/{id}", delete(end_session))
+        .route("/api/sessions/{id}/geojson", get(session_geojson))
+        .with_state(state)
+}
+
+async fn index() -> Html<&'static str> {
+    Html(include_str!("../static/index.html"))
+}
+
+/// Answers one message and returns the reply with any hotels found.
+async fn chat(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ChatRequest>,
+) -> Result<Json<ChatResponse>, ChatError> {
+    let message = non_empty(&request.message)?;
+    let (session_id, session) = session(&state, request.session_id.as_deref()).await;
+    let (reply, hotels) = answer(&session, message, None).await?;
+    Ok(Json(ChatResponse { session_id, reply, hotels }))
+}
+
+/// Answers one message as server-sent events: `session` with the session id,
+/// `status` while the agent works, `tool_call` and `tool_result` as each tool
+/// call starts and finishes, then `hotels` (if any were found) and `message`,
+/// or `error`, and finally `done`.
+async fn chat_stream(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ChatRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ChatError> {
+    non_empty(&request.message)?;
+    let (session_id, session) = session(&state, request.session_id.as_deref()).await;
+    let (events, receiver) = mpsc::channel(16);
+
+    tokio::spawn(async move {
+        let _ = events.send(Event::default().event("session").data(&session_id)).await;
+        let _ = events.send(Event::default().event("status").data("thinking")).await;
+
+        let (progress, mut tool_calls) = mpsc::unbounded_channel();
+        let forward = {
+            let events = events.clone();
+            tokio::spawn(async move {
+                while let Some(event) = tool_calls.recv().await {
+                    let _ = events.send(tool_call_event(event)).await;
+                }
+            })
+        };
+        let result = answer(&session, &request.message, Some(progress)).await;
+        // the progress sender is dropped with the turn, so this ends once
+        // every tool call event has been sent
+        let _ = forward.await;
+
+        let event = match result {
+            Ok((reply, hotels)) => {
+                if !hotels.is_empty() {
+                    let hotels = serde_json::to_string(&hotels).unwrap_or_else(|_| "[]".to_string());
+                    let _ = events.send(Event::default().event("hotels").data(hotels)).await;
+                }
+                Event::default().event("message").data(reply)
+            }
+            Err(e) => Event::default().event("error").data(e.to_string()),
+        };
+        let _ = events.send(event).await;
+        let _ = events.send(Event::default().event("done").data("")).await;
+    });
+
+    Ok(Sse::new(ReceiverStream::new(receiver).map(Ok)).keep_alive(KeepAlive::default()))
+}
+
+// `tool_call` with the tool and its arguments, `tool_result` with the tool,
+// its latency and the error if it failed
+fn tool_call_event(event: ToolCallEvent) -> Event {
+    let (name, data) = match event {
+        ToolCallEvent::Started { tool, arguments } => {
+            ("tool_call", serde_json::json!({ "tool": tool, "arguments": arguments }))
+        }
+        ToolCallEvent::Finished(record) => (
+            "tool_result",
+            serde_json::json!({ "tool": record.tool, "latency_ms": record.latency_ms, "error": record.error }),
+        ),
+    };
+    Event::default().event(name).data(data.to_string())
+}
+
+/// Forgets a conversation.
+async fn end_session(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> StatusCode {
+    state.sessions.lock().await.remove(&id);
+    StatusCode::NO_CONTENT
+}
+
+/// The session's latest hotel results as a GeoJSON FeatureCollection.
+async fn session_geojson(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let session = state
+        .sessions
+        .lock()
+        .await
+        .get(&id)
+        .map(|entry| entry.session.clone())
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let session = session.lock().await;
+    Ok(Json(to_geojson(&session.hotels)))
+}
+
+fn non_empty(message: &str) -> Result<&str, ChatError> {
+    let message = message.trim();
+    if message.is_empty() {
+        return Err(ChatError::EmptyMessage);
+    }
+    Ok(message)
+}
+
+// answers in the session, telling `progress` about the tool calls as they happen
+async fn answer(
+    session: &Mutex<ChatSession>,
+    message: &str,
+    progress: Option<mpsc::UnboundedSender<ToolCallEvent>>,
+) -> Result<(String, Vec<HotelResult>), ChatError> {
+    let message = non_empty(message)?;
+    // hold the session for the whole turn so concurrent messages in the
+    // same session can't interleave their history
+    let mut session = session.lock().await;
+    let ChatSession { agent, audit, conversation, .. } = &mut *session;
+    if let Some(progress) = progress {
+        audit.watch(progress);
+    }
+    let reply = conversation.send(&*agent, message).await;
+    audit.unwatch();
+    let reply = reply?;
+    let hotels: Vec<HotelResult> = latest_search_results(conversation).into_iter().flatten().collect();
+    if !hotels.is_empty() {
+        session.hotels = hotels.clone();
+    }
+    Ok((reply, hotels))
+}
+
+// the session with this id, or a new one under a fresh id when the id is
+// missing, unknown or expired, so clients can't choose their own ids
+async fn session(state: &AppState, id: Option<&str>) -> (String, Arc<Mutex<ChatSession>>) {
+    let mut sessions = state.sessions.lock().await;
+    let now = Instant::now();
+    sessions.retain(|_, entry| now.duration_since(entry.last_seen) < SESSION_TTL);
+
+    if let Some(id) = id
+        && let Some(entry) = sessions.get_mut(id)
+    {
+        entry.last_seen = now;
+        return (id.to_string(), entry.session.clone());
+    }
+
+    if sessions.len() >= state.max_sessions {
+        let oldest = sessions
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_seen)
+            .map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest {
+            sessions.remove(&oldest);
+        }
+    }
+
+    let id = new_session_id();
+    let audit = AuditLog::new(&id);
+    let session = Arc::new(Mutex::new(ChatSession {
+        agent: build_agent(&state.resources, Shortlist::default(), audit.clone()),
+        audit,
+        conversation: Conversation::new(MAX_CONVERSATION_TURNS),
+        hotels: Vec::new(),
+    }));
+    sessions.insert(
+        id.clone(),
+        SessionEntry {
+            session: session.clone(),
+            last_seen: now,
+        },
+    );
+    (id, session)
+}
+
+fn new_session_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::Mutex as StdMutex;
+    use axum::body::Body;
+    use axum::http::{header, Request};
+    use rig::providers::openai;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+    use tripadvisor_client::TripadvisorClient;
+    use crate::currency::RateTable;
+    use crate::location_cache::LocationCache;
+    use super::*;
+
+    /// One scripted LLM turn.
+    enum Reply {
+        ToolCall { name: &'static str, arguments: Value },
+        Text(&'static str),
+    }
+
+    #[derive(Clone)]
+    struct LlmState {
+        script: Arc<StdMutex<VecDeque<Reply>>>,
+        requests: Arc<StdMutex<Vec<Value>>>,
+    }
+
+    // scripted OpenAI-compatible `/chat/completions`, returning the server's url
+    // and the request bodies it receives
+    async fn mock_llm(script: Vec<Reply>) -> (String, Arc<StdMutex<Vec<Value>>>) {
+        let state = LlmState {
+            script: Arc::new(StdMutex::new(script.into())),
+            requests: Arc::default(),
+        };
+        let requests = state.requests.clone();
+        let router = Router::new()
+            .route("/chat/completions", post(chat_completions))
+            .with_state(state);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (url, requests)
+    }
+
+    async fn chat_completions(State(state): State<LlmState>, Json(request): Json<Value>) -> Response {
+        let reply = state.script.lock().unwrap().pop_front();
+        let call_id = format!("call_{}", request["messages"].as_array().map_or(0, Vec::len));
+        state.requests.lock().unwrap().push(request);
+        let Some(reply) = reply else {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "mock LLM script exhausted").into_response();
+        };
+        let (message, finish_reason) = match reply {
+            Reply::ToolCall { name, arguments } => (
+                json!({
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{
+                        "id": call_id,
+                        "type": "function",
+                        "function": { "name": name, "arguments": arguments.to_string() }
+                    }]
+                }),
+                "tool_calls",
+            ),
+            Reply::Text(text) => (json!({ "role": "assistant", "content": text }), "stop"),
+        };
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "gpt-4-turbo",
+            "system_fingerprint": null,
+            "choices": [{ "index": 0, "message": message, "logprobs": null, "finish_reason": finish_reason }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+        .into_response()
+    }
+
+    // the app wired to the mock LLM; Tripadvisor points at a closed port, so
+    // only tool calls that fail before any request can be scripted
+    fn test_state(llm_url: &str, max_sessions: usize) -> Arc<AppState> {
+        let cache_dir = std::env::temp_dir().join(format!("hotel-assistant-test-{}", uuid::Uuid::new_v4()));
+        let resources = AgentResources {
+            openai_client: openai::Client::from_url("test-key", llm_url),
+            tripadvisor: TripadvisorClient::new("test-key").unwrap().with_base_url("http://127.0.0.1:9"),
+            location_cache: Arc::new(LocationCache::open(&cache_dir, chrono::Duration::days(1))),
+            rates: Arc::new(RateTable::default()),
+        };
+        Arc::new(AppState {
+            resources,
+            sessions: Mutex::default(),
+            max_sessions,
+        })
+    }
+
+    async fn post_json(state: &Arc<AppState>, uri: &str, body: Value) -> (StatusCode, String) {
+        let request = Request::post(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    // (event, data) pairs of a server-sent events body
+    fn sse_events(body: &str) -> Vec<(String, String)> {
+        body.split("\n\n")
+            .filter(|block| !block.trim().is_empty())
+            .map(|block| {
+                let mut event = "message".to_string();
+                let mut data = Vec::new();
+                for line in block.lines() {
+                    if let Some(name) = line.strip_prefix("event:") {
+                        event = name.trim().to_string();
+                    } else if let Some(line) = line.strip_prefix("data:") {
+                        data.push(line.strip_prefix(' ').unwrap_or(line));
+                    }
+                }
+                (event, data.join("\n"))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn chat_returns_the_reply_and_a_session_id() {
+        let (url, _) = mock_llm(vec![Reply::Text("Where would you like to stay?")]).await;
+        let state = test_state(&url, MAX_SESSIONS);
+
+        let (status, body) = post_json(&state, "/api/chat", json!({ "message": "Find me a hotel" })).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let response: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(response["reply"], "Where would you like to stay?");
+        assert_eq!(response["hotels"], json!([]));
+        let session_id = response["session_id"].as_str().unwrap();
+        assert!(state.sessions.lock().await.contains_key(session_id));
+    }
+
+    #[tokio::test]
+    async fn empty_messages_are_rejected() {
+        let (url, requests) = mock_llm(Vec::new()).await;
+        let state = test_state(&url, MAX_SESSIONS);
+
+        for uri in ["/api/chat", "/api/chat/stream"] {
+            let (status, body) = post_json(&state, uri, json!({ "message": "  " })).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["error"], "The message is empty");
+        }
+        assert!(requests.lock().unwrap().is_empty());
+        assert!(state.sessions.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_session_id_continues_the_conversation() {
+        let (url, requests) = mock_llm(vec![Reply::Text("Which dates?"), Reply::Text("Searching Lisbon")]).await;
+        let state = test_state(&url, MAX_SESSIONS);
+
+        let (_, body) = post_json(&state, "/api/chat", json!({ "message": "A hotel in Lisbon" })).await;
+        let session_id = serde_json::from_str::<Value>(&body).unwrap()["session_id"].clone();
+        let (status, body) = post_json(
+            &state,
+            "/api/chat",
+            json!({ "session_id": session_id, "message": "Next weekend" }),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        let response: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(response["session_id"], session_id);
+        assert_eq!(response["reply"], "Searching Lisbon");
+        // the second request carries the first exchange
+        let second = requests.lock().unwrap()[1].to_string();
+        assert!(second.contains("A hotel in Lisbon"));
+        assert!(second.contains("Which dates?"));
+    }
+
+    #[tokio::test]
+    async fn unknown_session_ids_get_a_new_session() {
+        let (url, requests) = mock_llm(vec![Reply::Text("Hello")]).await;
+        let state = test_state(&url, MAX_SESSIONS);
+
+        let (_, body) = post_json(&state, "/api/chat", json!({ "session_id": "chosen-by-client", "message": "Hi" })).await;
+
+        let session_id = serde_json::from_str::<Value>(&body).unwrap()["session_id"].clone();
+        assert_ne!(session_id, "chosen-by-client");
+        assert!(!state.sessions.lock().await.contains_key("chosen-by-client"));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn the_least_recently_used_session_is_evicted() {
+        let state = test_state("http://127.0.0.1:9", 2);
+
+        let (first, _) = session(&state, None).await;
+        let (second, _) = session(&state, None).await;
+        // using the first session makes the second the oldest
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        session(&state, Some(&first)).await;
+        let (third, _) = session(&state, None).await;
+
+        let sessions = state.sessions.lock().await;
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.contains_key(&first));
+        assert!(!sessions.contains_key(&second));
+        assert!(sessions.contains_key(&third));
+    }
+
+    #[tokio::test]
+    async fn the_stream_reports_tool_calls_as_they_happen() {
+        let (url, _) = mock_llm(vec![
+            Reply::ToolCall {
+                name: "search_hotel",
+                arguments: json!({ "query": "Lisbon", "geoId": 189158, "checkIn": "tomorrow", "adults": 2, "rooms": 50 }),
+            },
+            Reply::Text("At most 10 rooms can be booked at once."),
+        ])
+        .await;
+        let state = test_state(&url, MAX_SESSIONS);
+
+        let (status, body) = post_json(&state, "/api/chat/stream", json!({ "message": "50 rooms in Lisbon" })).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let events = sse_events(&body);
+        let names: Vec<&str> = events.iter().map(|(event, _)| event.as_str()).collect();
+        assert_eq!(names, ["session", "status", "tool_call", "tool_result", "message", "done"]);
+        let call: Value = serde_json::from_str(&events[2].1).unwrap();
+        assert_eq!(call["tool"], "search_hotel");
+        assert_eq!(call["arguments"]["rooms"], 50);
+        let result: Value = serde_json::from_str(&events[3].1).unwrap();
+        assert_eq!(result["tool"], "search_hotel");
+        assert!(result["error"].as_str().unwrap().contains("rooms"));
+        assert_eq!(events[4].1, "At most 10 rooms can be booked at once.");
+    }
+}
@@ -0,0 +1,310 @@
+use std::io;
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use rig::completion::Message;
+use rig::message::{AssistantContent, ToolResultContent, UserContent};
+use serde::{Deserialize, Serialize};
+use crate::model::HotelResult;
+use crate::shortlist::{comparison_table, ShortlistedHotel};
+
+// Number of distinct hotels remembered per session
+const MAX_HOTELS: usize = 100;
+
+/// A CLI conversation as saved to disk: the message history, tool calls and
+/// results included, the hotels found along the way and the shortlist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub history: Vec<Message>,
+    #[serde(default)]
+    pub hotels: Vec<HotelResult>,
+    #[serde(default)]
+    pub shortlist: Vec<ShortlistedHotel>,
+}
+
+impl SavedSession {
+    pub fn start() -> Self {
+        let now = Utc::now();
+        Self {
+            // sortable and short enough to type after --resume; the random
+            // suffix keeps two CLIs started in the same second apart
+            id: format!("{}-{}", now.format("%Y%m%d-%H%M%S"), &uuid::Uuid::new_v4().simple().to_string()[..6]),
+            created_at: now,
+            updated_at: now,
+            history: Vec::new(),
+            hotels: Vec::new(),
+            shortlist: Vec::new(),
+        }
+    }
+
+    /// Adds newly found hotels, skipping ones we already have and dropping the
+    /// oldest ones once the limit is reached.
+    pub fn remember_hotels(&mut self, hotels: &[HotelResult]) {
+        for hotel in hotels {
+            match self.hotels.iter_mut().find(|known| known.id == hotel.id) {
+                // a later search may be for other dates, so keep its prices
+                Some(known) => *known = hotel.clone(),
+                None => self.hotels.push(hotel.clone()),
+            }
+        }
+        if self.hotels.len() > MAX_HOTELS {
+            let excess = self.hotels.len() - MAX_HOTELS;
+            self.hotels.drain(..excess);
+        }
+    }
+
+    /// The first thing the user asked, to tell sessions apart in listings.
+    pub fn title(&self) -> String {
+        self.history
+            .iter()
+            .find_map(|message| match message {
+                Message::User { content } => content.iter().find_map(|c| match c {
+                    UserContent::Text(text) => Some(text.text.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            })
+            .unwrap_or_else(|| "(empty)".to_string())
+    }
+
+    /// The conversation as a Markdown transcript, tool calls and results included.
+    pub fn to_markdown(&self) -> String {
+        let mut output = format!("# Hotel Finder session {}\n\n", self.id);
+        output.push_str(&format!(
+            "Started {}, last updated {}.\n\n",
+            self.created_at.format("%Y-%m-%d %H:%M UTC"),
+            self.updated_at.format("%Y-%m-%d %H:%M UTC")
+        ));
+
+        for message in &self.history {
+            match message {
+                Message::User { content } => {
+                    for content in content.iter() {
+                        match content {
+                            UserContent::Text(text) => output.push_str(&format!("**You:** {}\n\n", text.text)),
+                            UserContent::ToolResult(result) => {
+                                for content in result.content.iter() {
+                                    if let ToolResultContent::Text(text) = content {
+                                        output.push_str(&format!(
+                                            "<details><summary>Tool result</summary>\n\n```json\n{}\n```\n\n</details>\n\n",
+                                            text.text
+                                        ));
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Message::Assistant { content } => {
+                    for content in content.iter() {
+                        if let AssistantContent::Text(text) = content {
+                            output.push_str(&format!("**Assistant:** {}\n\n", text.text));
+                        }
+                        if let AssistantContent::ToolCall(call) = content {
+                            output.push_str(&format!(
+                                "> Called `{}` with `{}`\n\n",
+                                call.function.name, call.function.arguments
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        if !self.shortlist.is_empty() {
+            output.push_str("## Shortlist\n\n");
+            output.push_str(&comparison_table(&self.shortlist));
+            output.push('\n');
+        }
+
+        if !self.hotels.is_empty() {
+            output.push_str("## Hotels found\n\n");
+            for hotel in &self.hotels {
+                output.push_str(&format!("- **{}**", hotel.name));
+                if let Some(rating) = hotel.rating {
+                    output.push_str(&format!(", {}★", rating));
+                }
+                if let Some(neighbourhood) = &hotel.neighbourhood {
+                    output.push_str(&format!(", {}", neighbourhood));
+                }
+                if let Some(price) = &hotel.price {
+                    output.push_str(&format!(", {} per night", price));
+                }
+                if let Some(total) = &hotel.total {
+                    output.push_str(&format!(", {} total", total));
+                }
+                output.push('\n');
+            }
+        }
+        output
+    }
+}
+
+/// Saved sessions, one JSON file each in a directory.
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn save(&self, session: &mut SavedSession) -> io::Result<()> {
+        session.updated_at = Utc::now();
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.path(&session.id), serde_json::to_string_pretty(session)?)
+    }
+
+    pub fn load(&self, id: &str) -> io::Result<SavedSession> {
+        let json = std::fs::read_to_string(self.path(id))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Every readable saved session, most recently updated first.
+    pub fn list(&self) -> io::Result<Vec<SavedSession>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut sessions: Vec<SavedSession> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+            .filter_map(|json| serde_json::from_str(&json).ok())
+            .collect();
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        Ok(sessions)
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        // ids come from the command line, so keep them inside the directory
+        let id: String = id
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        self.dir.join(format!("{}.json", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rig::OneOrMany;
+    use serde_json::json;
+    use crate::model::Price;
+    use super::*;
+
+    fn hotel(id: &str, amount: f64) -> HotelResult {
+        let price = Price { amount, currency: "EUR".to_string() };
+        HotelResult {
+            id: id.to_string(),
+            name: format!("Hotel {}", id),
+            rating: Some(4.5),
+            review_count: Some(120),
+            price: Some(price.clone()),
+            nights: 2,
+            total: Some(Price { amount: amount * 2.0, ..price }),
+            neighbourhood: Some("Alfama".to_string()),
+            ..HotelResult::default()
+        }
+    }
+
+    fn temp_store() -> SessionStore {
+        SessionStore::new(std::env::temp_dir().join(format!("sessions-{}", uuid::Uuid::new_v4())))
+    }
+
+    #[test]
+    fn ids_started_in_the_same_second_differ() {
+        let first = SavedSession::start();
+        let second = SavedSession::start();
+        assert_ne!(first.id, second.id);
+        assert!(first.id.starts_with(&first.created_at.format("%Y%m%d-%H%M%S").to_string()));
+    }
+
+    #[test]
+    fn remember_hotels_updates_known_ones_and_drops_the_oldest() {
+        let mut session = SavedSession::start();
+        session.remember_hotels(&[hotel("1", 100.0), hotel("2", 80.0)]);
+        // other dates, other prices
+        session.remember_hotels(&[hotel("1", 120.0)]);
+        assert_eq!(session.hotels.len(), 2);
+        assert_eq!(session.hotels[0].price.as_ref().unwrap().amount, 120.0);
+
+        let many: Vec<HotelResult> = (3..=MAX_HOTELS + 1).map(|id| hotel(&id.to_string(), 50.0)).collect();
+        session.remember_hotels(&many);
+        assert_eq!(session.hotels.len(), MAX_HOTELS);
+        assert_eq!(session.hotels[0].id, "2");
+        assert_eq!(session.hotels.last().unwrap().id, (MAX_HOTELS + 1).to_string());
+    }
+
+    #[test]
+    fn markdown_shows_messages_tool_calls_and_hotels() {
+        let mut session = SavedSession::start();
+        session.history = vec![
+            Message::user("A hotel in Lisbon"),
+            Message::Assistant {
+                content: OneOrMany::one(AssistantContent::tool_call(
+                    "call_1",
+                    "search_hotel",
+                    json!({ "geoId": 189158 }),
+                )),
+            },
+            Message::User {
+                content: OneOrMany::one(UserContent::tool_result(
+                    "call_1",
+                    OneOrMany::one(ToolResultContent::text("[]")),
+                )),
+            },
+            Message::assistant("Here are some options."),
+        ];
+        session.remember_hotels(&[hotel("1", 100.0)]);
+
+        let markdown = session.to_markdown();
+        assert!(markdown.starts_with(&format!("# Hotel Finder session {}\n", session.id)));
+        assert!(markdown.contains("**You:** A hotel in Lisbon\n"));
+        assert!(markdown.contains("> Called `search_hotel` with `{\"geoId\":189158}`\n"));
+        assert!(markdown.contains("<details><summary>Tool result</summary>\n\n```json\n[]\n```"));
+        assert!(markdown.contains("**Assistant:** Here are some options.\n"));
+        assert!(markdown.contains("- **Hotel 1**, 4.5★, Alfama, 100.00 EUR per night, 200.00 EUR total\n"));
+        assert!(!markdown.contains("## Shortlist"));
+        assert_eq!(session.title(), "A hotel in Lisbon");
+    }
+
+    #[test]
+    fn paths_stay_inside_the_store() {
+        let store = SessionStore::new(PathBuf::from("/data/sessions"));
+        assert_eq!(store.path("../../etc/passwd"), PathBuf::from("/data/sessions/etcpasswd.json"));
+        assert_eq!(store.path("20250101-120000-a1b2c3"), PathBuf::from("/data/sessions/20250101-120000-a1b2c3.json"));
+    }
+
+    #[test]
+    fn sessions_round_trip_through_the_store() {
+        let store = temp_store();
+        assert!(store.list().unwrap().is_empty());
+
+        let mut older = SavedSession::start();
+        older.history.push(Message::user("Rome in May"));
+        store.save(&mut older).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let mut newer = SavedSession::start();
+        newer.history.push(Message::user("Paris next weekend"));
+        newer.remember_hotels(&[hotel("1", 100.0)]);
+        store.save(&mut newer).unwrap();
+        // unreadable files are skipped
+        std::fs::write(store.dir.join("broken.json"), "{").unwrap();
+
+        let loaded = store.load(&newer.id).unwrap();
+        assert_eq!(loaded.title(), "Paris next weekend");
+        assert_eq!(loaded.hotels.len(), 1);
+        assert_eq!(loaded.updated_at, newer.updated_at);
+
+        let titles: Vec<String> = store.list().unwrap().iter().map(SavedSession::title).collect();
+        assert_eq!(titles, ["Paris next weekend", "Rome in May"]);
+        assert!(store.load("missing").is_err());
+        std::fs::remove_dir_all(&store.dir).unwrap();
+    }
+}
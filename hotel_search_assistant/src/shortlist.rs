@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use serde::{Deserialize, Serialize};
+use crate::model::{HotelDetails, HotelResult};
+
+// Amenities listed per hotel in the comparison; the details list can run to dozens
+const MAX_COMPARED_AMENITIES: usize = 8;
+
+/// A hotel the user wants to keep in mind, with what's known about it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortlistedHotel {
+    #[serde(flatten)]
+    pub hotel: HotelResult,
+    pub amenities: Vec<String>,
+    pub address: Option<String>,
+    // why the user liked it, e.g. "the one with the pool"
+    pub note: Option<String>,
+}
+
+#[derive(Default)]
+struct ShortlistState {
+    // hotels seen in searches and details this session, so they can be
+    // shortlisted by id without another API call
+    found: HashMap<String, HotelResult>,
+    details: HashMap<String, HotelDetails>,
+    entries: Vec<ShortlistedHotel>,
+}
+
+/// The shortlist of one session, shared by the tools that fill and read it.
+/// Cheap to clone; clones share the same list.
+#[derive(Clone, Default)]
+pub struct Shortlist {
+    state: Arc<Mutex<ShortlistState>>,
+}
+
+impl Shortlist {
+    /// A shortlist continuing a saved session.
+    pub fn restore(found: &[HotelResult], entries: Vec<ShortlistedHotel>) -> Self {
+        let shortlist = Self::default();
+        shortlist.record_found(found);
+        shortlist.state().entries = entries;
+        shortlist
+    }
+
+    pub fn record_found(&self, hotels: &[HotelResult]) {
+        let mut state = self.state();
+        for hotel in hotels {
+            state.found.insert(hotel.id.clone(), hotel.clone());
+        }
+    }
+
+    /// Keeps the details for shortlisting and fills them in for a hotel that's
+    /// already on the list.
+    pub fn record_details(&self, details: &HotelDetails) {
+        let mut state = self.state();
+        for entry in state.entries.iter_mut().filter(|entry| entry.hotel.id == details.id) {
+            entry.amenities = details.amenities.clone();
+            entry.address = details.address.clone();
+            if entry.hotel.coordinates.is_none() {
+                entry.hotel.coordinates = details.coordinates.clone();
+            }
+        }
+        state.details.insert(details.id.clone(), details.clone());
+    }
+
+    /// Adds a hotel from this session's searches, or updates its note if it's
+    /// already on the list.
+    pub fn add(&self, hotel_id: &str, note: Option<String>) -> Option<ShortlistedHotel> {
+        let mut state = self.state();
+        if let Some(entry) = state.entries.iter_mut().find(|entry| entry.hotel.id == hotel_id) {
+            if note.is_some() {
+                entry.note = note;
+            }
+            return Some(entry.clone());
+        }
+
+        let details = state.details.get(hotel_id);
+        let hotel = match (state.found.get(hotel_id), details) {
+            (Some(hotel), _) => hotel.clone(),
+            (None, Some(details)) => HotelResult {
+                id: details.id.clone(),
+                name: details.name.clone(),
+                rating: details.rating,
+                review_count: details.review_count,
+                coordinates: details.coordinates.clone(),
+                ..HotelResult::default()
+            },
+            (None, None) => return None,
+        };
+        let entry = ShortlistedHotel {
+            amenities: details
+                .map(|details| details.amenities.clone())
+                .unwrap_or_else(|| hotel.features.clone()),
+            address: details.and_then(|details| details.address.clone()),
+            hotel,
+            note,
+        };
+        state.entries.push(entry.clone());
+        Some(entry)
+    }
+
+    pub fn remove(&self, hotel_id: &str) -> Option<ShortlistedHotel> {
+        let mut state = self.state();
+        let index = state.entries.iter().position(|entry| entry.hotel.id == hotel_id)?;
+        Some(state.entries.remove(index))
+    }
+
+    pub fn entries(&self) -> Vec<ShortlistedHotel> {
+        self.state().entries.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, ShortlistState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The hotels side by side as a Markdown table, one column per hotel.
+pub fn comparison_table(entries: &[ShortlistedHotel]) -> String {
+    if entries.is_empty() {
+        return "The shortlist is empty.\n".to_string();
+    }
+
+    let mut rows: Vec<(&str, Vec<String>)> = vec![
+        ("Price per night", Vec::new()),
+        ("Total", Vec::new()),
+        ("Rating", Vec::new()),
+        ("Location", Vec::new()),
+        ("Amenities", Vec::new()),
+        ("Note", Vec::new()),
+    ];
+    for entry in entries {
+        let hotel = &entry.hotel;
+        let cells = [
+            hotel.price.as_ref().map(|price| price.to_string()),
+            hotel.total.as_ref().map(|total| {
+                format!(
+                    "{} ({} {}, {} {})",
+                    total,
+                    hotel.nights,
+                    if hotel.nights == 1 { "night" } else { "nights" },
+                    hotel.rooms,
+                    if hotel.rooms == 1 { "room" } else { "rooms" }
+                )
+            }),
+            hotel.rating.map(|rating| match hotel.review_count {
+                Some(count) => format!("{}★ ({} reviews)", rating, count),
+                None => format!("{}★", rating),
+            }),
+            entry.address.clone().or_else(|| hotel.neighbourhood.clone()),
+            (!entry.amenities.is_empty()).then(|| {
+                let mut amenities = entry.amenities[..entry.amenities.len().min(MAX_COMPARED_AMENITIES)].join(", ");
+                if entry.amenities.len() > MAX_COMPARED_AMENITIES {
+                    amenities.push_str(", …");
+                }
+                amenities
+            }),
+            entry.note.clone(),
+        ];
+        for ((_, row), cell) in rows.iter_mut().zip(cells) {
+            row.push(cell.map_or("–".to_string(), |cell| escape_cell(&cell)));
+        }
+    }
+
+    let names: Vec<String> = entries.iter().map(|entry| escape_cell(&entry.hotel.name)).collect();
+    let mut table = format!("| | {} |\n", names.join(" | "));
+    table.push_str(&format!("|---|{}\n", "---|".repeat(entries.len())));
+    for (label, cells) in rows {
+        table.push_str(&format!("| {} | {} |\n", label, cells.join(" | ")));
+    }
+    table
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Coordinates, Price};
+    use super::*;
+
+    fn hotel(id: &str, name: &str) -> HotelResult {
+        HotelResult {
+            id: id.to_string(),
+            name: name.to_string(),
+            rating: Some(4.5),
+            review_count: Some(1200),
+            price: Some(Price { amount: 150.0, currency: "EUR".to_string() }),
+            total: Some(Price { amount: 150.0, currency: "EUR".to_string() }),
+            features: vec!["Pool".to_string()],
+            neighbourhood: Some("Chiado".to_string()),
+            ..HotelResult::default()
+        }
+    }
+
+    fn details(id: &str, amenities: &[&str]) -> HotelDetails {
+        HotelDetails {
+            id: id.to_string(),
+            name: format!("Hotel {}", id),
+            rating: Some(4.0),
+            review_count: Some(300),
+            ranking: None,
+            description: String::new(),
+            amenities: amenities.iter().map(|amenity| amenity.to_string()).collect(),
+            address: Some("Rua Garrett 1, Lisbon".to_string()),
+            coordinates: Some(Coordinates { latitude: 38.71, longitude: -9.14 }),
+            photos: Vec::new(),
+            category_ratings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn add_takes_hotels_from_searches_and_details() {
+        let shortlist = Shortlist::default();
+        shortlist.record_found(&[hotel("1", "Pool Hotel")]);
+        shortlist.record_details(&details("2", &["Spa"]));
+
+        let found = shortlist.add("1", Some("the one with the pool".to_string())).unwrap();
+        assert_eq!(found.amenities, ["Pool"]);
+        assert_eq!(found.note.as_deref(), Some("the one with the pool"));
+        let detailed = shortlist.add("2", None).unwrap();
+        assert_eq!(detailed.hotel.name, "Hotel 2");
+        assert!(detailed.hotel.price.is_none());
+        assert_eq!(detailed.amenities, ["Spa"]);
+        assert_eq!(detailed.address.as_deref(), Some("Rua Garrett 1, Lisbon"));
+        assert!(shortlist.add("3", None).is_none());
+
+        // adding again keeps the entry, and its note unless a new one is given
+        shortlist.add("1", None).unwrap();
+        assert_eq!(shortlist.entries().len(), 2);
+        assert_eq!(shortlist.entries()[0].note.as_deref(), Some("the one with the pool"));
+        shortlist.add("1", Some("near the river".to_string())).unwrap();
+        assert_eq!(shortlist.entries()[0].note.as_deref(), Some("near the river"));
+    }
+
+    #[test]
+    fn record_details_fills_in_shortlisted_hotels() {
+        let shortlist = Shortlist::default();
+        shortlist.record_found(&[hotel("1", "Pool Hotel")]);
+        shortlist.add("1", None).unwrap();
+
+        shortlist.record_details(&details("1", &["Pool", "Free WiFi"]));
+
+        let entry = &shortlist.entries()[0];
+        assert_eq!(entry.amenities, ["Pool", "Free WiFi"]);
+        assert_eq!(entry.address.as_deref(), Some("Rua Garrett 1, Lisbon"));
+        assert_eq!(entry.hotel.coordinates.as_ref().unwrap().latitude, 38.71);
+        // the search result's price is kept
+        assert_eq!(entry.hotel.price.as_ref().unwrap().amount, 150.0);
+    }
+
+    #[test]
+    fn restore_continues_a_saved_shortlist() {
+        let saved = Shortlist::default();
+        saved.record_found(&[hotel("1", "Pool Hotel")]);
+        saved.add("1", Some("favourite".to_string())).unwrap();
+
+        let shortlist = Shortlist::restore(&[hotel("2", "River Hotel")], saved.entries());
+        assert_eq!(shortlist.entries()[0].note.as_deref(), Some("favourite"));
+        // hotels found in the saved session can still be added
+        assert!(shortlist.add("2", None).is_some());
+        assert!(shortlist.remove("1").is_some());
+        assert_eq!(shortlist.entries()[0].hotel.id, "2");
+    }
+
+    #[test]
+    fn table_has_one_column_per_hotel() {
+        let mut first = ShortlistedHotel {
+            hotel: hotel("1", "Pool | Spa Hotel"),
+            amenities: (1..=10).map(|i| format!("Amenity {}", i)).collect(),
+            address: None,
+            note: Some("quiet\nand central".to_string()),
+        };
+        first.hotel.nights = 3;
+        first.hotel.rooms = 2;
+        let second = ShortlistedHotel {
+            hotel: HotelResult { price: None, total: None, rating: None, ..hotel("2", "River Hotel") },
+            amenities: Vec::new(),
+            address: Some("Cais do Sodré".to_string()),
+            note: None,
+        };
+
+        let table = comparison_table(&[first, second]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "| | Pool \\| Spa Hotel | River Hotel |");
+        assert_eq!(lines[1], "|---|---|---|");
+        assert_eq!(lines[2], "| Price per night | 150.00 EUR | – |");
+        assert_eq!(lines[3], "| Total | 150.00 EUR (3 nights, 2 rooms) | – |");
+        assert_eq!(lines[4], "| Rating | 4.5★ (1200 reviews) | – |");
+        assert_eq!(lines[5], "| Location | Chiado | Cais do Sodré |");
+        assert_eq!(
+            lines[6],
+            "| Amenities | Amenity 1, Amenity 2, Amenity 3, Amenity 4, Amenity 5, Amenity 6, Amenity 7, Amenity 8, … | – |"
+        );
+        assert_eq!(lines[7], "| Note | quiet and central | – |");
+        assert_eq!(lines.len(), 8);
+    }
+
+    #[test]
+    fn totals_for_one_night_and_room_are_singular() {
+        let entry = ShortlistedHotel {
+            hotel: hotel("1", "Pool Hotel"),
+            amenities: Vec::new(),
+            address: None,
+            note: None,
+        };
+        assert!(comparison_table(&[entry]).contains("| Total | 150.00 EUR (1 night, 1 room) |"));
+        assert_eq!(comparison_table(&[]), "The shortlist is empty.\n");
+    }
+}
@@ -0,0 +1,122 @@
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde_json::json;
+use crate::tool_error::HotelSearchError;
+use crate::model::{ShortlistAddArgs, ShortlistCompareArgs, ShortlistRemoveArgs};
+use crate::shortlist::{comparison_table, Shortlist, ShortlistedHotel};
+
+pub struct ShortlistAddTool {
+    pub shortlist: Shortlist,
+}
+
+impl Tool for ShortlistAddTool {
+    const NAME: &'static str = "shortlist_add";
+    type Error = HotelSearchError;
+    type Args = ShortlistAddArgs;
+    type Output = ShortlistedHotel;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Adds a hotel from this conversation's searches to the user's shortlist, \
+                with a note on why they liked it. Adding a hotel that's already on it updates the note.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "hotelId": {
+                        "type": "string",
+                        "description": "The hotel's id, as returned by search_hotel."
+                    },
+                    "note": {
+                        "type": "string",
+                        "description": "What the user liked about it in their words, e.g. \"the one with the pool\"."
+                    }
+                },
+                "required": ["hotelId"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        tracing::debug!(hotel_id = %args.hotel_id, "adding to the shortlist");
+        self.shortlist.add(&args.hotel_id, args.note).ok_or_else(|| {
+            HotelSearchError::invalid_argument(
+                "hotelId",
+                format!("hotel {} wasn't found in this conversation; search for it first", args.hotel_id),
+            )
+        })
+    }
+}
+
+pub struct ShortlistRemoveTool {
+    pub shortlist: Shortlist,
+}
+
+impl Tool for ShortlistRemoveTool {
+    const NAME: &'static str = "shortlist_remove";
+    type Error = HotelSearchError;
+    type Args = ShortlistRemoveArgs;
+    type Output = ShortlistedHotel;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Removes a hotel from the user's shortlist and returns it.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "hotelId": {
+                        "type": "string",
+                        "description": "The id of the shortlisted hotel. Use shortlist_compare to see the shortlist if unsure."
+                    }
+                },
+                "required": ["hotelId"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        tracing::debug!(hotel_id = %args.hotel_id, "removing from the shortlist");
+        self.shortlist.remove(&args.hotel_id).ok_or_else(|| {
+            HotelSearchError::invalid_argument("hotelId", format!("hotel {} is not on the shortlist", args.hotel_id))
+        })
+    }
+}
+
+pub struct ShortlistCompareTool {
+    pub shortlist: Shortlist,
+}
+
+impl Tool for ShortlistCompareTool {
+    const NAME: &'static str = "shortlist_compare";
+    type Error = HotelSearchError;
+    type Args = ShortlistCompareArgs;
+    type Output = String;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Returns the shortlisted hotels side by side as a Markdown table of price per night, \
+                total, rating, location, amenities and the user's note, one column per hotel. \
+                Also use it to find a shortlisted hotel the user describes (\"the one with the pool\").".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "hotelIds": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Only compare these shortlisted hotels. Leave out to compare the whole shortlist."
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let mut entries = self.shortlist.entries();
+        if let Some(ids) = args.hotel_ids.filter(|ids| !ids.is_empty()) {
+            entries.retain(|entry| ids.contains(&entry.hotel.id));
+        }
+        Ok(comparison_table(&entries))
+    }
+}
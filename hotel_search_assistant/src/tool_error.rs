@@ -0,0 +1,314 @@
+use rig::completion::ToolDefinition;
+use rig::tool::Tool;
+use serde::Serialize;
+use tripadvisor_client::TripadvisorError;
+use crate::dates::DateError;
+
+/// Why a tool call failed, in terms the agent can act on.
+///
+/// Failures reach the model as the tool's result (see [`Recoverable`]), so
+/// each variant says what went wrong and `next_step` what to do about it.
+#[derive(Debug, thiserror::Error, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HotelSearchError {
+    #[error("Invalid {field}: {reason}")]
+    InvalidArgument { field: String, reason: String },
+    #[error("No location found for \"{query}\"")]
+    LocationNotFound { query: String },
+    #[error("The Tripadvisor API quota is used up")]
+    QuotaExceeded {
+        // when the API said the quota resets
+        retry_after_secs: Option<u64>,
+    },
+    #[error("The Tripadvisor API is rate limiting requests")]
+    RateLimited {
+        // when the API said it can be retried
+        retry_after_secs: Option<u64>,
+    },
+    #[error("Tripadvisor request failed{}: {message}", .status.map(|s| format!(" with HTTP {}", s)).unwrap_or_default())]
+    Upstream { status: Option<u16>, message: String },
+}
+
+impl HotelSearchError {
+    pub fn invalid_argument(field: &str, reason: impl Into<String>) -> Self {
+        HotelSearchError::InvalidArgument {
+            field: field.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Arguments that don't match the tool's parameters, attributed to the
+    /// field at fault.
+    pub fn malformed_arguments(error: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let reason = error.inner().to_string();
+        let field = match error.path().to_string() {
+            // missing fields are reported on the object that lacks them
+            path if path == "." => reason
+                .strip_prefix("missing field `")
+                .and_then(|rest| rest.split('`').next())
+                .unwrap_or("arguments")
+                .to_string(),
+            path => path,
+        };
+        Self::invalid_argument(&field, reason)
+    }
+
+    /// A stay that couldn't be parsed or isn't valid, attributed to the
+    /// argument the user should correct.
+    pub fn invalid_stay(error: DateError, check_out: Option<&str>) -> Self {
+        let field = match &error {
+            DateError::Unrecognised(text) if Some(text.as_str()) == check_out => "checkOut",
+            DateError::NoNights { .. } | DateError::TooLong(_) => "checkOut",
+            _ => "checkIn",
+        };
+        Self::invalid_argument(field, error.to_string())
+    }
+
+    /// What the agent should do next.
+    pub fn next_step(&self) -> String {
+        match self {
+            HotelSearchError::InvalidArgument { field, .. } if field == "checkIn" || field == "checkOut" => {
+                "Explain the problem with the dates and ask the user for valid stay dates.".to_string()
+            }
+            HotelSearchError::InvalidArgument { field, .. } => {
+                format!("Correct {} and call the tool again, or ask the user if it's unclear what they meant.", field)
+            }
+            HotelSearchError::LocationNotFound { .. } => {
+                "Ask the user to check the spelling or name a nearby city.".to_string()
+            }
+            HotelSearchError::QuotaExceeded { .. } => {
+                "Don't retry; tell the user the search service is out of requests for now.".to_string()
+            }
+            HotelSearchError::RateLimited { .. } => {
+                "Retry once; if it is still limited, tell the user the search service is busy and to try again in a minute.".to_string()
+            }
+            HotelSearchError::Upstream { status: Some(status), .. } if *status < 500 => {
+                "Don't retry with the same arguments; tell the user the search failed.".to_string()
+            }
+            HotelSearchError::Upstream { .. } => {
+                "Retry once; if it fails again, tell the user the search service is unavailable.".to_string()
+            }
+        }
+    }
+}
+
+impl From<TripadvisorError> for HotelSearchError {
+    fn from(error: TripadvisorError) -> Self {
+        let message = error.to_string();
+        match error {
+            TripadvisorError::QuotaExceeded { reset_in } => HotelSearchError::QuotaExceeded {
+                retry_after_secs: reset_in.map(|wait| wait.as_secs()),
+            },
+            // transient, unlike an exhausted quota
+            TripadvisorError::RateLimited { retry_after } => HotelSearchError::RateLimited {
+                retry_after_secs: retry_after.map(|wait| wait.as_secs()),
+            },
+            TripadvisorError::Status { status, .. } => HotelSearchError::Upstream {
+                status: Some(status.as_u16()),
+                message,
+            },
+            TripadvisorError::Http(e) => HotelSearchError::Upstream {
+                status: e.status().map(|status| status.as_u16()),
+                message,
+            },
+            _ => HotelSearchError::Upstream { status: None, message },
+        }
+    }
+}
+
+/// A tool's result, or why it failed.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum ToolOutcome<T> {
+    Done(T),
+    Failed {
+        error: HotelSearchError,
+        message: String,
+        next_step: String,
+    },
+}
+
+/// Wraps a tool so that its errors are returned to the model as the tool's
+/// result instead of failing the whole turn. The model can then recover, e.g.
+/// by asking the user for other dates.
+///
+/// The arguments are taken as JSON and deserialized here rather than by rig,
+/// so arguments that don't fit the tool are reported the same way.
+pub struct Recoverable<T>(pub T);
+
+impl<T> Tool for Recoverable<T>
+where
+    T: Tool<Error = HotelSearchError>,
+{
+    const NAME: &'static str = T::NAME;
+    // never returned, failures are part of the output
+    type Error = HotelSearchError;
+    type Args = serde_json::Value;
+    type Output = ToolOutcome<T::Output>;
+
+    async fn definition(&self, prompt: String) -> ToolDefinition {
+        self.0.definition(prompt).await
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let result = match serde_path_to_error::deserialize(args) {
+            Ok(args) => self.0.call(args).await,
+            Err(error) => Err(HotelSearchError::malformed_arguments(error)),
+        };
+        match result {
+            Ok(output) => Ok(ToolOutcome::Done(output)),
+            Err(error) => Ok(ToolOutcome::Failed {
+                message: error.to_string(),
+                next_step: error.next_step(),
+                error,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use chrono::NaiveDate;
+    use reqwest::StatusCode;
+    use serde::Deserialize;
+    use serde_json::json;
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct StayArgs {
+        #[serde(rename = "checkIn")]
+        check_in: String,
+        rooms: Option<u32>,
+        #[serde(rename = "childrenAges")]
+        children_ages: Option<Vec<u32>>,
+    }
+
+    // fails for stays starting "yesterday", otherwise returns the rooms asked for
+    struct StayTool;
+
+    impl Tool for StayTool {
+        const NAME: &'static str = "stay";
+        type Error = HotelSearchError;
+        type Args = StayArgs;
+        type Output = u32;
+
+        async fn definition(&self, _prompt: String) -> ToolDefinition {
+            ToolDefinition {
+                name: Self::NAME.to_string(),
+                description: String::new(),
+                parameters: json!({}),
+            }
+        }
+
+        async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+            if args.check_in == "yesterday" {
+                return Err(HotelSearchError::invalid_argument("checkIn", "is in the past"));
+            }
+            Ok(args.rooms.unwrap_or(1) + args.children_ages.map_or(0, |ages| ages.len() as u32))
+        }
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        text.parse().unwrap()
+    }
+
+    async fn call(args: serde_json::Value) -> serde_json::Value {
+        serde_json::to_value(Recoverable(StayTool).call(args).await.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn failures_serialize_with_kind_field_and_next_step() {
+        let error = HotelSearchError::invalid_argument("rooms", "must be between 1 and 10, got 12");
+        let outcome: ToolOutcome<Vec<u32>> = ToolOutcome::Failed {
+            message: error.to_string(),
+            next_step: error.next_step(),
+            error,
+        };
+        assert_eq!(
+            serde_json::to_value(&outcome).unwrap(),
+            json!({
+                "error": { "kind": "invalid_argument", "field": "rooms", "reason": "must be between 1 and 10, got 12" },
+                "message": "Invalid rooms: must be between 1 and 10, got 12",
+                "next_step": "Correct rooms and call the tool again, or ask the user if it's unclear what they meant."
+            })
+        );
+        // results are passed through as they are
+        assert_eq!(serde_json::to_value(ToolOutcome::Done(vec![1, 2])).unwrap(), json!([1, 2]));
+
+        let quota = serde_json::to_value(HotelSearchError::QuotaExceeded { retry_after_secs: Some(3600) }).unwrap();
+        assert_eq!(quota, json!({ "kind": "quota_exceeded", "retry_after_secs": 3600 }));
+    }
+
+    #[test]
+    fn stay_errors_name_the_argument_to_correct() {
+        let field = |error: DateError, check_out: Option<&str>| match HotelSearchError::invalid_stay(error, check_out) {
+            HotelSearchError::InvalidArgument { field, .. } => field,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(field(DateError::Unrecognised("the 45th".to_string()), Some("the 45th")), "checkOut");
+        assert_eq!(field(DateError::Unrecognised("someday".to_string()), Some("Friday")), "checkIn");
+        assert_eq!(field(DateError::Unrecognised("someday".to_string()), None), "checkIn");
+        assert_eq!(field(DateError::InPast(date("2024-01-01")), None), "checkIn");
+        let no_nights = DateError::NoNights { check_in: date("2025-08-10"), check_out: date("2025-08-08") };
+        assert_eq!(field(no_nights, Some("Aug 8")), "checkOut");
+        assert_eq!(field(DateError::TooLong(45), None), "checkOut");
+    }
+
+    #[test]
+    fn quota_and_rate_limits_stay_apart() {
+        let quota = HotelSearchError::from(TripadvisorError::QuotaExceeded { reset_in: Some(Duration::from_secs(3600)) });
+        assert!(matches!(quota, HotelSearchError::QuotaExceeded { retry_after_secs: Some(3600) }));
+        assert!(quota.next_step().starts_with("Don't retry"));
+
+        let limited = HotelSearchError::from(TripadvisorError::RateLimited { retry_after: Some(Duration::from_secs(2)) });
+        assert!(matches!(limited, HotelSearchError::RateLimited { retry_after_secs: Some(2) }));
+        assert!(limited.next_step().starts_with("Retry once"));
+        let limited = HotelSearchError::from(TripadvisorError::RateLimited { retry_after: None });
+        assert!(matches!(limited, HotelSearchError::RateLimited { retry_after_secs: None }));
+    }
+
+    #[test]
+    fn upstream_errors_keep_the_status() {
+        let not_found = HotelSearchError::from(TripadvisorError::Status {
+            status: StatusCode::NOT_FOUND,
+            message: "Unknown geoId".to_string(),
+        });
+        assert!(matches!(&not_found, HotelSearchError::Upstream { status: Some(404), .. }));
+        assert_eq!(not_found.to_string(), "Tripadvisor request failed with HTTP 404: API returned HTTP 404 Not Found: Unknown geoId");
+        assert!(not_found.next_step().starts_with("Don't retry"));
+
+        let unavailable = HotelSearchError::from(TripadvisorError::Status {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            message: String::new(),
+        });
+        assert!(unavailable.next_step().starts_with("Retry once"));
+
+        let api = HotelSearchError::from(TripadvisorError::Api("Something went wrong".to_string()));
+        assert!(matches!(&api, HotelSearchError::Upstream { status: None, .. }));
+        assert_eq!(api.to_string(), "Tripadvisor request failed: API error: Something went wrong");
+    }
+
+    #[tokio::test]
+    async fn malformed_arguments_are_returned_to_the_model() {
+        assert_eq!(call(json!({ "checkIn": "tomorrow", "rooms": 2 })).await, json!(2));
+
+        let missing = call(json!({ "rooms": 2 })).await;
+        assert_eq!(missing["error"], json!({ "kind": "invalid_argument", "field": "checkIn", "reason": "missing field `checkIn`" }));
+        assert!(missing["next_step"].as_str().unwrap().contains("valid stay dates"));
+
+        let wrong_type = call(json!({ "checkIn": "tomorrow", "rooms": "two" })).await;
+        assert_eq!(wrong_type["error"]["field"], "rooms");
+        assert!(wrong_type["error"]["reason"].as_str().unwrap().starts_with("invalid type: string \"two\""));
+
+        let nested = call(json!({ "checkIn": "tomorrow", "childrenAges": [4, "six"] })).await;
+        assert_eq!(nested["error"]["field"], "childrenAges[1]");
+
+        let not_an_object = call(json!("tomorrow")).await;
+        assert_eq!(not_an_object["error"]["field"], "arguments");
+
+        // the tool's own failures come back the same way
+        let failed = call(json!({ "checkIn": "yesterday" })).await;
+        assert_eq!(failed["message"], "Invalid checkIn: is in the past");
+    }
+}
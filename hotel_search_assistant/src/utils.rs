@@ -1,41 +1,147 @@
-use chrono::NaiveDate;
-use chrono::Datelike;
-use crate::hotel_search_tool::HotelSearchError;
-
-/// Parses a date string and infers the year.
-/// If the year is missing, it assumes the *next upcoming* occurrence of that month/day.
-pub fn parse_and_infer_year(date_str: &str, today: NaiveDate) -> Result<NaiveDate, HotelSearchError> {
-    // Try parsing with year first (YYYY-MM-DD)
-    if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-        return Ok(date);
+use crate::model::Price;
+
+/// Parses a review count as shown by Tripadvisor, e.g. "1,037".
+pub fn parse_review_count(count: &str) -> Option<u32> {
+    count.trim().replace(',', "").parse().ok()
+}
+
+/// Hotel titles come numbered by search position, e.g. "1. Abode Bombay".
+pub fn strip_list_number(title: &str) -> String {
+    match title.split_once(". ") {
+        Some((number, name)) if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) => {
+            name.to_string()
+        }
+        _ => title.to_string(),
     }
-    // Try parsing without year (MM-DD)
-    if let Ok(mut date) = NaiveDate::parse_from_str(date_str, "%m-%d") {
-        // Assume current year initially
-        date = date.with_year(today.year()).unwrap();
-
-        // If the parsed date (in current year) has already passed, use next year
-        if date < today {
-            date = date.with_year(today.year() + 1).unwrap();
+}
+
+/// Removes markup such as the `<b>` highlighting in location titles.
+pub fn strip_tags(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => output.push(c),
+            _ => {}
         }
-        return Ok(date);
     }
-    // Try parsing common month-day formats (e.g., "August 8th")
-    if let Ok(mut date) = NaiveDate::parse_from_str(date_str, "%B %e") {
-        date = date.with_year(today.year()).unwrap();
-        if date < today {
-            date = date.with_year(today.year() + 1).unwrap();
+    output
+}
+
+/// Parses a display price such as "$120", "US$1,234", "€95" or "₹ 5,000" into an
+/// amount and currency. For ranges like "$100 - $200" the first price is used.
+pub fn parse_price(price: &str) -> Option<Price> {
+    let start = price.find(|c: char| c.is_ascii_digit())?;
+    let len = price[start..]
+        .find(|c: char| !(c.is_ascii_digit() || c == ',' || c == '.'))
+        .unwrap_or(price.len() - start);
+    let amount = parse_amount(&price[start..start + len])?;
+
+    // the currency is written before the amount, or after it when there's no prefix
+    let prefix = price[..start].trim();
+    let symbol = if prefix.is_empty() {
+        price[start + len..].split_whitespace().next().unwrap_or("")
+    } else {
+        prefix
+    };
+    Some(Price {
+        amount,
+        currency: currency_code(symbol),
+    })
+}
+
+// Both "1,234.56" and "1.234,56" occur depending on the locale. The last
+// separator is the decimal point when one or two digits follow it; every
+// other separator groups thousands.
+fn parse_amount(number: &str) -> Option<f64> {
+    let number = number.trim_end_matches([',', '.']);
+    let digits: String = match number.rfind([',', '.']) {
+        Some(i) if (2..=3).contains(&(number.len() - i)) => {
+            let (whole, fraction) = number.split_at(i);
+            format!("{}.{}", whole.replace([',', '.'], ""), &fraction[1..])
+        }
+        _ => number.replace([',', '.'], ""),
+    };
+    digits.parse().ok()
+}
+
+fn currency_code(symbol: &str) -> String {
+    let code = match symbol {
+        "$" | "US$" => "USD",
+        "€" => "EUR",
+        "£" => "GBP",
+        "₹" | "Rs." | "Rs" => "INR",
+        "¥" => "JPY",
+        "C$" | "CA$" => "CAD",
+        "A$" | "AU$" => "AUD",
+        // ISO codes are passed through, unknown symbols kept as they are
+        other => return other.to_string(),
+    };
+    code.to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_display_prices() {
+        let cases = [
+            ("$123", 123.0, "USD"),
+            ("US$99", 99.0, "USD"),
+            ("€1,234", 1234.0, "EUR"),
+            ("1.234,56 €", 1234.56, "EUR"),
+            ("€1.234", 1234.0, "EUR"),
+            ("£95.50", 95.5, "GBP"),
+            ("₹ 5,000", 5000.0, "INR"),
+            ("Rs. 12,500", 12500.0, "INR"),
+            ("C$1,234.5", 1234.5, "CAD"),
+            ("5,000 INR", 5000.0, "INR"),
+            ("12,5 CHF", 12.5, "CHF"),
+            ("$1,234,567", 1234567.0, "USD"),
+            ("$100 - $200", 100.0, "USD"),
+            ("$120.", 120.0, "USD"),
+            ("120", 120.0, ""),
+        ];
+        for (text, amount, currency) in cases {
+            let price = parse_price(text).unwrap_or_else(|| panic!("{text:?} didn't parse"));
+            assert_eq!(price.amount, amount, "{text:?}");
+            assert_eq!(price.currency, currency, "{text:?}");
         }
-        return Ok(date);
     }
-    // Add more `parse_from_str` patterns if you expect other formats like "8th August"
-    if let Ok(mut date) = NaiveDate::parse_from_str(date_str, "%e %B") {
-        date = date.with_year(today.year()).unwrap();
-        if date < today {
-            date = date.with_year(today.year() + 1).unwrap();
+
+    #[test]
+    fn missing_prices_are_none() {
+        for text in ["", "   ", "$", "Price unavailable", "€ --"] {
+            assert!(parse_price(text).is_none(), "{text:?}");
         }
-        return Ok(date);
     }
-    // If all attempts fail
-    Err(HotelSearchError::InvalidResponse(format!("Could not parse date: {}", date_str)))
-}
\ No newline at end of file
+
+    #[test]
+    fn parses_review_counts() {
+        assert_eq!(parse_review_count("1,037"), Some(1037));
+        assert_eq!(parse_review_count(" 12 "), Some(12));
+        assert_eq!(parse_review_count("1,234,567"), Some(1_234_567));
+        assert_eq!(parse_review_count(""), None);
+        assert_eq!(parse_review_count("no reviews"), None);
+    }
+
+    #[test]
+    fn strips_list_numbers_only_when_there_is_a_number() {
+        assert_eq!(strip_list_number("1. Abode Bombay"), "Abode Bombay");
+        assert_eq!(strip_list_number("12. Hotel Le Six"), "Hotel Le Six");
+        assert_eq!(strip_list_number(". x"), ". x");
+        assert_eq!(strip_list_number("St. Regis Mumbai"), "St. Regis Mumbai");
+        assert_eq!(strip_list_number("Hotel 1. Stock"), "Hotel 1. Stock");
+        assert_eq!(strip_list_number("Abode Bombay"), "Abode Bombay");
+    }
+
+    #[test]
+    fn strips_tags() {
+        assert_eq!(strip_tags("<b>Paris</b>, Ile-de-France"), "Paris, Ile-de-France");
+        assert_eq!(strip_tags("no markup"), "no markup");
+    }
+}
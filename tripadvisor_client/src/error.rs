@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TripadvisorError {
+    #[error("Missing API key, set RAPIDAPI_KEY")]
+    MissingApiKey,
+    #[error("The API key contains characters that can't be sent in a header")]
+    InvalidApiKey,
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Rate limited by the API, still limited after retrying (retry after {retry_after:?})")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("API quota exceeded, it resets in {reset_in:?}")]
+    QuotaExceeded { reset_in: Option<Duration> },
+    #[error("API returned HTTP {status}: {message}")]
+    Status { status: StatusCode, message: String },
+    #[error("API error: {0}")]
+    Api(String),
+    #[error("Invalid response: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
+    #[error("Fixture {0} could not be read or written: {1}")]
+    Fixture(PathBuf, std::io::Error),
+}
@@ -0,0 +1,257 @@
+//! Client for the Tripadvisor API on RapidAPI, shared by the hotel and flight
+//! assistants.
+//!
+//! Every call goes through [`TripadvisorClient::get`], which sends the RapidAPI
+//! headers, unwraps the `{status, message, data}` envelope, backs off when the
+//! API answers 429 and records the `x-ratelimit-*` headers so callers can see
+//! how much of the quota is left.
+//!
+//! In record mode every exchange is also written to a fixture file, and in
+//! replay mode responses are served from those files, so the assistants can
+//! run offline and without spending quota.
+
+mod error;
+mod recording;
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use reqwest::{Request, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub use error::TripadvisorError;
+pub use recording::Mode;
+
+pub const DEFAULT_BASE_URL: &str = "https://tripadvisor16.p.rapidapi.com";
+const RAPIDAPI_HOST: &str = "tripadvisor16.p.rapidapi.com";
+const DEFAULT_FIXTURE_DIR: &str = "fixtures/tripadvisor";
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+// first back-off when the API doesn't say how long to wait; doubled on every retry
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+// waits longer than this mean the plan's quota is used up, not a burst limit
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Request counts reported by RapidAPI, updated after every response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuotaUsage {
+    /// Requests sent by this client, retries included.
+    pub requests_made: u64,
+    /// Requests allowed in the current quota period.
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// Time until the quota period resets.
+    pub reset_in: Option<Duration>,
+}
+
+// the response envelope used by every endpoint
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    status: bool,
+    #[serde(default)]
+    message: String,
+    data: Option<T>,
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+/// Cheap to clone; clones share the HTTP connection pool and quota counters.
+#[derive(Debug, Clone)]
+pub struct TripadvisorClient {
+    http: reqwest::Client,
+    base_url: String,
+    headers: HeaderMap,
+    api_key: String,
+    max_retries: u32,
+    mode: Mode,
+    quota: Arc<Mutex<QuotaUsage>>,
+}
+
+impl TripadvisorClient {
+    pub fn new(api_key: &str) -> Result<Self, TripadvisorError> {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-rapidapi-host", HeaderValue::from_static(RAPIDAPI_HOST));
+        headers.insert(
+            "x-rapidapi-key",
+            HeaderValue::from_str(api_key).map_err(|_| TripadvisorError::InvalidApiKey)?,
+        );
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            headers,
+            api_key: api_key.to_string(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            mode: Mode::Live,
+            quota: Arc::default(),
+        })
+    }
+
+    /// Reads the key from `RAPIDAPI_KEY`. `TRIPADVISOR_BASE_URL` overrides the
+    /// base URL, e.g. to point at a local mock server.
+    ///
+    /// `TRIPADVISOR_MODE=record` or `replay` switches on recording or replay, with
+    /// fixtures in `TRIPADVISOR_FIXTURES` (default `fixtures/tripadvisor`). Replay
+    /// doesn't need an API key.
+    pub fn from_env() -> Result<Self, TripadvisorError> {
+        let fixtures = std::env::var("TRIPADVISOR_FIXTURES")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_FIXTURE_DIR));
+        let mode = match std::env::var("TRIPADVISOR_MODE")
+            .as_deref()
+            .map(str::to_lowercase)
+            .as_deref()
+        {
+            Ok("record") => Mode::Record(fixtures),
+            Ok("replay") => Mode::Replay(fixtures),
+            _ => Mode::Live,
+        };
+
+        let api_key = match std::env::var("RAPIDAPI_KEY") {
+            Ok(api_key) => api_key,
+            Err(_) if matches!(mode, Mode::Replay(_)) => String::new(),
+            Err(_) => return Err(TripadvisorError::MissingApiKey),
+        };
+        let client = Self::new(&api_key)?.with_mode(mode);
+        Ok(match std::env::var("TRIPADVISOR_BASE_URL") {
+            Ok(base_url) => client.with_base_url(base_url),
+            Err(_) => client,
+        })
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// How often a rate-limited request is retried before giving up.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    pub fn quota(&self) -> QuotaUsage {
+        self.quota.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Calls an endpoint, e.g. `/api/v1/hotels/searchHotels`, and returns the
+    /// `data` field of the response.
+    pub async fn get<T, Q>(&self, path: &str, query: &Q) -> Result<T, TripadvisorError>
+    where
+        T: DeserializeOwned,
+        Q: Serialize + ?Sized,
+    {
+        let request = self
+            .http
+            .get(format!("{}{}", self.base_url, path))
+            .headers(self.headers.clone())
+            .query(query)
+            .build()?;
+
+        let (status, text) = match &self.mode {
+            Mode::Replay(dir) => {
+                recording::replay(dir, path, &recording::normalise_query(request.url()))?
+            }
+            Mode::Live => self.send(request).await?,
+            Mode::Record(dir) => {
+                let query = recording::normalise_query(request.url());
+                let (status, text) = self.send(request).await?;
+                recording::record(dir, path, query, status, &text, &self.api_key)?;
+                (status, text)
+            }
+        };
+
+        if !status.is_success() {
+            let message = serde_json::from_str::<ApiError>(&text)
+                .map(|error| error.message)
+                .unwrap_or(text);
+            return Err(TripadvisorError::Status { status, message });
+        }
+
+        let envelope: ApiResponse<T> = serde_json::from_str(&text)?;
+        match envelope {
+            ApiResponse {
+                status: true,
+                data: Some(data),
+                ..
+            } => Ok(data),
+            ApiResponse { message, .. } => Err(TripadvisorError::Api(message)),
+        }
+    }
+
+    // sends the request, retrying while the API answers 429
+    async fn send(&self, request: Request) -> Result<(StatusCode, String), TripadvisorError> {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 0;
+
+        let response = loop {
+            // GET requests have no body, so they can always be cloned
+            let retry = request.try_clone().expect("GET requests can be cloned");
+            let response = self.http.execute(retry).await?;
+            self.record_quota(&response);
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                break response;
+            }
+            let wait = retry_after(&response);
+            if wait.is_some_and(|wait| wait > MAX_BACKOFF) {
+                return Err(TripadvisorError::QuotaExceeded { reset_in: wait });
+            }
+            if attempt >= self.max_retries {
+                return Err(TripadvisorError::RateLimited { retry_after: wait });
+            }
+            attempt += 1;
+            tokio::time::sleep(wait.unwrap_or(backoff)).await;
+            backoff *= 2;
+        };
+
+        let status = response.status();
+        Ok((status, response.text().await?))
+    }
+
+    fn record_quota(&self, response: &Response) {
+        let headers = response.headers();
+        let mut quota = self.quota.lock().unwrap_or_else(|e| e.into_inner());
+        quota.requests_made += 1;
+        if let Some(limit) = header_number(headers, "x-ratelimit-requests-limit") {
+            quota.limit = Some(limit);
+        }
+        if let Some(remaining) = header_number(headers, "x-ratelimit-requests-remaining") {
+            quota.remaining = Some(remaining);
+        }
+        if let Some(reset) = header_number(headers, "x-ratelimit-requests-reset") {
+            quota.reset_in = Some(Duration::from_secs(reset));
+        }
+    }
+}
+
+// How long the API asked us to wait: `Retry-After`, or the quota reset when no
+// requests are left.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let headers = response.headers();
+    if let Some(seconds) = header_number(headers, RETRY_AFTER.as_str()) {
+        return Some(Duration::from_secs(seconds));
+    }
+    match header_number(headers, "x-ratelimit-requests-remaining") {
+        Some(0) => header_number(headers, "x-ratelimit-requests-reset").map(Duration::from_secs),
+        _ => None,
+    }
+}
+
+fn header_number(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
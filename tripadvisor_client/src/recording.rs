@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::TripadvisorError;
+
+/// Where responses come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Calls the API.
+    Live,
+    /// Calls the API and writes every exchange to a fixture file in the directory.
+    Record(PathBuf),
+    /// Serves responses from fixture files in the directory, without any network traffic.
+    Replay(PathBuf),
+}
+
+/// A recorded request and the response it got. The API key is never written.
+#[derive(Debug, Serialize, Deserialize)]
+struct Fixture {
+    path: String,
+    query: BTreeMap<String, String>,
+    status: u16,
+    body: Value,
+}
+
+/// Query parameters as they're matched against fixtures: sorted, with empty
+/// values dropped, so the order the tools insert them in doesn't matter.
+pub(crate) fn normalise_query(url: &reqwest::Url) -> BTreeMap<String, String> {
+    url.query_pairs()
+        .filter(|(_, value)| !value.trim().is_empty())
+        .map(|(name, value)| (name.into_owned(), value.trim().to_string()))
+        .collect()
+}
+
+pub(crate) fn record(
+    dir: &Path,
+    path: &str,
+    query: BTreeMap<String, String>,
+    status: StatusCode,
+    body: &str,
+    api_key: &str,
+) -> Result<(), TripadvisorError> {
+    // the key shouldn't appear in a response, but fixtures get committed
+    let body = if api_key.is_empty() {
+        body.to_string()
+    } else {
+        body.replace(api_key, "<redacted>")
+    };
+    let file = fixture_file(dir, path, &query);
+    let fixture = Fixture {
+        path: path.to_string(),
+        query,
+        status: status.as_u16(),
+        body: serde_json::from_str(&body).unwrap_or(Value::String(body)),
+    };
+    std::fs::create_dir_all(dir).map_err(|e| TripadvisorError::Fixture(dir.to_path_buf(), e))?;
+    let json = serde_json::to_string_pretty(&fixture)?;
+    std::fs::write(&file, json).map_err(|e| TripadvisorError::Fixture(file, e))
+}
+
+pub(crate) fn replay(
+    dir: &Path,
+    path: &str,
+    query: &BTreeMap<String, String>,
+) -> Result<(StatusCode, String), TripadvisorError> {
+    let file = fixture_file(dir, path, query);
+    let json = std::fs::read_to_string(&file).map_err(|e| TripadvisorError::Fixture(file, e))?;
+    let fixture: Fixture = serde_json::from_str(&json)?;
+    let status = StatusCode::from_u16(fixture.status).unwrap_or(StatusCode::OK);
+    let body = match fixture.body {
+        Value::String(text) => text,
+        body => body.to_string(),
+    };
+    Ok((status, body))
+}
+
+// e.g. "searchHotels-3f2a9c0d1e4b5a67.json"
+fn fixture_file(dir: &Path, path: &str, query: &BTreeMap<String, String>) -> PathBuf {
+    let endpoint = path.rsplit('/').next().unwrap_or("request");
+    let mut key = path.to_string();
+    for (name, value) in query {
+        key.push_str(&format!("&{name}={value}"));
+    }
+    dir.join(format!("{endpoint}-{:016x}.json", fnv1a(key.as_bytes())))
+}
+
+// stable across Rust versions, unlike `DefaultHasher`, so fixture names don't change
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,182 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use axum::Router;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tripadvisor_client::{Mode, TripadvisorClient, TripadvisorError};
+
+async fn serve(router: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{addr}")
+}
+
+// answers 429 to the first `limited` requests, then succeeds
+async fn rate_limited_server(limited: usize) -> String {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let router = Router::new()
+        .route(
+            "/api/v1/hotels/searchHotels",
+            get(move |State(calls): State<Arc<AtomicUsize>>, headers: HeaderMap| async move {
+                assert_eq!(headers["x-rapidapi-key"], "test-key");
+                assert_eq!(headers["x-rapidapi-host"], "tripadvisor16.p.rapidapi.com");
+                let quota = [
+                    ("x-ratelimit-requests-limit", "500".to_string()),
+                    ("x-ratelimit-requests-remaining", "42".to_string()),
+                    ("x-ratelimit-requests-reset", "3600".to_string()),
+                ];
+                if calls.fetch_add(1, Ordering::SeqCst) < limited {
+                    return (StatusCode::TOO_MANY_REQUESTS, [("retry-after", "0".to_string())], "slow down")
+                        .into_response();
+                }
+                (quota, axum::Json(json!({ "status": true, "message": "Success", "data": { "hotels": 3 } })))
+                    .into_response()
+            }),
+        )
+        .with_state(calls);
+    serve(router).await
+}
+
+#[tokio::test]
+async fn retries_after_429_and_tracks_quota() {
+    let client = TripadvisorClient::new("test-key")
+        .unwrap()
+        .with_base_url(rate_limited_server(2).await);
+
+    let data: Value = client
+        .get("/api/v1/hotels/searchHotels", &[("geoId", "1")])
+        .await
+        .unwrap();
+
+    assert_eq!(data["hotels"], 3);
+    let quota = client.quota();
+    assert_eq!(quota.requests_made, 3);
+    assert_eq!(quota.limit, Some(500));
+    assert_eq!(quota.remaining, Some(42));
+    assert_eq!(quota.reset_in, Some(Duration::from_secs(3600)));
+}
+
+#[tokio::test]
+async fn gives_up_after_max_retries() {
+    let client = TripadvisorClient::new("test-key")
+        .unwrap()
+        .with_base_url(rate_limited_server(5).await)
+        .with_max_retries(1);
+
+    let result = client
+        .get::<Value, _>("/api/v1/hotels/searchHotels", &[("geoId", "1")])
+        .await;
+
+    assert!(matches!(result, Err(TripadvisorError::RateLimited { .. })));
+    assert_eq!(client.quota().requests_made, 2);
+}
+
+#[tokio::test]
+async fn exhausted_quota_is_not_retried() {
+    let router = Router::new().route(
+        "/api/v1/hotels/searchLocation",
+        get(|| async {
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [
+                    ("x-ratelimit-requests-remaining", "0"),
+                    ("x-ratelimit-requests-reset", "86400"),
+                ],
+                "quota exceeded",
+            )
+        }),
+    );
+    let client = TripadvisorClient::new("test-key")
+        .unwrap()
+        .with_base_url(serve(router).await);
+
+    let result = client
+        .get::<Value, _>("/api/v1/hotels/searchLocation", &[("query", "Paris")])
+        .await;
+
+    match result {
+        Err(TripadvisorError::QuotaExceeded { reset_in }) => {
+            assert_eq!(reset_in, Some(Duration::from_secs(86400)))
+        }
+        other => panic!("expected QuotaExceeded, got {other:?}"),
+    }
+    assert_eq!(client.quota().requests_made, 1);
+}
+
+#[tokio::test]
+async fn unsuccessful_envelope_is_an_api_error() {
+    let router = Router::new().route(
+        "/api/v1/hotels/searchLocation",
+        get(|| async {
+            axum::Json(json!({ "status": false, "message": "Invalid geoId", "data": null }))
+        }),
+    );
+    let client = TripadvisorClient::new("test-key")
+        .unwrap()
+        .with_base_url(serve(router).await);
+
+    let result = client
+        .get::<Value, _>("/api/v1/hotels/searchLocation", &[("query", "Paris")])
+        .await;
+
+    assert!(matches!(result, Err(TripadvisorError::Api(message)) if message == "Invalid geoId"));
+}
+
+#[tokio::test]
+async fn recorded_responses_replay_without_the_api() {
+    let fixtures =
+        std::env::temp_dir().join(format!("tripadvisor-fixtures-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&fixtures);
+
+    let recorder = TripadvisorClient::new("test-key")
+        .unwrap()
+        .with_base_url(rate_limited_server(0).await)
+        .with_mode(Mode::Record(fixtures.clone()));
+    let recorded: Value = recorder
+        .get(
+            "/api/v1/hotels/searchHotels",
+            &[("geoId", "1"), ("rooms", ""), ("adults", "2")],
+        )
+        .await
+        .unwrap();
+
+    let files: Vec<_> = std::fs::read_dir(&fixtures)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(files.len(), 1);
+    assert!(
+        !std::fs::read_to_string(&files[0])
+            .unwrap()
+            .contains("test-key")
+    );
+
+    // nothing listens here, and the parameters come in a different order
+    let replayer = TripadvisorClient::new("")
+        .unwrap()
+        .with_base_url("http://127.0.0.1:1")
+        .with_mode(Mode::Replay(fixtures.clone()));
+    let replayed: Value = replayer
+        .get(
+            "/api/v1/hotels/searchHotels",
+            &[("adults", "2"), ("geoId", "1")],
+        )
+        .await
+        .unwrap();
+    assert_eq!(replayed, recorded);
+    assert_eq!(replayer.quota().requests_made, 0);
+
+    let missing = replayer
+        .get::<Value, _>("/api/v1/hotels/searchHotels", &[("geoId", "2")])
+        .await;
+    assert!(matches!(missing, Err(TripadvisorError::Fixture(..))));
+
+    std::fs::remove_dir_all(&fixtures).unwrap();
+}